@@ -0,0 +1,29 @@
+use std::{path::PathBuf, process::Command};
+
+fn main() {
+  // the commit is reported on the admin `/status` endpoint
+  if let Some(hash) = git(&["rev-parse", "--short", "HEAD"]) {
+    println!("cargo:rustc-env=FFH_GIT_HASH={hash}");
+  }
+
+  // HEAD belongs to the worktree, while the branches it points to live in the common directory
+  // shared by every worktree, packed into packed-refs after `git gc`
+  if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+    println!("cargo:rerun-if-changed={git_dir}/HEAD");
+  }
+  if let Some(common_dir) = git(&["rev-parse", "--git-common-dir"]).map(PathBuf::from) {
+    println!("cargo:rerun-if-changed={}", common_dir.join("refs").display());
+    // a missing file would rerun the script on every build
+    let packed_refs = common_dir.join("packed-refs");
+    if packed_refs.exists() {
+      println!("cargo:rerun-if-changed={}", packed_refs.display());
+    }
+  }
+}
+
+/// Output of a successful git command, trimmed.
+fn git(args: &[&str]) -> Option<String> {
+  let output = Command::new("git").args(args).output().ok()?;
+  let output = String::from_utf8(output.stdout).ok().filter(|_| output.status.success())?;
+  Some(output.trim().to_owned())
+}
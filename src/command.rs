@@ -0,0 +1,10 @@
+mod admin;
+pub mod conflicts;
+pub mod diagnostics;
+pub mod inspect;
+pub mod openapi;
+pub mod profile;
+pub mod replay;
+pub mod serve;
+pub mod stats;
+pub mod verify;
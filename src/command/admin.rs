@@ -0,0 +1,107 @@
+//! Requests to the listeners of a running `ffh serve`.
+
+use super::serve::ADMIN_ADDRESS;
+use anyhow::{anyhow, bail, Context, Result};
+use awc::{
+  http::{Method, Uri},
+  Client, Connector,
+};
+use serde_json::Value;
+use std::{collections::BTreeMap, time::Duration};
+
+/// Largest response body read, font files included.
+const MAX_BODY: usize = 256 * 1024 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A response, header names lowercased.
+pub struct Response {
+  pub status: u16,
+  pub headers: BTreeMap<String, String>,
+  pub body: Vec<u8>,
+}
+
+/// Sends a bodyless request to the admin listener and returns the response body, failing on error
+/// statuses.
+pub async fn request(method: &str, path: &str) -> Result<String> {
+  let (host, port) = ADMIN_ADDRESS;
+  let response = send(&format!("http://{host}:{port}"), method, path, &BTreeMap::new()).await?;
+  let body = String::from_utf8_lossy(&response.body);
+  if !(200..300).contains(&response.status) {
+    // errors come as ErrorDTO, of which only the message is worth printing
+    let error: Option<Value> = serde_json::from_str(&body).ok();
+    let message = error.as_ref().and_then(|error| error["message"].as_str()).unwrap_or(&body);
+    bail!("{method} {path} failed with {}: {message}", response.status);
+  }
+  Ok(body.into_owned())
+}
+
+/// Sends a bodyless request with `headers` to `path` under `base`, e.g. `https://127.0.0.1:7335`,
+/// whatever the status of the response.
+pub async fn send(
+  base: &str,
+  method: &str,
+  path: &str,
+  headers: &BTreeMap<String, String>,
+) -> Result<Response> {
+  let method = Method::from_bytes(method.as_bytes()).context("Invalid method")?;
+  // traces record uncompressed bodies, so no Accept-Encoding is sent
+  let uri: Uri = format!("{base}{path}").parse().context("Invalid address")?;
+  let mut request = client(&uri)?.request(method, uri).no_decompress();
+  for (name, value) in headers {
+    request = request.insert_header((name.as_str(), value.as_str()));
+  }
+
+  let mut response = (request.send().await)
+    .map_err(|e| anyhow!("Unable to reach ffh at {base}, is `ffh serve` running? {e}"))?;
+  let body = response.body().limit(MAX_BODY).await.context("Malformed response")?;
+  let headers = (response.headers().iter())
+    .map(|(name, value)| (name.as_str().to_owned(), value.to_str().unwrap_or_default().to_owned()))
+    .collect();
+  Ok(Response { status: response.status().as_u16(), headers, body: body.to_vec() })
+}
+
+fn client(uri: &Uri) -> Result<Client> {
+  #[allow(unused_mut)]
+  let mut connector = Connector::new().timeout(TIMEOUT);
+  let https = uri.scheme_str() == Some("https");
+  cfg_if::cfg_if! {
+    if #[cfg(all(feature = "openssl", not(feature = "rustls")))] {
+      // the certificate of 7335 is issued for figmadaemon.com rather than the loopback address
+      // replayed against, so it is only left unverified there, other hosts are verified as usual
+      if https && is_loopback(uri) {
+        use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+        let mut ssl = SslConnector::builder(SslMethod::tls())?;
+        ssl.set_verify(SslVerifyMode::NONE);
+        connector = connector.openssl(ssl.build());
+      }
+    } else {
+      if https {
+        bail!("This build of ffh speaks https with the openssl feature only, use http:// instead");
+      }
+    }
+  }
+  Ok(Client::builder().connector(connector).timeout(TIMEOUT).disable_redirects().finish())
+}
+
+/// Whether `uri` points to this machine.
+#[cfg(all(feature = "openssl", not(feature = "rustls")))]
+fn is_loopback(uri: &Uri) -> bool {
+  let host = uri.host().unwrap_or_default();
+  // IPv6 addresses come in brackets
+  let host = host.trim_start_matches('[').trim_end_matches(']');
+  host.eq_ignore_ascii_case("localhost")
+    || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Percent-encodes a path segment.
+pub fn encode(segment: &str) -> String {
+  segment
+    .bytes()
+    .map(|byte| match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+        (byte as char).to_string()
+      }
+      byte => format!("%{byte:02X}"),
+    })
+    .collect()
+}
@@ -0,0 +1,25 @@
+use anyhow::Result;
+use ffh::{config::Config, dto::FontConflictDTO, ServerState};
+
+/// Prints the fonts installed more than once and which copy is handed to Figma.
+pub fn run(config: &Config, json: bool) -> Result<()> {
+  let state = ServerState::new(config)?;
+  let conflicts = state.database.conflicts();
+
+  if json {
+    let conflicts: Vec<_> = conflicts.iter().map(FontConflictDTO::from).collect();
+    println!("{}", serde_json::to_string_pretty(&conflicts)?);
+    return Ok(());
+  }
+
+  println!("{} fonts installed more than once", conflicts.len());
+  for conflict in conflicts {
+    println!("{} ({} {})", conflict.postscript, conflict.family, conflict.style);
+    println!("  + {}", conflict.winner.to_string_lossy());
+    for loser in &conflict.losers {
+      println!("  - {}", loser.to_string_lossy());
+    }
+  }
+
+  Ok(())
+}
@@ -0,0 +1,69 @@
+use anyhow::Result;
+use ffh::{config::Config, ServerState};
+
+/// Prints the fonts that were left out of the font list and why.
+pub fn run(config: &Config, json: bool) -> Result<()> {
+  let state = ServerState::new(config)?;
+  let skipped = state.database.skipped();
+  let collisions = state.database.collisions();
+  let embedding = state.database.embedding();
+  let quarantined = state.database.quarantined();
+
+  if json {
+    let report = ffh::dto::DiagnosticsDTO::from(&state.database);
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    return Ok(());
+  }
+
+  println!("{} fonts listed, {} skipped", state.database.len(), skipped.len());
+  for (label, priority, fonts) in state.database.sources() {
+    println!("  {fonts:>6} from {label} (priority {priority})");
+  }
+  for font in skipped {
+    let reason: &'static str = font.reason.into();
+    let path = font.path.as_ref().map_or("<unknown>".into(), |path| path.to_string_lossy());
+    match &font.detail {
+      Some(detail) => println!("{reason:<24} {path} ({detail})"),
+      None => println!("{reason:<24} {path}"),
+    }
+  }
+
+  if !state.database.conflicts().is_empty() {
+    println!(
+      "{} fonts installed more than once, see `ffh conflicts`",
+      state.database.conflicts().len()
+    );
+  }
+
+  if !collisions.is_empty() {
+    println!("{} PostScript names used by more than one font", collisions.len());
+  }
+  for collision in collisions {
+    println!("{}", collision.postscript);
+    for (path, postscript) in &collision.fonts {
+      println!("  {postscript:<40} {}", path.to_string_lossy());
+    }
+  }
+
+  if !quarantined.is_empty() {
+    println!("{} fonts quarantined after failing validation", quarantined.len());
+  }
+  for font in quarantined {
+    println!("{:<40} {} ({})", font.postscript, font.path.to_string_lossy(), font.reason);
+  }
+
+  if !embedding.is_empty() {
+    println!("{} fonts with embedding restrictions", embedding.len());
+  }
+  for decision in embedding {
+    let (restriction, action): (&'static str, &'static str) =
+      (decision.embedding.into(), decision.action.into());
+    println!(
+      "{restriction:<18} {action:<6} {:<40} {}",
+      decision.postscript,
+      decision.path.to_string_lossy()
+    );
+  }
+
+  Ok(())
+}
@@ -0,0 +1,52 @@
+use anyhow::{anyhow, Result};
+use ffh::{config::Config, dto::FontMetadataDTO, provider::metadata, ServerState};
+
+/// Prints the metadata of the font with the given PostScript name.
+pub fn run(config: &Config, postscript: &str, json: bool) -> Result<()> {
+  let state = ServerState::new(config)?;
+  let font = state
+    .database
+    .iter()
+    .find(|f| f.postscript == postscript)
+    .ok_or_else(|| anyhow!("Font `{postscript}` not found"))?;
+  let metadata = metadata::inspect(&std::fs::read(&font.path)?, font.index)?;
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&FontMetadataDTO::new(font, &metadata))?);
+    return Ok(());
+  }
+
+  println!("{} ({} {})", font.postscript, font.family, font.style);
+  println!("  {:<16} {} #{}", "path", font.path.to_string_lossy(), font.index);
+  let outlines: &'static str = metadata.outlines.map_or("bitmap", Into::into);
+  println!("  {:<16} {outlines}", "outlines");
+  if let Some(vendor) = &metadata.vendor {
+    println!("  {:<16} {vendor}", "vendor");
+  }
+  if let Some(panose) = metadata.panose {
+    let panose: Vec<_> = panose.iter().map(u8::to_string).collect();
+    println!("  {:<16} {}", "panose", panose.join(" "));
+  }
+
+  let metrics = &metadata.metrics;
+  println!("  {:<16} {}", "units per em", metrics.units_per_em);
+  println!("  {:<16} {}", "ascender", metrics.ascender);
+  println!("  {:<16} {}", "descender", metrics.descender);
+  println!("  {:<16} {}", "line gap", metrics.line_gap);
+  if let Some(x_height) = metrics.x_height {
+    println!("  {:<16} {x_height}", "x-height");
+  }
+  if let Some(cap_height) = metrics.cap_height {
+    println!("  {:<16} {cap_height}", "cap height");
+  }
+  println!("  {:<16} {}", "tables", metadata.tables.join(" "));
+
+  println!("names");
+  for name in &metadata.names {
+    let kind = name.kind().map_or_else(|| name.id.to_string(), str::to_owned);
+    let language = name.language.unwrap_or("?");
+    println!("  {kind:<28} {language:<6} {}", name.value.replace('\n', " "));
+  }
+
+  Ok(())
+}
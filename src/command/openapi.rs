@@ -0,0 +1,8 @@
+use anyhow::Result;
+use ffh::openapi;
+
+/// Prints the OpenAPI description of the HTTP API.
+pub fn run() -> Result<()> {
+  println!("{}", openapi::document().to_pretty_json()?);
+  Ok(())
+}
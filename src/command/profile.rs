@@ -0,0 +1,29 @@
+use super::admin;
+use anyhow::Result;
+use serde_json::Value;
+
+/// Switches the profile of the running server, or prints the profiles when no name is given.
+pub async fn run(name: Option<&str>, clear: bool, json: bool) -> Result<()> {
+  let body = match (name, clear) {
+    (_, true) => admin::request("DELETE", "/profile").await?,
+    (Some(name), false) => {
+      admin::request("PUT", &format!("/profile/{}", admin::encode(name))).await?
+    }
+    (None, false) => admin::request("GET", "/profile").await?,
+  };
+
+  let profiles: Value = serde_json::from_str(&body)?;
+  if json {
+    println!("{}", serde_json::to_string_pretty(&profiles)?);
+    return Ok(());
+  }
+
+  let active = profiles["active"].as_str();
+  println!("active profile: {}", active.unwrap_or("<none>"));
+  for profile in profiles["profiles"].as_array().into_iter().flatten() {
+    let name = profile["name"].as_str().unwrap_or_default();
+    let marker = if Some(name) == active { '*' } else { ' ' };
+    println!("{marker} {name:<32} {} fonts", profile["fonts"]);
+  }
+  Ok(())
+}
@@ -0,0 +1,120 @@
+use super::admin;
+use anyhow::{bail, Result};
+use ffh::trace::{self, TraceBody, TraceResponse, RESPONSE_HEADERS};
+use serde_json::Value;
+use std::path::Path;
+
+/// Differences listed per request at most, the rest only counted.
+const MAX_DIFFERENCES: usize = 20;
+
+/// Sends the requests of a trace to a running server again and prints how the responses differ
+/// from the recorded ones, failing when any of them does.
+pub async fn run(trace: &Path, address: &str) -> Result<()> {
+  let entries = trace::load(trace)?;
+  // a bare host:port is spoken to over plain HTTP
+  let base = if address.contains("://") {
+    address.trim_end_matches('/').to_owned()
+  } else {
+    format!("http://{address}")
+  };
+
+  let mut failed = 0;
+  for entry in &entries {
+    let response = admin::send(&base, &entry.method, &entry.uri, &entry.headers).await?;
+    let headers = (response.headers.iter())
+      .filter(|(name, _)| RESPONSE_HEADERS.contains(&name.as_str()))
+      .map(|(name, value)| (name.clone(), value.clone()))
+      .collect();
+    let content_type = response.headers.get("content-type").map(String::as_str);
+    let replayed = TraceResponse {
+      status: response.status,
+      headers,
+      body: TraceBody::new(content_type, &response.body),
+    };
+
+    let differences = diff(&entry.response, &replayed);
+    if differences.is_empty() {
+      println!("same     {} {}", entry.method, entry.uri);
+      continue;
+    }
+    failed += 1;
+    println!("differs  {} {}", entry.method, entry.uri);
+    for difference in differences.iter().take(MAX_DIFFERENCES) {
+      println!("  {difference}");
+    }
+    if differences.len() > MAX_DIFFERENCES {
+      println!("  and {} more", differences.len() - MAX_DIFFERENCES);
+    }
+  }
+
+  if failed > 0 {
+    bail!("{failed} of {} responses differ from the trace", entries.len());
+  }
+  Ok(())
+}
+
+fn diff(recorded: &TraceResponse, replayed: &TraceResponse) -> Vec<String> {
+  let mut differences = vec![];
+  if recorded.status != replayed.status {
+    differences.push(format!("status: recorded {}, got {}", recorded.status, replayed.status));
+  }
+
+  for name in RESPONSE_HEADERS {
+    let (expected, found) = (recorded.headers.get(name), replayed.headers.get(name));
+    if expected != found {
+      let expected = expected.map_or("none", String::as_str);
+      let found = found.map_or("none", String::as_str);
+      differences.push(format!("{name}: recorded {expected}, got {found}"));
+    }
+  }
+
+  match (&recorded.body, &replayed.body) {
+    (TraceBody::Json { value: expected }, TraceBody::Json { value: found }) => {
+      diff_json("body", expected, found, &mut differences);
+    }
+    (expected, found) if expected != found => {
+      differences.push(format!("body: recorded {}, got {}", describe(expected), describe(found)));
+    }
+    _ => {}
+  }
+  differences
+}
+
+fn diff_json(path: &str, expected: &Value, found: &Value, differences: &mut Vec<String>) {
+  match (expected, found) {
+    (Value::Object(expected), Value::Object(found)) => {
+      for (key, value) in expected {
+        let path = format!("{path}.{key}");
+        match found.get(key) {
+          Some(found) => diff_json(&path, value, found, differences),
+          None => differences.push(format!("{path}: recorded {value}, got nothing")),
+        }
+      }
+      for (key, value) in found.iter().filter(|(key, _)| !expected.contains_key(*key)) {
+        differences.push(format!("{path}.{key}: recorded nothing, got {value}"));
+      }
+    }
+    (Value::Array(expected), Value::Array(found)) if expected.len() == found.len() => {
+      for (index, (expected, found)) in expected.iter().zip(found).enumerate() {
+        diff_json(&format!("{path}[{index}]"), expected, found, differences);
+      }
+    }
+    (Value::Array(expected), Value::Array(found)) => {
+      let (expected, found) = (expected.len(), found.len());
+      differences.push(format!("{path}: recorded {expected} items, got {found}"));
+    }
+    (expected, found) if expected != found => {
+      differences.push(format!("{path}: recorded {expected}, got {found}"));
+    }
+    _ => {}
+  }
+}
+
+fn describe(body: &TraceBody) -> String {
+  match body {
+    TraceBody::Empty => "no body".to_owned(),
+    TraceBody::Json { value } => value.to_string(),
+    TraceBody::Text { text } => format!("{text:?}"),
+    TraceBody::Binary { sha256, length } => format!("{length} bytes with SHA-256 {sha256}"),
+  }
+}
@@ -0,0 +1,166 @@
+use actix_web::web::Data;
+use anyhow::{Context, Result};
+
+use log::LevelFilter;
+use log4rs::append::console::ConsoleAppender;
+use log4rs::append::file::FileAppender;
+use log4rs::config::{Appender, Config as LogConfig, Root};
+
+use actix_web::{web, App, HttpServer};
+
+use chrono::Local;
+use ffh::{config::Config, middleware, route, Listeners, ServerState};
+use std::{env, time::Duration};
+
+/// Loopback address of the listener Figma talks to.
+pub const FIGMA_ADDRESS: (&str, u16) = ("127.0.0.1", 44950);
+/// Loopback address of the admin listener (`/metrics`, `/diagnostics`, `/fonts`).
+pub const ADMIN_ADDRESS: (&str, u16) = ("127.0.0.1", 44951);
+
+pub async fn run(config: Config) -> Result<()> {
+  env::set_var("RUST_LOG", "actix_server=info,actix_web=info");
+
+  let log_file_path = {
+    let mut path = env::temp_dir();
+    path.push(format!("ffh_{}.log", Local::now().timestamp()));
+    path
+  };
+
+  let stdout = ConsoleAppender::builder().build();
+
+  let requests = FileAppender::builder().build(log_file_path.clone())?;
+
+  let log_config = LogConfig::builder()
+    .appender(Appender::builder().build("stdout", Box::new(stdout)))
+    .appender(Appender::builder().build("file", Box::new(requests)))
+    .build(Root::builder().appenders(vec!["stdout", "file"]).build(LevelFilter::Info))?;
+
+  let _handle = log4rs::init_config(log_config)?;
+
+  log::info!("Log path: {}", log_file_path.to_string_lossy());
+
+  let state = Data::new(ServerState::new(&config)?);
+
+  let figma_state = state.clone();
+  let mut server = HttpServer::new(move || {
+    App::new()
+      .app_data(figma_state.clone())
+      // answer with 429 the requests over the rate limits and the download cap
+      .wrap(middleware::LimitRequests)
+      // guard server to allow only requests from figma
+      .wrap(middleware::AllowFigmaOnly)
+      // answer the errors above with ErrorDTO, before the CORS headers so that Figma can read them
+      .wrap(middleware::JsonErrors)
+      .wrap(
+        middleware::DefaultHeaders::new()
+          .add(("Access-Control-Allow-Origin", "https://www.figma.com"))
+          .add(("Access-Control-Allow-Private-Network", "true")),
+      )
+      // collect request metrics, including the requests rejected above
+      .wrap(middleware::RecordMetrics)
+      // trace requests and uncompressed responses, including the requests rejected above
+      .wrap(middleware::RecordTrace)
+      .wrap(middleware::CompressExcept::new(route::UNCOMPRESSED))
+      // enable logger - always register actix-web Logger middleware last
+      .wrap(middleware::Logger::default())
+      // register the figma routes
+      .configure(route::figma)
+      // default
+      .default_service(
+        // 404 for GET request
+        web::to(route::notfound::handler),
+      )
+  })
+  .bind(FIGMA_ADDRESS)?;
+
+  // assigned by whichever TLS backend is enabled
+  #[allow(clippy::needless_late_init)]
+  let certificate_expiry;
+  cfg_if::cfg_if! {
+    if #[cfg(all(feature = "rustls", not(feature = "openssl")))] {
+      server = server.bind_rustlsi()?;
+      certificate_expiry = None;
+    } else if #[cfg(all(feature = "openssl", not(feature = "rustls")))] {
+      let (acceptor, expiry) = create_ssl_acceptor()?;
+      server = server.bind_openssl(("127.0.0.1", 7335), acceptor)?;
+      certificate_expiry = Some(expiry);
+    } else {
+      certificate_expiry = None;
+    }
+  };
+
+  // admin endpoints are served on a separate listener bound to loopback only
+  let admin_state = state.clone();
+  let admin = HttpServer::new(move || {
+    App::new()
+      .app_data(admin_state.clone())
+      .wrap(middleware::JsonErrors)
+      .wrap(middleware::Logger::default())
+      // register the admin routes
+      .configure(route::admin)
+      // default
+      .default_service(web::to(route::notfound::handler))
+  })
+  .workers(1)
+  .bind(ADMIN_ADDRESS)?;
+
+  // reported on the admin status endpoint
+  let _ = state.listeners.set(Listeners {
+    figma: (server.addrs_with_scheme().into_iter())
+      .map(|(address, scheme)| format!("{scheme}://{address}"))
+      .collect(),
+    admin: admin.addrs().into_iter().map(|address| format!("http://{address}")).collect(),
+    certificate_expiry,
+  });
+
+  // usage statistics are written periodically and once more when shutting down
+  let usage_state = state.clone();
+  let flush_interval = Duration::from_secs(config.usage.flush_interval.max(1));
+  actix_rt::spawn(async move {
+    let mut interval = actix_rt::time::interval(flush_interval);
+    loop {
+      interval.tick().await;
+      if let Err(e) = usage_state.usage.flush() {
+        log::warn!("{e}");
+      }
+    }
+  });
+
+  futures::try_join!(server.run(), admin.run())?;
+  state.usage.flush()?;
+
+  Ok(())
+}
+
+#[cfg(all(feature = "openssl", not(feature = "rustls")))]
+fn create_ssl_acceptor() -> Result<(openssl::ssl::SslAcceptorBuilder, std::time::SystemTime)> {
+  use openssl::{
+    asn1::Asn1Time,
+    pkcs12::Pkcs12,
+    ssl::{SslAcceptor, SslMethod},
+  };
+  use std::time::UNIX_EPOCH;
+
+  let pkcs12 = include_bytes!("../../assets/figma.pfx");
+  let pkcs12 = Pkcs12::from_der(pkcs12)?;
+  let password = include_str!("../../assets/figma.txt");
+
+  let identity = pkcs12.parse2(password)?;
+  let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
+
+  for ca in identity.ca.context("No CA found")?.iter() {
+    acceptor.add_client_ca(ca)?;
+  }
+
+  let cert = identity.cert.context("No cert found")?;
+  acceptor.set_certificate(&cert)?;
+
+  let pkey = identity.pkey.context("No private key found")?;
+  acceptor.set_private_key(&pkey)?;
+
+  let expiry = Asn1Time::from_unix(0)?.diff(cert.not_after())?;
+  let expiry = i64::from(expiry.days) * 86400 + i64::from(expiry.secs);
+  let expiry = UNIX_EPOCH + Duration::from_secs(expiry.try_into()?);
+
+  Ok((acceptor, expiry))
+}
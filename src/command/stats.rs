@@ -0,0 +1,54 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use ffh::{config::Config, dto::FontUsageDTO, usage::UsageReport, ServerState};
+
+/// Prints how often every font was requested, most used first and never requested fonts last.
+pub fn run(config: &Config, json: bool) -> Result<()> {
+  let path = config.usage.path().context("No data directory to read usage statistics from")?;
+  let report = UsageReport::load(&path)?;
+  let state = ServerState::new(config)?;
+
+  let time = |secs: u64| {
+    DateTime::from_timestamp(secs as i64, 0).map(|time| time.with_timezone(&Local).to_rfc3339())
+  };
+  let mut fonts: Vec<_> = (report.by_use().into_iter())
+    .map(|(postscript, usage)| FontUsageDTO {
+      postscript: postscript.clone(),
+      family: usage.family.clone(),
+      requests: usage.requests,
+      first_requested: time(usage.first_requested),
+      last_requested: time(usage.last_requested),
+    })
+    .collect();
+
+  fonts.extend(report.unused(&state.database).into_iter().map(|(postscript, family)| {
+    FontUsageDTO {
+      postscript: postscript.to_owned(),
+      family: family.to_owned(),
+      requests: 0,
+      first_requested: None,
+      last_requested: None,
+    }
+  }));
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&fonts)?);
+    return Ok(());
+  }
+
+  let used = fonts.iter().filter(|font| font.requests > 0).count();
+  println!("{used} of {} fonts requested ({})", fonts.len(), path.to_string_lossy());
+  for font in &fonts {
+    // seconds are left out of the text output
+    let day = |time: &Option<String>| time.as_deref().map_or("-", |time| &time[..16]).to_owned();
+    println!(
+      "{:>8} {:<16} {:<16} {:<40} {}",
+      font.requests,
+      day(&font.first_requested),
+      day(&font.last_requested),
+      font.postscript,
+      font.family
+    );
+  }
+  Ok(())
+}
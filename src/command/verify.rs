@@ -0,0 +1,37 @@
+use anyhow::{bail, Result};
+use ffh::{
+  config::Config,
+  dto::VerificationDTO,
+  manifest::{Manifest, VerifyStatus},
+  ServerState,
+};
+use std::path::Path;
+
+/// Checks the fonts of this machine against a manifest, failing when any of them does not match.
+pub fn run(config: &Config, manifest: &Path, json: bool) -> Result<()> {
+  let manifest = Manifest::load(manifest)?;
+  let state = ServerState::new(config)?;
+  let verifications = manifest.verify(&state.database);
+
+  if json {
+    let verifications: Vec<_> = verifications.iter().map(VerificationDTO::from).collect();
+    println!("{}", serde_json::to_string_pretty(&verifications)?);
+  } else {
+    for verification in &verifications {
+      let status: &'static str = verification.status.into();
+      let path = verification.path.as_ref().map(|path| path.to_string_lossy());
+      let postscript = &verification.postscript;
+      match (path, &verification.detail) {
+        (Some(path), Some(detail)) => println!("{status:<14} {postscript:<40} {path} ({detail})"),
+        (Some(path), None) => println!("{status:<14} {postscript:<40} {path}"),
+        (None, _) => println!("{status:<14} {postscript}"),
+      }
+    }
+  }
+
+  let failed = verifications.iter().filter(|v| v.status != VerifyStatus::Ok).count();
+  if failed > 0 {
+    bail!("{failed} of {} fonts do not match the manifest", verifications.len());
+  }
+  Ok(())
+}
@@ -0,0 +1,73 @@
+use crate::{
+  fontcache::CacheConfig,
+  manifest::ManifestConfig,
+  protocol::ProtocolConfig,
+  provider::{
+    duplicates::DuplicateConfig, embedding::EmbeddingConfig, localized::NameConfig,
+    profile::ProfileConfig, SourceConfig,
+  },
+  ratelimit::LimitConfig,
+  sanitize::SanitizeConfig,
+  trace::TraceConfig,
+  usage::UsageConfig,
+};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, io, path::Path};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigErr {
+  #[error("Unable to read the configuration file: {0}")]
+  Io(#[from] io::Error),
+
+  #[error("Invalid configuration file: {0}")]
+  Parse(#[from] toml::de::Error),
+  #[error("Invalid configuration: {0}")]
+  Invalid(&'static str),
+}
+
+type Result<T, E = ConfigErr> = std::result::Result<T, E>;
+
+/// Settings read from `ffh/config.toml` in the user's configuration directory.
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  /// Where fonts are looked for, fontconfig alone when empty.
+  pub sources: Vec<SourceConfig>,
+  /// Profile listed at startup, every font outside of profiles when unset.
+  pub profile: Option<String>,
+  pub profiles: BTreeMap<String, ProfileConfig>,
+  pub duplicates: DuplicateConfig,
+  pub names: NameConfig,
+  pub embedding: EmbeddingConfig,
+  pub sanitize: SanitizeConfig,
+  pub cache: CacheConfig,
+  pub manifest: ManifestConfig,
+  pub usage: UsageConfig,
+  pub protocol: ProtocolConfig,
+  pub trace: TraceConfig,
+  pub limits: LimitConfig,
+}
+
+impl Config {
+  /// Loads the configuration from `path`, or from the default location when there is a file.
+  pub fn load(path: Option<&Path>) -> Result<Self> {
+    let default_path = dirs::config_dir().map(|dir| dir.join("ffh").join("config.toml"));
+
+    let config: Self = match path.or(default_path.as_deref().filter(|path| path.is_file())) {
+      Some(path) => toml::from_str(&fs::read_to_string(path)?)?,
+      None => Self::default(),
+    };
+    config.validate()?;
+    Ok(config)
+  }
+
+  /// Refuses the combinations of settings that can't be honoured.
+  pub fn validate(&self) -> Result<()> {
+    if self.sanitize.clean_copy && !self.sanitize.enabled {
+      // clean copies can't be built without validating the font
+      return Err(ConfigErr::Invalid("sanitize.clean-copy requires sanitize.enabled"));
+    }
+    Ok(())
+  }
+}
@@ -1,7 +1,23 @@
+mod coverage;
+mod diagnostics;
 mod error;
+mod font;
 mod fontfiles;
+mod manifest;
+mod metadata;
+mod profile;
+mod status;
+mod usage;
 mod version;
 
+pub use coverage::CoverageDTO;
+pub use diagnostics::*;
 pub use error::ErrorDTO;
+pub use font::*;
 pub use fontfiles::*;
+pub use manifest::*;
+pub use metadata::*;
+pub use profile::*;
+pub use status::*;
+pub use usage::*;
 pub use version::VersionDTO;
@@ -0,0 +1,29 @@
+use crate::provider::{coverage, FontDescriptor};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct CoverageDTO {
+  pub postscript: String,
+  /// Number of characters the font maps.
+  pub codepoints: u32,
+  /// Unicode ranges like `U+0020-007E`.
+  pub ranges: Vec<String>,
+}
+
+impl From<&FontDescriptor> for CoverageDTO {
+  fn from(value: &FontDescriptor) -> Self {
+    let ranges = value
+      .coverage
+      .ranges()
+      .iter()
+      .map(|range| {
+        let mut formatted = String::new();
+        let _ = coverage::write_range(&mut formatted, range);
+        formatted
+      })
+      .collect();
+
+    Self { postscript: value.postscript.clone(), codepoints: value.coverage.len(), ranges }
+  }
+}
@@ -0,0 +1,155 @@
+use crate::{
+  provider::{
+    duplicates::FontConflict, embedding::EmbeddingDecision, postscript::PostscriptCollision,
+    FontDatabase, SkippedFont,
+  },
+  sanitize::QuarantinedFont,
+};
+use serde::Serialize;
+use std::path::PathBuf;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct SkippedFontDTO {
+  #[schema(value_type = Option<String>)]
+  pub path: Option<PathBuf>,
+  pub reason: &'static str,
+  pub detail: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RenamedFontDTO {
+  #[schema(value_type = String)]
+  pub path: PathBuf,
+  pub postscript: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PostscriptCollisionDTO {
+  pub postscript: String,
+  pub fonts: Vec<RenamedFontDTO>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FontConflictDTO {
+  pub postscript: String,
+  pub family: String,
+  pub style: String,
+  #[schema(value_type = String)]
+  pub winner: PathBuf,
+  #[schema(value_type = Vec<String>)]
+  pub losers: Vec<PathBuf>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EmbeddingDecisionDTO {
+  pub postscript: String,
+  #[schema(value_type = String)]
+  pub path: PathBuf,
+  /// `restricted`, `preview-and-print` or `editable`.
+  pub embedding: &'static str,
+  /// `serve`, `warn`, `list` or `hide`.
+  pub action: &'static str,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct QuarantinedFontDTO {
+  pub postscript: String,
+  #[schema(value_type = String)]
+  pub path: PathBuf,
+  pub reason: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FontSourceDTO {
+  pub label: String,
+  pub priority: i32,
+  /// Fonts listed from the source, copies that lost to another source not included.
+  pub fonts: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DiagnosticsDTO {
+  pub fonts: usize,
+  pub sources: Vec<FontSourceDTO>,
+  pub skipped: Vec<SkippedFontDTO>,
+  pub conflicts: Vec<FontConflictDTO>,
+  pub collisions: Vec<PostscriptCollisionDTO>,
+  pub embedding: Vec<EmbeddingDecisionDTO>,
+  pub quarantined: Vec<QuarantinedFontDTO>,
+}
+
+impl From<&FontDatabase> for DiagnosticsDTO {
+  fn from(database: &FontDatabase) -> Self {
+    Self {
+      fonts: database.len(),
+      sources: database.sources().into_iter().map(FontSourceDTO::from).collect(),
+      skipped: database.skipped().iter().map(SkippedFontDTO::from).collect(),
+      conflicts: database.conflicts().iter().map(FontConflictDTO::from).collect(),
+      collisions: database.collisions().iter().map(PostscriptCollisionDTO::from).collect(),
+      embedding: database.embedding().iter().map(EmbeddingDecisionDTO::from).collect(),
+      quarantined: database.quarantined().iter().map(QuarantinedFontDTO::from).collect(),
+    }
+  }
+}
+
+impl From<(&str, i32, usize)> for FontSourceDTO {
+  fn from((label, priority, fonts): (&str, i32, usize)) -> Self {
+    Self { label: label.to_owned(), priority, fonts }
+  }
+}
+
+impl From<&SkippedFont> for SkippedFontDTO {
+  fn from(value: &SkippedFont) -> Self {
+    Self { path: value.path.clone(), reason: value.reason.into(), detail: value.detail.clone() }
+  }
+}
+
+impl From<&PostscriptCollision> for PostscriptCollisionDTO {
+  fn from(value: &PostscriptCollision) -> Self {
+    Self {
+      postscript: value.postscript.clone(),
+      fonts: value
+        .fonts
+        .iter()
+        .map(|(path, postscript)| RenamedFontDTO {
+          path: path.clone(),
+          postscript: postscript.clone(),
+        })
+        .collect(),
+    }
+  }
+}
+
+impl From<&FontConflict> for FontConflictDTO {
+  fn from(value: &FontConflict) -> Self {
+    Self {
+      postscript: value.postscript.clone(),
+      family: value.family.clone(),
+      style: value.style.clone(),
+      winner: value.winner.clone(),
+      losers: value.losers.clone(),
+    }
+  }
+}
+
+impl From<&EmbeddingDecision> for EmbeddingDecisionDTO {
+  fn from(value: &EmbeddingDecision) -> Self {
+    Self {
+      postscript: value.postscript.clone(),
+      path: value.path.clone(),
+      embedding: value.embedding.into(),
+      action: value.action.into(),
+    }
+  }
+}
+
+impl From<&QuarantinedFont> for QuarantinedFontDTO {
+  fn from(value: &QuarantinedFont) -> Self {
+    Self {
+      postscript: value.postscript.clone(),
+      path: value.path.clone(),
+      reason: value.reason.clone(),
+    }
+  }
+}
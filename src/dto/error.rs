@@ -1,7 +1,12 @@
 use serde::Serialize;
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ErrorDTO {
   pub version: usize,
-  pub error: String,
+  /// Stable code of the error, such as `font_not_found`.
+  pub code: &'static str,
+  pub message: String,
+  #[serde(rename = "retryAfter", skip_serializing_if = "Option::is_none")]
+  pub retry_after: Option<u64>,
 }
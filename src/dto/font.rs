@@ -0,0 +1,55 @@
+use crate::provider::{localized::LocalizedName, FontDescriptor};
+use serde::Serialize;
+use std::{path::PathBuf, time::UNIX_EPOCH};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct LocalizedNameDTO {
+  pub language: String,
+  pub name: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FontDTO {
+  pub postscript: String,
+  #[schema(value_type = String)]
+  pub path: PathBuf,
+  pub index: u32,
+  pub family: String,
+  pub style: String,
+  pub families: Vec<LocalizedNameDTO>,
+  pub styles: Vec<LocalizedNameDTO>,
+  pub source: String,
+  pub priority: i32,
+  /// Modification time of the file in seconds since the Unix epoch, when it was scanned.
+  pub modified: Option<u64>,
+  pub size: u64,
+  pub user_installed: bool,
+}
+
+impl From<&LocalizedName> for LocalizedNameDTO {
+  fn from(value: &LocalizedName) -> Self {
+    Self { language: value.language.clone(), name: value.name.clone() }
+  }
+}
+
+impl From<&FontDescriptor> for FontDTO {
+  fn from(value: &FontDescriptor) -> Self {
+    Self {
+      postscript: value.postscript.clone(),
+      path: value.path.clone(),
+      index: value.index,
+      family: value.family.clone(),
+      style: value.style.clone(),
+      families: value.families.iter().map(LocalizedNameDTO::from).collect(),
+      styles: value.styles.iter().map(LocalizedNameDTO::from).collect(),
+      source: value.source.clone(),
+      priority: value.priority,
+      modified: (value.modified)
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_secs()),
+      size: value.size,
+      user_installed: value.user_installed,
+    }
+  }
+}
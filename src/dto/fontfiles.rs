@@ -1,7 +1,9 @@
 use serde::Serialize;
-use std::{collections::HashMap, path::PathBuf};
+use std::collections::HashMap;
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+/// A font entry of protocol version 4.
+#[derive(Serialize, ToSchema)]
 pub struct FontDescriptorDTO {
   pub postscript: String,
   pub family: String,
@@ -9,11 +11,39 @@ pub struct FontDescriptorDTO {
   pub weight: usize,
   pub stretch: usize,
   pub italic: bool,
+  /// Label of the font source the file came from.
+  pub source: String,
 }
 
-#[derive(Serialize)]
-pub struct FontFilesDTO {
+/// A font entry of protocol version 5.
+#[derive(Serialize, ToSchema)]
+pub struct FontDescriptorV5DTO {
+  #[serde(flatten)]
+  pub base: FontDescriptorDTO,
+  /// Family name in the configured languages, `family` being the English one.
+  #[serde(rename = "localizedFamily")]
+  pub localized_family: String,
+  #[serde(rename = "localizedStyle")]
+  pub localized_style: String,
+  /// Modification time of the file in seconds since the Unix epoch, changing when it is updated.
+  #[serde(rename = "modifiedAt")]
+  pub modified_at: Option<u64>,
+  #[serde(rename = "userInstalled")]
+  pub user_installed: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FontFilesDTO<D = FontDescriptorDTO> {
   pub version: usize,
+  /// Entries by font file path.
   #[serde(rename = "fontFiles")]
-  pub font_files: HashMap<PathBuf, Vec<FontDescriptorDTO>>,
+  pub font_files: HashMap<String, Vec<D>>,
+}
+
+/// A font list in the protocol version asked for.
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum AnyFontFilesDTO {
+  V4(FontFilesDTO<FontDescriptorDTO>),
+  V5(FontFilesDTO<FontDescriptorV5DTO>),
 }
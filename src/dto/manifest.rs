@@ -0,0 +1,23 @@
+use crate::manifest::Verification;
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+pub struct VerificationDTO {
+  pub postscript: String,
+  pub path: Option<PathBuf>,
+  /// `ok`, `missing`, `name_mismatch`, `outdated`, `hash_mismatch` or `unreadable`.
+  pub status: &'static str,
+  pub detail: Option<String>,
+}
+
+impl From<&Verification> for VerificationDTO {
+  fn from(value: &Verification) -> Self {
+    Self {
+      postscript: value.postscript.clone(),
+      path: value.path.clone(),
+      status: value.status.into(),
+      detail: value.detail.clone(),
+    }
+  }
+}
@@ -0,0 +1,76 @@
+use crate::provider::{
+  metadata::{FontMetadata, NameRecord, VerticalMetrics},
+  FontDescriptor,
+};
+use serde::Serialize;
+use std::path::PathBuf;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct NameRecordDTO {
+  pub id: u16,
+  /// What the name ID stands for, like `designer` or `license_url`.
+  pub kind: Option<&'static str>,
+  pub language: Option<&'static str>,
+  pub value: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VerticalMetricsDTO {
+  pub units_per_em: u16,
+  pub ascender: i16,
+  pub descender: i16,
+  pub line_gap: i16,
+  pub x_height: Option<i16>,
+  pub cap_height: Option<i16>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FontMetadataDTO {
+  pub postscript: String,
+  #[schema(value_type = String)]
+  pub path: PathBuf,
+  pub index: u32,
+  pub names: Vec<NameRecordDTO>,
+  pub vendor: Option<String>,
+  pub panose: Option<[u8; 10]>,
+  pub metrics: VerticalMetricsDTO,
+  /// `TrueType`, `CFF` or `CFF2`, missing for bitmap-only fonts.
+  pub outlines: Option<&'static str>,
+  pub tables: Vec<String>,
+}
+
+impl FontMetadataDTO {
+  pub fn new(font: &FontDescriptor, metadata: &FontMetadata) -> Self {
+    Self {
+      postscript: font.postscript.clone(),
+      path: font.path.clone(),
+      index: font.index,
+      names: metadata.names.iter().map(NameRecordDTO::from).collect(),
+      vendor: metadata.vendor.clone(),
+      panose: metadata.panose,
+      metrics: VerticalMetricsDTO::from(&metadata.metrics),
+      outlines: metadata.outlines.map(Into::into),
+      tables: metadata.tables.clone(),
+    }
+  }
+}
+
+impl From<&NameRecord> for NameRecordDTO {
+  fn from(value: &NameRecord) -> Self {
+    Self { id: value.id, kind: value.kind(), language: value.language, value: value.value.clone() }
+  }
+}
+
+impl From<&VerticalMetrics> for VerticalMetricsDTO {
+  fn from(value: &VerticalMetrics) -> Self {
+    Self {
+      units_per_em: value.units_per_em,
+      ascender: value.ascender,
+      descender: value.descender,
+      line_gap: value.line_gap,
+      x_height: value.x_height,
+      cap_height: value.cap_height,
+    }
+  }
+}
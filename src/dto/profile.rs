@@ -0,0 +1,15 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct ProfileDTO {
+  pub name: String,
+  /// Fonts listed while the profile is active.
+  pub fonts: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProfilesDTO {
+  pub active: Option<String>,
+  pub profiles: Vec<ProfileDTO>,
+}
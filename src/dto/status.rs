@@ -0,0 +1,63 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct BuildDTO {
+  /// Version of ffh.
+  pub version: String,
+  /// Commit ffh was built from, `unknown` outside of a git checkout.
+  pub git_hash: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SourceStatusDTO {
+  pub label: String,
+  /// `fontconfig`, `directory`, `project` or `archive`.
+  pub kind: &'static str,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LibraryDTO {
+  pub name: String,
+  pub version: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProviderDTO {
+  /// Protocol version of the fonts, the lowest of all sources.
+  pub api_version: usize,
+  pub sources: Vec<SourceStatusDTO>,
+  /// Native libraries the sources scan the fonts with.
+  pub libraries: Vec<LibraryDTO>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ScanDTO {
+  /// Time of the last scan, RFC 3339.
+  pub scanned_at: String,
+  /// Duration of the last scan in seconds.
+  pub duration: f64,
+  /// Fonts listed for the active profile.
+  pub fonts: usize,
+  pub families: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListenersDTO {
+  pub figma: Vec<String>,
+  pub admin: Vec<String>,
+  /// Expiry of the certificate served over https, RFC 3339.
+  pub certificate_expiry: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StatusDTO {
+  pub build: BuildDTO,
+  /// Protocol version answered to Figma when it asks for none.
+  pub protocol: usize,
+  pub provider: ProviderDTO,
+  pub scan: ScanDTO,
+  pub profile: Option<String>,
+  /// Empty until the listeners are bound.
+  pub listeners: ListenersDTO,
+}
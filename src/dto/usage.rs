@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct FontUsageDTO {
+  pub postscript: String,
+  pub family: String,
+  pub requests: u64,
+  /// RFC 3339 time, `None` for fonts never requested.
+  pub first_requested: Option<String>,
+  pub last_requested: Option<String>,
+}
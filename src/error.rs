@@ -0,0 +1,193 @@
+//! The error every route and middleware answers with, serialized to [`ErrorDTO`].
+
+use crate::{
+  dto::ErrorDTO, middleware::ForbiddenReason, protocol::ProtocolVersion, ratelimit::RateLimitErr,
+  sanitize::SanitizeErr,
+};
+use actix_web::{
+  error::{BlockingError, QueryPayloadError},
+  http::{header::RETRY_AFTER, StatusCode},
+  HttpRequest, HttpResponse, ResponseError,
+};
+use std::{fmt::Display, io};
+use strum_macros::IntoStaticStr;
+use thiserror::Error;
+
+/// Stable, machine-readable reason of an error.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ErrorCode {
+  /// The query string could not be parsed.
+  InvalidQuery,
+  /// The parameters were understood but are not acceptable.
+  InvalidRequest,
+  UnsupportedFreetype,
+  MissingOrigin,
+  InvalidOrigin,
+  ForeignOrigin,
+  /// The embedding permissions of the font do not allow serving it.
+  EmbeddingRestricted,
+  /// No route for the path.
+  NotFound,
+  FontNotFound,
+  FileNotFound,
+  UnknownProfile,
+  /// The font could not be parsed, subset or rendered.
+  UnprocessableFont,
+  RateLimited,
+  TooManyDownloads,
+  Internal,
+}
+
+impl ErrorCode {
+  pub fn status(self) -> StatusCode {
+    match self {
+      Self::InvalidQuery | Self::InvalidRequest | Self::UnsupportedFreetype => {
+        StatusCode::BAD_REQUEST
+      }
+      Self::MissingOrigin
+      | Self::InvalidOrigin
+      | Self::ForeignOrigin
+      | Self::EmbeddingRestricted => StatusCode::FORBIDDEN,
+      Self::NotFound | Self::FontNotFound | Self::FileNotFound | Self::UnknownProfile => {
+        StatusCode::NOT_FOUND
+      }
+      Self::UnprocessableFont => StatusCode::UNPROCESSABLE_ENTITY,
+      Self::RateLimited | Self::TooManyDownloads => StatusCode::TOO_MANY_REQUESTS,
+      Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+  }
+
+  /// The code of errors raised outside of ffh, such as by actix-web itself.
+  pub fn for_status(status: StatusCode) -> Self {
+    match status {
+      StatusCode::NOT_FOUND => Self::NotFound,
+      StatusCode::TOO_MANY_REQUESTS => Self::RateLimited,
+      status if status.is_client_error() => Self::InvalidRequest,
+      _ => Self::Internal,
+    }
+  }
+
+  fn message(self) -> &'static str {
+    match self {
+      Self::InvalidQuery | Self::InvalidRequest => "Invalid request",
+      Self::UnsupportedFreetype => "Unsupported FreeType version",
+      Self::MissingOrigin | Self::InvalidOrigin | Self::ForeignOrigin => {
+        "Only Figma may use the font helper"
+      }
+      Self::EmbeddingRestricted => "The font's embedding permissions do not allow serving it",
+      Self::NotFound => "Not found",
+      Self::FontNotFound => "Font not found",
+      Self::FileNotFound => "File not found",
+      Self::UnknownProfile => "Unknown profile",
+      Self::UnprocessableFont => "The font could not be processed",
+      Self::RateLimited | Self::TooManyDownloads => "Too many requests",
+      Self::Internal => "Internal error",
+    }
+  }
+}
+
+#[derive(Error, Debug)]
+#[error("{message}")]
+pub struct ApiError {
+  pub code: ErrorCode,
+  pub message: String,
+  /// Seconds until the request may succeed, sent as `Retry-After`.
+  pub retry_after: Option<u64>,
+}
+
+impl ApiError {
+  pub fn new(code: ErrorCode, message: impl Display) -> Self {
+    Self { code, message: message.to_string(), retry_after: None }
+  }
+
+  /// The error in the schema of protocol `version`.
+  pub fn response(&self, version: ProtocolVersion) -> HttpResponse {
+    let mut res = HttpResponse::build(self.code.status());
+    if let Some(retry_after) = self.retry_after {
+      res.insert_header((RETRY_AFTER, retry_after));
+    }
+    res.json(ErrorDTO {
+      version: version.into(),
+      code: self.code.into(),
+      message: self.message.clone(),
+      retry_after: self.retry_after,
+    })
+  }
+
+  /// Keeps the code of errors raised by ffh and derives one from the status of the others.
+  pub fn from_error(e: &actix_web::Error) -> Self {
+    match e.as_error::<Self>() {
+      Some(e) => Self { code: e.code, message: e.message.clone(), retry_after: e.retry_after },
+      None => Self::new(ErrorCode::for_status(e.as_response_error().status_code()), e),
+    }
+  }
+
+  /// Error handler of `web::QueryConfig`.
+  pub fn query(e: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    Self::new(ErrorCode::InvalidQuery, e).into()
+  }
+}
+
+/// Rendered with the newest protocol version outside of [`crate::middleware::JsonErrors`], which
+/// knows the negotiated one.
+impl ResponseError for ApiError {
+  fn status_code(&self) -> StatusCode {
+    self.code.status()
+  }
+
+  fn error_response(&self) -> HttpResponse {
+    self.response(ProtocolVersion::LATEST)
+  }
+}
+
+impl From<ErrorCode> for ApiError {
+  fn from(code: ErrorCode) -> Self {
+    Self::new(code, code.message())
+  }
+}
+
+impl From<ForbiddenReason> for ApiError {
+  fn from(reason: ForbiddenReason) -> Self {
+    match reason {
+      ForbiddenReason::MissingOrigin => ErrorCode::MissingOrigin,
+      ForbiddenReason::InvalidOrigin => ErrorCode::InvalidOrigin,
+      ForbiddenReason::ForeignOrigin => ErrorCode::ForeignOrigin,
+    }
+    .into()
+  }
+}
+
+impl From<RateLimitErr> for ApiError {
+  fn from(e: RateLimitErr) -> Self {
+    let code = match e {
+      RateLimitErr::Rate { .. } => ErrorCode::RateLimited,
+      RateLimitErr::Downloads { .. } => ErrorCode::TooManyDownloads,
+    };
+    Self { code, retry_after: Some(e.retry_after()), message: e.to_string() }
+  }
+}
+
+impl From<io::Error> for ApiError {
+  fn from(e: io::Error) -> Self {
+    match e.kind() {
+      io::ErrorKind::NotFound => Self::new(ErrorCode::FileNotFound, e),
+      _ => Self::new(ErrorCode::Internal, e),
+    }
+  }
+}
+
+impl From<SanitizeErr> for ApiError {
+  fn from(e: SanitizeErr) -> Self {
+    match e {
+      SanitizeErr::Io(e) => e.into(),
+      e => Self::new(ErrorCode::UnprocessableFont, e),
+    }
+  }
+}
+
+impl From<BlockingError> for ApiError {
+  fn from(e: BlockingError) -> Self {
+    Self::new(ErrorCode::Internal, e)
+  }
+}
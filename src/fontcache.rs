@@ -0,0 +1,197 @@
+//! Least recently used font bytes kept in memory, so hot fonts are not read from disk again.
+
+use actix_web::web::Bytes;
+use flate2::{write::GzEncoder, Compression};
+use serde::Deserialize;
+use std::{
+  borrow::Borrow,
+  collections::HashMap,
+  fs::Metadata,
+  hash::Hash,
+  io::{self, Write},
+  path::PathBuf,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+  },
+  time::SystemTime,
+};
+
+#[derive(Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CacheConfig {
+  pub enabled: bool,
+  /// Upper bound of the cached bytes, gzip copies included.
+  pub max_bytes: u64,
+  /// Also keep a gzip copy for clients accepting it.
+  pub precompress: bool,
+}
+
+impl Default for CacheConfig {
+  fn default() -> Self {
+    Self { enabled: true, max_bytes: 128 * 1024 * 1024, precompress: false }
+  }
+}
+
+/// Identifies cached bytes: the file and whether they are its clean copy.
+pub type CacheKey = (PathBuf, bool);
+
+/// Size and mtime of a file when it was cached.
+type Version = (u64, Option<SystemTime>);
+
+/// Font bytes held in memory.
+pub struct CachedFont {
+  pub data: Bytes,
+  pub gzip: Option<Bytes>,
+}
+
+impl CachedFont {
+  fn size(&self) -> u64 {
+    (self.data.len() + self.gzip.as_ref().map_or(0, Bytes::len)) as u64
+  }
+}
+
+struct Entry<V> {
+  value: V,
+  size: u64,
+  last_used: u64,
+}
+
+struct Entries<K, V> {
+  entries: HashMap<K, Entry<V>>,
+  bytes: u64,
+  clock: u64,
+}
+
+/// Values kept up to a total size, usually in bytes, least recently used evicted first.
+pub struct Lru<K, V> {
+  max_bytes: u64,
+  entries: Mutex<Entries<K, V>>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Lru<K, V> {
+  pub fn new(max_bytes: u64) -> Self {
+    Self { max_bytes, entries: Mutex::new(Entries { entries: HashMap::new(), bytes: 0, clock: 0 }) }
+  }
+
+  /// Returns the value of `key`, marking it as recently used.
+  pub fn get<Q: Eq + Hash + ?Sized>(&self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+  {
+    let mut entries = self.entries.lock().unwrap();
+    entries.clock += 1;
+    let clock = entries.clock;
+    let entry = entries.entries.get_mut(key)?;
+    entry.last_used = clock;
+    Some(entry.value.clone())
+  }
+
+  /// Keeps `value`, taking up `size`, unless it is larger than the whole cache.
+  pub fn insert(&self, key: K, value: V, size: u64) {
+    if size > self.max_bytes {
+      return;
+    }
+
+    let mut entries = self.entries.lock().unwrap();
+    if let Some(replaced) = entries.entries.remove(&key) {
+      entries.bytes -= replaced.size;
+    }
+
+    while entries.bytes + size > self.max_bytes {
+      let Some(oldest) =
+        entries.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(key, _)| key.clone())
+      else {
+        break;
+      };
+      if let Some(evicted) = entries.entries.remove(&oldest) {
+        entries.bytes -= evicted.size;
+      }
+    }
+
+    entries.clock += 1;
+    let last_used = entries.clock;
+    entries.bytes += size;
+    entries.entries.insert(key, Entry { value, size, last_used });
+  }
+
+  /// Number of cached bytes and entries.
+  pub fn usage(&self) -> (u64, usize) {
+    let entries = self.entries.lock().unwrap();
+    (entries.bytes, entries.entries.len())
+  }
+}
+
+pub struct FontCache {
+  config: CacheConfig,
+  entries: Lru<CacheKey, (Version, Arc<CachedFont>)>,
+  hits: AtomicU64,
+  misses: AtomicU64,
+}
+
+impl FontCache {
+  pub fn new(config: &CacheConfig) -> Self {
+    Self {
+      config: config.clone(),
+      entries: Lru::new(config.max_bytes),
+      hits: AtomicU64::default(),
+      misses: AtomicU64::default(),
+    }
+  }
+
+  /// Whether a file of `size` bytes is worth caching.
+  pub fn accepts(&self, size: u64) -> bool {
+    self.config.enabled && size <= self.config.max_bytes
+  }
+
+  /// Returns the cached bytes of `key` when the file is unchanged, otherwise builds and caches them,
+  /// blocking.
+  pub fn get_or_insert_with<E: From<io::Error>>(
+    &self,
+    key: CacheKey,
+    metadata: &Metadata,
+    build: impl FnOnce() -> Result<Vec<u8>, E>,
+  ) -> Result<Arc<CachedFont>, E> {
+    let version = (metadata.len(), metadata.modified().ok());
+
+    if let Some((_, font)) = self.entries.get(&key).filter(|(cached, _)| *cached == version) {
+      self.hits.fetch_add(1, Ordering::Relaxed);
+      return Ok(font);
+    }
+    self.misses.fetch_add(1, Ordering::Relaxed);
+
+    let data = build()?;
+    let gzip = match self.config.precompress {
+      true => Some(Bytes::from(gzip(&data)?)),
+      false => None,
+    };
+    let font = Arc::new(CachedFont { data: Bytes::from(data), gzip });
+    self.insert(key, version, font.clone());
+    Ok(font)
+  }
+
+  fn insert(&self, key: CacheKey, version: Version, font: Arc<CachedFont>) {
+    if self.config.enabled {
+      self.entries.insert(key, (version, font.clone()), font.size());
+    }
+  }
+
+  pub fn hits(&self) -> u64 {
+    self.hits.load(Ordering::Relaxed)
+  }
+
+  pub fn misses(&self) -> u64 {
+    self.misses.load(Ordering::Relaxed)
+  }
+
+  /// Number of cached bytes and entries.
+  pub fn usage(&self) -> (u64, usize) {
+    self.entries.usage()
+  }
+}
+
+fn gzip(data: &[u8]) -> io::Result<Vec<u8>> {
+  let mut encoder = GzEncoder::new(Vec::with_capacity(data.len() / 2), Compression::default());
+  encoder.write_all(data)?;
+  encoder.finish()
+}
@@ -0,0 +1,307 @@
+//! Cacheable, ranged responses for font files.
+
+use crate::{
+  fontcache::{CachedFont, FontCache, Lru},
+  sanitize::{self, SanitizeErr},
+};
+use actix_files::HttpRange;
+use actix_web::{
+  body::SizedStream,
+  http::{
+    header::{self, EntityTag, HttpDate, IfNoneMatch, IfRange},
+    StatusCode,
+  },
+  web, HttpMessage, HttpRequest, HttpResponse,
+};
+use futures::{stream, Stream};
+use sha2::{Digest, Sha256};
+use std::{
+  fs::{self, File, Metadata},
+  io::{self, Read, Seek, SeekFrom},
+  path::{Path, PathBuf},
+  sync::Arc,
+  time::SystemTime,
+};
+
+/// Browsers may keep the font but have to revalidate it, which is a cheap 304 thanks to the ETag.
+pub const CACHE_CONTROL: &str = "private, no-cache";
+const CHUNK_SIZE: u64 = 64 * 1024;
+/// Number of files whose entity tags are remembered, the least recently served forgotten first.
+const CONTENT_HASHES: u64 = 4096;
+
+/// Size and mtime of a file when it was hashed.
+type Version = (u64, Option<SystemTime>);
+
+/// SHA-256 based entity tags of served files, recomputed whenever size or mtime change.
+pub struct ContentHashes {
+  hashes: Lru<PathBuf, (Version, EntityTag)>,
+}
+
+impl Default for ContentHashes {
+  fn default() -> Self {
+    Self { hashes: Lru::new(CONTENT_HASHES) }
+  }
+}
+
+impl ContentHashes {
+  /// Returns the entity tag of `path`, hashing the file on a miss, so keep it off the event loop.
+  pub fn etag(&self, path: &Path, metadata: &Metadata) -> io::Result<EntityTag> {
+    let version = (metadata.len(), metadata.modified().ok());
+
+    if let Some((hashed, etag)) = self.hashes.get(path) {
+      if hashed == version {
+        return Ok(etag);
+      }
+    }
+
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    let etag = EntityTag::new_strong(format!("{:x}", hasher.finalize()));
+
+    self.hashes.insert(path.to_owned(), (version, etag.clone()), 1);
+    Ok(etag)
+  }
+}
+
+/// Outcome of validating served files, checked again whenever size or mtime change.
+pub struct Validations {
+  results: Lru<PathBuf, (Version, Option<String>)>,
+}
+
+impl Default for Validations {
+  fn default() -> Self {
+    Self { results: Lru::new(CONTENT_HASHES) }
+  }
+}
+
+impl Validations {
+  /// Validates `path` unless it already passed or failed at this version, blocking.
+  ///
+  /// A file that can't be read isn't remembered as failing, it is read again next time.
+  pub fn check(&self, path: &Path, metadata: &Metadata) -> Result<(), SanitizeErr> {
+    let version = (metadata.len(), metadata.modified().ok());
+
+    let failure = match self.results.get(path) {
+      Some((checked, failure)) if checked == version => failure,
+      _ => {
+        let failure = match sanitize::check_file(path) {
+          Ok(()) => None,
+          Err(SanitizeErr::Io(e)) => return Err(e.into()),
+          Err(e) => {
+            log::warn!("Refusing to serve {}: {}", path.to_string_lossy(), e);
+            Some(e.to_string())
+          }
+        };
+        self.results.insert(path.to_owned(), (version, failure.clone()), 1);
+        failure
+      }
+    };
+    failure.map_or(Ok(()), |reason| Err(SanitizeErr::Failed(reason)))
+  }
+}
+
+/// Where the bytes of a response come from.
+enum Body {
+  File(PathBuf),
+  Memory(Arc<CachedFont>),
+}
+
+/// Everything needed to answer a request for a font file.
+pub struct FontFile {
+  pub metadata: Metadata,
+  pub etag: EntityTag,
+  pub content_type: &'static str,
+  body: Body,
+}
+
+impl FontFile {
+  /// Stats, hashes and sniffs the file, and loads it into `cache` when it fits, blocking.
+  pub fn open(path: &Path, hashes: &ContentHashes, cache: &FontCache) -> io::Result<Self> {
+    let metadata = path.metadata()?;
+    let etag = hashes.etag(path, &metadata)?;
+
+    if !cache.accepts(metadata.len()) {
+      let content_type = content_type(path)?;
+      return Ok(Self { metadata, etag, content_type, body: Body::File(path.to_owned()) });
+    }
+
+    let font = cache.get_or_insert_with((path.to_owned(), false), &metadata, || fs::read(path))?;
+    let content_type = sfnt_content_type(&font.data);
+    Ok(Self { metadata, etag, content_type, body: Body::Memory(font) })
+  }
+
+  /// Builds or fetches a re-serialized copy of the file, tagged after the original, blocking.
+  pub fn clean(
+    path: &Path,
+    hashes: &ContentHashes,
+    cache: &FontCache,
+  ) -> Result<Self, SanitizeErr> {
+    let metadata = path.metadata()?;
+    let source = hashes.etag(path, &metadata)?;
+    let etag = EntityTag::new_strong(format!("{}-clean", source.tag()));
+
+    let font = cache.get_or_insert_with((path.to_owned(), true), &metadata, || {
+      sanitize::clean(&fs::read(path)?)
+    })?;
+    let content_type = sfnt_content_type(&font.data);
+    Ok(Self { metadata, etag, content_type, body: Body::Memory(font) })
+  }
+
+  /// Builds the response honouring `If-None-Match`, `Range` and `If-Range`.
+  ///
+  /// Returns the response and the number of body bytes it is going to send.
+  pub fn into_response(self, req: &HttpRequest) -> (HttpResponse, u64) {
+    let last_modified = self.metadata.modified().ok().map(HttpDate::from);
+
+    // the gzip copy is a representation of its own, which can't be combined with ranges
+    let gzip = match &self.body {
+      Body::Memory(font) if !req.headers().contains_key(header::RANGE) && accepts_gzip(req) => {
+        font.gzip.clone()
+      }
+      _ => None,
+    };
+    let etag = match gzip {
+      Some(_) => EntityTag::new_strong(format!("{}-gzip", self.etag.tag())),
+      None => self.etag.clone(),
+    };
+
+    let mut res = HttpResponse::Ok();
+    if let Body::Memory(font) = &self.body {
+      if font.gzip.is_some() {
+        res.insert_header((header::VARY, "Accept-Encoding"));
+      }
+    }
+    res
+      .insert_header((header::ETAG, etag.to_string()))
+      .insert_header((header::CACHE_CONTROL, CACHE_CONTROL))
+      .insert_header((header::ACCEPT_RANGES, "bytes"))
+      .insert_header((header::CONTENT_TYPE, self.content_type));
+    if let Some(last_modified) = last_modified {
+      res.insert_header((header::LAST_MODIFIED, last_modified));
+    }
+
+    if is_not_modified(req, &etag) {
+      return (res.status(StatusCode::NOT_MODIFIED).finish(), 0);
+    }
+
+    if let Some(gzip) = gzip {
+      let length = gzip.len() as u64;
+      return (res.insert_header((header::CONTENT_ENCODING, "gzip")).body(gzip), length);
+    }
+
+    let size = match &self.body {
+      Body::File(_) => self.metadata.len(),
+      Body::Memory(font) => font.data.len() as u64,
+    };
+
+    // a range of an older version of the file must not be spliced into the new one
+    let range_applies = match req.get_header::<IfRange>() {
+      Some(IfRange::EntityTag(etag)) => etag.strong_eq(&self.etag),
+      Some(IfRange::Date(date)) => last_modified.is_some_and(|modified| modified == date),
+      None => true,
+    };
+
+    let (offset, length) = match req.headers().get(header::RANGE).filter(|_| range_applies) {
+      None => (0, size),
+      Some(range) => match range.to_str().ok().map(|range| HttpRange::parse(range, size)) {
+        Some(Ok(ranges)) if ranges.len() == 1 => {
+          let range = ranges[0];
+          res.status(StatusCode::PARTIAL_CONTENT).insert_header((
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", range.start, range.start + range.length - 1, size),
+          ));
+          (range.start, range.length)
+        }
+        // multipart/byteranges answers are not supported, the whole file is served instead
+        Some(Ok(ranges)) if ranges.len() > 1 => (0, size),
+        Some(_) => {
+          res.insert_header((header::CONTENT_RANGE, format!("bytes */{size}")));
+          return (res.status(StatusCode::RANGE_NOT_SATISFIABLE).finish(), 0);
+        }
+        None => return (res.status(StatusCode::BAD_REQUEST).finish(), 0),
+      },
+    };
+
+    match self.body {
+      Body::File(path) => {
+        (res.body(SizedStream::new(length, read_chunks(path, offset, length))), length)
+      }
+      Body::Memory(font) => {
+        let end = (offset + length) as usize;
+        (res.body(font.data.slice(offset as usize..end)), length)
+      }
+    }
+  }
+}
+
+/// Whether the client takes gzip encoded responses.
+fn accepts_gzip(req: &HttpRequest) -> bool {
+  let Some(accepted) = req.headers().get(header::ACCEPT_ENCODING).and_then(|v| v.to_str().ok())
+  else {
+    return false;
+  };
+  accepted.split(',').any(|coding| {
+    let mut parts = coding.split(';').map(str::trim);
+    parts.next() == Some("gzip") && parts.all(|param| param.replace(' ', "") != "q=0")
+  })
+}
+
+/// Whether `If-None-Match` lets the client keep the representation tagged `etag`.
+pub fn is_not_modified(req: &HttpRequest, etag: &EntityTag) -> bool {
+  match req.get_header::<IfNoneMatch>() {
+    Some(IfNoneMatch::Any) => true,
+    Some(IfNoneMatch::Items(items)) => items.iter().any(|item| item.weak_eq(etag)),
+    None => false,
+  }
+}
+
+/// Streams `length` bytes of `path` starting at `offset`, reading on the blocking thread pool.
+fn read_chunks(
+  path: PathBuf,
+  offset: u64,
+  length: u64,
+) -> impl Stream<Item = Result<web::Bytes, io::Error>> {
+  stream::try_unfold(
+    (None::<File>, path, offset, length),
+    |(file, path, offset, remaining)| async move {
+      if remaining == 0 {
+        return Ok(None);
+      }
+
+      let open_path = path.clone();
+      let (file, chunk) = web::block(move || -> io::Result<_> {
+        let mut file = match file {
+          Some(file) => file,
+          None => File::open(open_path)?,
+        };
+        file.seek(SeekFrom::Start(offset))?;
+
+        let mut chunk = vec![0; remaining.min(CHUNK_SIZE) as usize];
+        file.read_exact(&mut chunk)?;
+        Ok((file, chunk))
+      })
+      .await
+      .map_err(io::Error::other)??;
+
+      let read = chunk.len() as u64;
+      Ok(Some((web::Bytes::from(chunk), (Some(file), path, offset + read, remaining - read))))
+    },
+  )
+}
+
+/// Picks the media type from the sfnt version tag rather than the file name.
+fn content_type(path: &Path) -> io::Result<&'static str> {
+  let mut tag = [0; 4];
+  File::open(path)?.read_exact(&mut tag)?;
+  Ok(sfnt_content_type(&tag))
+}
+
+/// Media type of font data starting with `data`.
+pub fn sfnt_content_type(data: &[u8]) -> &'static str {
+  match data.get(..4) {
+    Some(b"\x00\x01\x00\x00" | b"true") => "font/ttf",
+    Some(b"OTTO") => "font/otf",
+    Some(b"ttcf") => "font/collection",
+    _ => "application/octet-stream",
+  }
+}
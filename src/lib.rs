@@ -1,7 +1,21 @@
-mod dto;
+pub mod config;
+pub mod dto;
+pub mod error;
+pub mod fontcache;
+pub mod fontresponse;
+pub mod manifest;
+pub mod metrics;
 pub mod middleware;
+pub mod openapi;
+pub mod preview;
+pub mod protocol;
 pub mod provider;
-mod query;
+pub mod query;
+pub mod ratelimit;
 pub mod route;
+pub mod sanitize;
 mod serverstate;
-pub use serverstate::ServerState;
\ No newline at end of file
+pub mod subset;
+pub mod trace;
+pub mod usage;
+pub use serverstate::{Listeners, ServerState};
@@ -0,0 +1,178 @@
+//! Fonts a project requires, committed next to the design files and checked against this machine.
+
+use crate::provider::FontDescriptor;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+  fs::{self, File},
+  io,
+  path::{Path, PathBuf},
+  slice,
+};
+use strum_macros::IntoStaticStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ManifestErr {
+  #[error("Unable to read the manifest {0}: {1}")]
+  Io(PathBuf, #[source] io::Error),
+
+  #[error("Invalid manifest {0}: {1}")]
+  Parse(PathBuf, #[source] toml::de::Error),
+
+  #[error("Invalid manifest {0}: version `{2}` of {1} is not a number")]
+  InvalidVersion(PathBuf, String, String),
+}
+
+type Result<T, E = ManifestErr> = std::result::Result<T, E>;
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ManifestConfig {
+  /// Manifest of the project, only needed for strict mode.
+  pub path: Option<PathBuf>,
+  /// List only the fonts of the manifest, and only the copies matching their entry.
+  pub strict: bool,
+}
+
+/// A font the project requires. Everything but the PostScript name is optional and only checked
+/// when given.
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ManifestFont {
+  pub postscript: String,
+  pub family: Option<String>,
+  pub style: Option<String>,
+  /// `head.fontRevision`, such as `2.037`, the minimum version accepted.
+  pub version: Option<String>,
+  /// SHA-256 of the font file, hex encoded.
+  pub sha256: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+  pub fonts: Vec<ManifestFont>,
+}
+
+/// Outcome of checking a manifest entry.
+#[derive(Copy, Clone, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum VerifyStatus {
+  Ok,
+  Missing,
+  /// Family or style differ from the manifest.
+  NameMismatch,
+  /// `head.fontRevision` is lower than the version in the manifest.
+  Outdated,
+  HashMismatch,
+  /// The font file could not be read to hash it.
+  Unreadable,
+}
+
+pub struct Verification {
+  pub postscript: String,
+  pub path: Option<PathBuf>,
+  pub status: VerifyStatus,
+  /// Expected and found values, or the error.
+  pub detail: Option<String>,
+}
+
+impl Manifest {
+  pub fn load(path: &Path) -> Result<Self> {
+    let content = fs::read_to_string(path).map_err(|e| ManifestErr::Io(path.to_owned(), e))?;
+    let manifest: Self =
+      toml::from_str(&content).map_err(|e| ManifestErr::Parse(path.to_owned(), e))?;
+
+    // a version that cannot be compared would pass every font
+    for font in &manifest.fonts {
+      if let Some(version) =
+        font.version.as_ref().filter(|version| parse_version(version).is_none())
+      {
+        let (postscript, version) = (font.postscript.clone(), version.clone());
+        return Err(ManifestErr::InvalidVersion(path.to_owned(), postscript, version));
+      }
+    }
+    Ok(manifest)
+  }
+
+  /// Whether `font` verifies [`VerifyStatus::Ok`] against its entry, which strict mode requires.
+  pub fn admits(&self, font: &FontDescriptor) -> bool {
+    (self.fonts.iter().find(|required| required.postscript == font.postscript)).is_some_and(
+      |required| verify_font(required, slice::from_ref(font)).status == VerifyStatus::Ok,
+    )
+  }
+
+  /// Checks every entry against the listed `fonts`, reading the files whose hash is required.
+  pub fn verify(&self, fonts: &[FontDescriptor]) -> Vec<Verification> {
+    self.fonts.iter().map(|required| verify_font(required, fonts)).collect()
+  }
+}
+
+fn verify_font(required: &ManifestFont, fonts: &[FontDescriptor]) -> Verification {
+  let verification = |path: Option<&Path>, status, detail: Option<String>| Verification {
+    postscript: required.postscript.clone(),
+    path: path.map(Path::to_owned),
+    status,
+    detail,
+  };
+
+  let Some(font) = fonts.iter().find(|font| font.postscript == required.postscript) else {
+    return verification(None, VerifyStatus::Missing, None);
+  };
+  let path = Some(font.path.as_path());
+
+  let names = [(&required.family, &font.family), (&required.style, &font.style)];
+  for (expected, found) in names {
+    if let Some(expected) = expected.as_ref().filter(|expected| *expected != found) {
+      let detail = format!("expected {expected}, found {found}");
+      return verification(path, VerifyStatus::NameMismatch, Some(detail));
+    }
+  }
+
+  if let Some(expected) = &required.version {
+    let found = font.revision.map(|revision| format!("{revision:.3}"));
+    let is_outdated = match (parse_version(expected), font.revision) {
+      (Some(expected), Some(found)) => thousandths(found) < thousandths(expected),
+      (Some(_), None) => true,
+      (None, _) => false,
+    };
+    if is_outdated {
+      let found = found.as_deref().unwrap_or("no version");
+      let detail = format!("expected {expected}, found {found}");
+      return verification(path, VerifyStatus::Outdated, Some(detail));
+    }
+  }
+
+  if let Some(expected) = &required.sha256 {
+    match sha256(&font.path) {
+      Ok(found) if found.eq_ignore_ascii_case(expected) => {}
+      Ok(found) => {
+        let detail = format!("expected {expected}, found {found}");
+        return verification(path, VerifyStatus::HashMismatch, Some(detail));
+      }
+      Err(e) => return verification(path, VerifyStatus::Unreadable, Some(e.to_string())),
+    }
+  }
+
+  verification(path, VerifyStatus::Ok, None)
+}
+
+/// Reads `2.037` out of `2.037` or `Version 2.037;hotconv`.
+fn parse_version(version: &str) -> Option<f32> {
+  let start = version.find(|c: char| c.is_ascii_digit())?;
+  let number = &version[start..];
+  let end = number.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(number.len());
+  number[..end].parse().ok()
+}
+
+/// Versions are compared with three decimals, `head.fontRevision` being a 16.16 fixed number.
+fn thousandths(version: f32) -> i64 {
+  (version * 1000.0).round() as i64
+}
+
+fn sha256(path: &Path) -> io::Result<String> {
+  let mut hasher = Sha256::new();
+  io::copy(&mut File::open(path)?, &mut hasher)?;
+  Ok(format!("{:x}", hasher.finalize()))
+}
@@ -0,0 +1,198 @@
+use crate::{
+  fontcache::FontCache,
+  middleware::ForbiddenReason,
+  provider::{FontDatabase, SkipReason, SkippedFont},
+};
+use std::{
+  collections::BTreeMap,
+  fmt::Write,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+  },
+  time::Duration,
+};
+
+/// Upper bounds (in seconds) of the latency histogram buckets.
+const DURATION_BUCKETS: [f64; 12] =
+  [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Default, Clone)]
+struct Histogram {
+  buckets: [u64; DURATION_BUCKETS.len()],
+  count: u64,
+  sum: f64,
+}
+
+impl Histogram {
+  fn observe(&mut self, duration: Duration) {
+    let seconds = duration.as_secs_f64();
+    for (bucket, bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+      if seconds <= bound {
+        *bucket += 1;
+      }
+    }
+    self.count += 1;
+    self.sum += seconds;
+  }
+
+  fn write(&self, out: &mut String, name: &str, labels: &str) {
+    let separator = if labels.is_empty() { "" } else { "," };
+    for (bucket, bound) in self.buckets.iter().zip(DURATION_BUCKETS) {
+      let _ = writeln!(out, "{name}_bucket{{{labels}{separator}le=\"{bound}\"}} {bucket}");
+    }
+    let _ = writeln!(out, "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {}", self.count);
+    let labels = if labels.is_empty() { String::new() } else { format!("{{{labels}}}") };
+    let _ = writeln!(out, "{name}_sum{labels} {}", self.sum);
+    let _ = writeln!(out, "{name}_count{labels} {}", self.count);
+  }
+}
+
+#[derive(Default)]
+struct RequestMetrics {
+  totals: BTreeMap<(String, u16), u64>,
+  durations: BTreeMap<String, Histogram>,
+}
+
+#[derive(Default)]
+struct ScanMetrics {
+  durations: Histogram,
+  /// Sources that could not be scanned.
+  errors: u64,
+  skipped: BTreeMap<SkipReason, u64>,
+}
+
+/// Process-wide counters exposed in the Prometheus text format on `/metrics`.
+#[derive(Default)]
+pub struct Metrics {
+  requests: Mutex<RequestMetrics>,
+  forbidden: Mutex<BTreeMap<ForbiddenReason, u64>>,
+  font_file_bytes: AtomicU64,
+  font_subset_bytes: AtomicU64,
+  scans: Mutex<ScanMetrics>,
+}
+
+impl Metrics {
+  pub fn record_request(&self, route: &str, status: u16, duration: Duration) {
+    let mut requests = self.requests.lock().unwrap();
+    *requests.totals.entry((route.to_owned(), status)).or_default() += 1;
+    requests.durations.entry(route.to_owned()).or_default().observe(duration);
+  }
+
+  pub fn record_forbidden(&self, reason: ForbiddenReason) {
+    *self.forbidden.lock().unwrap().entry(reason).or_default() += 1;
+  }
+
+  pub fn record_font_file_bytes(&self, bytes: u64) {
+    self.font_file_bytes.fetch_add(bytes, Ordering::Relaxed);
+  }
+
+  pub fn record_font_subset_bytes(&self, bytes: u64) {
+    self.font_subset_bytes.fetch_add(bytes, Ordering::Relaxed);
+  }
+
+  pub fn record_scan(&self, duration: Duration, skipped: &[SkippedFont]) {
+    let mut scans = self.scans.lock().unwrap();
+    scans.durations.observe(duration);
+    for font in skipped {
+      *scans.skipped.entry(font.reason).or_default() += 1;
+      if font.reason == SkipReason::SourceUnavailable {
+        scans.errors += 1;
+      }
+    }
+  }
+
+  /// Renders all metrics in the Prometheus text exposition format.
+  pub fn render(&self, database: &FontDatabase, font_cache: &FontCache) -> String {
+    let mut out = String::new();
+
+    {
+      let requests = self.requests.lock().unwrap();
+
+      out.push_str("# HELP ffh_http_requests_total Number of handled HTTP requests.\n");
+      out.push_str("# TYPE ffh_http_requests_total counter\n");
+      for ((route, status), total) in &requests.totals {
+        let _ = writeln!(
+          out,
+          "ffh_http_requests_total{{route=\"{}\",status=\"{status}\"}} {total}",
+          escape(route)
+        );
+      }
+
+      out.push_str("# HELP ffh_http_request_duration_seconds Time spent handling HTTP requests.\n");
+      out.push_str("# TYPE ffh_http_request_duration_seconds histogram\n");
+      for (route, histogram) in &requests.durations {
+        histogram.write(
+          &mut out,
+          "ffh_http_request_duration_seconds",
+          &format!("route=\"{}\"", escape(route)),
+        );
+      }
+    }
+
+    out.push_str("# HELP ffh_forbidden_requests_total Requests rejected by the origin guard.\n");
+    out.push_str("# TYPE ffh_forbidden_requests_total counter\n");
+    for (reason, total) in self.forbidden.lock().unwrap().iter() {
+      let reason: &'static str = reason.into();
+      let _ = writeln!(out, "ffh_forbidden_requests_total{{reason=\"{reason}\"}} {total}");
+    }
+
+    out.push_str("# HELP ffh_font_file_bytes_total Bytes served by /figma/font-file.\n");
+    out.push_str("# TYPE ffh_font_file_bytes_total counter\n");
+    let _ =
+      writeln!(out, "ffh_font_file_bytes_total {}", self.font_file_bytes.load(Ordering::Relaxed));
+
+    out.push_str("# HELP ffh_font_subset_bytes_total Bytes served by /figma/font-subset.\n");
+    out.push_str("# TYPE ffh_font_subset_bytes_total counter\n");
+    let subset_bytes = self.font_subset_bytes.load(Ordering::Relaxed);
+    let _ = writeln!(out, "ffh_font_subset_bytes_total {subset_bytes}");
+
+    out.push_str("# HELP ffh_font_cache_hits_total Font file requests served from memory.\n");
+    out.push_str("# TYPE ffh_font_cache_hits_total counter\n");
+    let _ = writeln!(out, "ffh_font_cache_hits_total {}", font_cache.hits());
+
+    out.push_str("# HELP ffh_font_cache_misses_total Font file requests read from disk.\n");
+    out.push_str("# TYPE ffh_font_cache_misses_total counter\n");
+    let _ = writeln!(out, "ffh_font_cache_misses_total {}", font_cache.misses());
+
+    let (cached_bytes, cached_files) = font_cache.usage();
+    out.push_str("# HELP ffh_font_cache_bytes Bytes held by the font cache.\n");
+    out.push_str("# TYPE ffh_font_cache_bytes gauge\n");
+    let _ = writeln!(out, "ffh_font_cache_bytes {cached_bytes}");
+
+    out.push_str("# HELP ffh_font_cache_entries Files held by the font cache.\n");
+    out.push_str("# TYPE ffh_font_cache_entries gauge\n");
+    let _ = writeln!(out, "ffh_font_cache_entries {cached_files}");
+
+    out.push_str("# HELP ffh_fonts Number of fonts in the font database.\n");
+    out.push_str("# TYPE ffh_fonts gauge\n");
+    let _ = writeln!(out, "ffh_fonts {}", database.len());
+
+    {
+      let scans = self.scans.lock().unwrap();
+
+      out.push_str(
+        "# HELP ffh_font_scan_duration_seconds Time spent scanning the platform fonts.\n",
+      );
+      out.push_str("# TYPE ffh_font_scan_duration_seconds histogram\n");
+      scans.durations.write(&mut out, "ffh_font_scan_duration_seconds", "");
+
+      out.push_str("# HELP ffh_font_source_errors_total Font sources that could not be scanned.\n");
+      out.push_str("# TYPE ffh_font_source_errors_total counter\n");
+      let _ = writeln!(out, "ffh_font_source_errors_total {}", scans.errors);
+
+      out.push_str("# HELP ffh_fonts_skipped_total Fonts left out of the font list.\n");
+      out.push_str("# TYPE ffh_fonts_skipped_total counter\n");
+      for (reason, total) in &scans.skipped {
+        let reason: &'static str = reason.into();
+        let _ = writeln!(out, "ffh_fonts_skipped_total{{reason=\"{reason}\"}} {total}");
+      }
+    }
+
+    out
+  }
+}
+
+fn escape(value: &str) -> String {
+  value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -1,5 +1,15 @@
 pub use actix_web::middleware::*;
 
 mod allowonlyfigma;
+mod compressexcept;
+mod jsonerrors;
+mod limitrequests;
+mod recordmetrics;
+mod recordtrace;
 
 pub use allowonlyfigma::*;
+pub use compressexcept::*;
+pub use jsonerrors::*;
+pub use limitrequests::*;
+pub use recordmetrics::*;
+pub use recordtrace::*;
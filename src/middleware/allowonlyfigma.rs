@@ -1,13 +1,27 @@
-use std::rc::Rc;
+use std::{future::Future, pin::Pin, rc::Rc};
 
+use crate::{error::ApiError, ServerState};
 use actix_service::{Service, Transform};
 use actix_web::{
+  body::EitherBody,
   dev::{ServiceRequest, ServiceResponse},
-  error,
   http::header::{ORIGIN, REFERER},
-  Error,
+  web, Error,
 };
-use futures::future::{err, ok, Either, Ready};
+use futures::future::{ok, Ready};
+use strum_macros::IntoStaticStr;
+
+/// Why a request was rejected by [`AllowFigmaOnly`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ForbiddenReason {
+  /// Neither `Origin` nor `Referer` header was sent.
+  MissingOrigin,
+  /// The header value is not visible ASCII.
+  InvalidOrigin,
+  /// The request came from somewhere else than Figma.
+  ForeignOrigin,
+}
 
 pub struct AllowFigmaOnly;
 
@@ -15,8 +29,9 @@ impl<S, B> Transform<S, ServiceRequest> for AllowFigmaOnly
 where
   S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
   S::Future: 'static,
+  B: 'static,
 {
-  type Response = ServiceResponse<B>;
+  type Response = ServiceResponse<EitherBody<B>>;
   type Error = Error;
   type Transform = AllowFigmaOnlyMiddleware<S>;
   type InitError = ();
@@ -34,28 +49,39 @@ impl<S, B> Service<ServiceRequest> for AllowFigmaOnlyMiddleware<S>
 where
   S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
   S::Future: 'static,
+  B: 'static,
 {
-  type Response = ServiceResponse<B>;
+  type Response = ServiceResponse<EitherBody<B>>;
   type Error = Error;
-  type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
 
   actix_web::dev::forward_ready!(service);
 
   fn call(&self, req: ServiceRequest) -> Self::Future {
     let request_host = {
       if let Some(value) = req.headers().get(ORIGIN) {
-        value.to_str().ok()
+        value.to_str().map_err(|_| ForbiddenReason::InvalidOrigin)
       } else if let Some(value) = req.headers().get(REFERER) {
-        value.to_str().ok()
+        value.to_str().map_err(|_| ForbiddenReason::InvalidOrigin)
       } else {
-        None
+        Err(ForbiddenReason::MissingOrigin)
+      }
+    };
+
+    let reason = match request_host {
+      Ok("https://www.figma.com") => {
+        let future = self.service.call(req);
+        return Box::pin(async move { future.await.map(ServiceResponse::map_into_left_body) });
       }
+      Ok(_) => ForbiddenReason::ForeignOrigin,
+      Err(reason) => reason,
     };
 
-    if request_host.is_some() && request_host.unwrap() == "https://www.figma.com" {
-      return Either::Left(self.service.call(req));
+    if let Some(state) = req.app_data::<web::Data<ServerState>>() {
+      state.metrics.record_forbidden(reason);
     }
 
-    Either::Right(err(error::ErrorForbidden("Forbidden")))
+    let res = req.error_response(ApiError::from(reason)).map_into_right_body();
+    Box::pin(async move { Ok(res) })
   }
-}
\ No newline at end of file
+}
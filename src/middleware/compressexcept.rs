@@ -0,0 +1,73 @@
+use std::{future::Future, pin::Pin, rc::Rc};
+
+use actix_service::{
+  boxed::{self, BoxService},
+  Service, ServiceExt, Transform,
+};
+use actix_web::{
+  body::{BoxBody, MessageBody},
+  dev::{ServiceRequest, ServiceResponse},
+  middleware::Compress,
+  Error,
+};
+
+/// [`Compress`] for every path but `paths`, whose handlers negotiate the encoding themselves, such
+/// as font files that come with gzip copies and answer ranges of the uncompressed bytes.
+pub struct CompressExcept {
+  paths: &'static [&'static str],
+}
+
+impl CompressExcept {
+  pub fn new(paths: &'static [&'static str]) -> Self {
+    Self { paths }
+  }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for CompressExcept
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<BoxBody>;
+  type Error = Error;
+  type Transform = CompressExceptMiddleware<S>;
+  type InitError = ();
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Transform, Self::InitError>>>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    let service = Rc::new(service);
+    let compress = Compress::default().new_transform(service.clone());
+    let paths = self.paths;
+    Box::pin(async move {
+      let compress = boxed::service(compress.await?.map(ServiceResponse::map_into_boxed_body));
+      Ok(CompressExceptMiddleware { service, compress, paths })
+    })
+  }
+}
+pub struct CompressExceptMiddleware<S> {
+  service: Rc<S>,
+  compress: BoxService<ServiceRequest, ServiceResponse<BoxBody>, Error>,
+  paths: &'static [&'static str],
+}
+
+impl<S, B> Service<ServiceRequest> for CompressExceptMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<BoxBody>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  actix_web::dev::forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    if self.paths.contains(&req.path()) {
+      let future = self.service.call(req);
+      return Box::pin(async move { future.await.map(ServiceResponse::map_into_boxed_body) });
+    }
+    self.compress.call(req)
+  }
+}
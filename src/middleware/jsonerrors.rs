@@ -0,0 +1,78 @@
+use std::{
+  future::Future,
+  pin::Pin,
+  rc::Rc,
+  task::{Context, Poll},
+};
+
+use crate::{error::ApiError, protocol::ProtocolVersion, ServerState};
+use actix_service::{Service, Transform};
+use actix_web::{
+  body::{EitherBody, MessageBody},
+  dev::{ServiceRequest, ServiceResponse},
+  error::InternalError,
+  web, Error,
+};
+use futures::future::{ok, Ready};
+
+/// Answers every error of the wrapped services with an [`crate::dto::ErrorDTO`] in the negotiated
+/// protocol version.
+pub struct JsonErrors;
+
+impl<S, B> Transform<S, ServiceRequest> for JsonErrors
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = Error;
+  type Transform = JsonErrorsMiddleware<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ok(JsonErrorsMiddleware { service: Rc::new(service) })
+  }
+}
+pub struct JsonErrorsMiddleware<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for JsonErrorsMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.service.poll_ready(cx)
+  }
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let version = (req.app_data::<web::Data<ServerState>>())
+      .map_or(ProtocolVersion::LATEST, |state| state.protocol.current());
+    let future = self.service.call(req);
+
+    Box::pin(async move {
+      match future.await {
+        // handlers and middleware answer their errors themselves, in the newest version
+        Ok(res) if res.response().error().is_some() => {
+          let (req, res) = res.into_parts();
+          let e = ApiError::from_error(res.error().unwrap());
+          Ok(ServiceResponse::new(req, e.response(version)).map_into_right_body())
+        }
+        Ok(res) => Ok(res.map_into_left_body()),
+        // rendered without reaching the middleware wrapping this one
+        Err(e) => {
+          let res = ApiError::from_error(&e).response(version);
+          Err(InternalError::from_response(e, res).into())
+        }
+      }
+    })
+  }
+}
@@ -0,0 +1,100 @@
+use std::{
+  future::Future,
+  pin::Pin,
+  rc::Rc,
+  task::{Context, Poll},
+};
+
+use crate::{error::ApiError, ratelimit::DownloadPermit, ServerState};
+use actix_service::{Service, Transform};
+use actix_web::{
+  body::{BodySize, BoxBody, MessageBody},
+  dev::{ServiceRequest, ServiceResponse},
+  web::{self, Bytes},
+  Error,
+};
+use futures::future::{ok, Ready};
+
+/// Answers with 429 the requests over the limits of [`crate::ratelimit::RateLimiter`], holding the
+/// download permit until the body is sent.
+pub struct LimitRequests;
+
+impl<S, B> Transform<S, ServiceRequest> for LimitRequests
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<BoxBody>;
+  type Error = Error;
+  type Transform = LimitRequestsMiddleware<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ok(LimitRequestsMiddleware { service: Rc::new(service) })
+  }
+}
+pub struct LimitRequestsMiddleware<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for LimitRequestsMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<BoxBody>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.service.poll_ready(cx)
+  }
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let admitted = match req.app_data::<web::Data<ServerState>>() {
+      Some(state) => state.limiter.admit(req.path()),
+      None => Ok(None),
+    };
+    let permit = match admitted {
+      Ok(permit) => permit,
+      Err(e) => {
+        let res = req.error_response(ApiError::from(e));
+        return Box::pin(async move { Ok(res) });
+      }
+    };
+    let future = self.service.call(req);
+
+    Box::pin(async move {
+      let res = future.await?;
+      Ok(match permit {
+        Some(permit) => res
+          .map_body(|_, body| BoxBody::new(PermittedBody { body: body.boxed(), _permit: permit })),
+        None => res.map_into_boxed_body(),
+      })
+    })
+  }
+}
+
+/// A body keeping its download permit until it is sent or dropped.
+struct PermittedBody {
+  body: BoxBody,
+  _permit: DownloadPermit,
+}
+
+impl MessageBody for PermittedBody {
+  type Error = <BoxBody as MessageBody>::Error;
+
+  fn size(&self) -> BodySize {
+    self.body.size()
+  }
+
+  fn poll_next(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+    Pin::new(&mut self.get_mut().body).poll_next(cx)
+  }
+}
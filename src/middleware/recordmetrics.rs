@@ -0,0 +1,73 @@
+use std::{
+  future::Future,
+  pin::Pin,
+  rc::Rc,
+  task::{Context, Poll},
+  time::Instant,
+};
+
+use crate::ServerState;
+use actix_service::{Service, Transform};
+use actix_web::{
+  dev::{ServiceRequest, ServiceResponse},
+  web, Error,
+};
+use futures::future::{ok, Ready};
+
+/// Records request counts and latencies per matched route into [`crate::metrics::Metrics`].
+pub struct RecordMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for RecordMetrics
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S::Future: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Transform = RecordMetricsMiddleware<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ok(RecordMetricsMiddleware { service: Rc::new(service) })
+  }
+}
+pub struct RecordMetricsMiddleware<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RecordMetricsMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S::Future: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.service.poll_ready(cx)
+  }
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let started = Instant::now();
+    // unmatched paths are folded together to keep the label cardinality bounded
+    let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_owned());
+    let state = req.app_data::<web::Data<ServerState>>().cloned();
+    let future = self.service.call(req);
+
+    Box::pin(async move {
+      let result = future.await;
+
+      if let Some(state) = state {
+        let status = match &result {
+          Ok(res) => res.status(),
+          Err(e) => e.as_response_error().status_code(),
+        };
+        state.metrics.record_request(&route, status.as_u16(), started.elapsed());
+      }
+
+      result
+    })
+  }
+}
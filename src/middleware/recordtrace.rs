@@ -0,0 +1,95 @@
+use std::{
+  future::Future,
+  pin::Pin,
+  rc::Rc,
+  task::{Context, Poll},
+};
+
+use crate::{
+  trace::{TraceEntry, TraceResponse},
+  ServerState,
+};
+use actix_service::{Service, Transform};
+use actix_web::{
+  body::{self, BoxBody, MessageBody},
+  dev::{ServiceRequest, ServiceResponse},
+  web, Error,
+};
+use futures::future::{ok, Ready};
+
+/// Writes every request and its response to the trace file of [`crate::trace::Tracer`], when
+/// configured. Responses are buffered to hash them, so it belongs inside the compression.
+pub struct RecordTrace;
+
+impl<S, B> Transform<S, ServiceRequest> for RecordTrace
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<BoxBody>;
+  type Error = Error;
+  type Transform = RecordTraceMiddleware<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ok(RecordTraceMiddleware { service: Rc::new(service) })
+  }
+}
+pub struct RecordTraceMiddleware<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RecordTraceMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<BoxBody>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.service.poll_ready(cx)
+  }
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let state =
+      (req.app_data::<web::Data<ServerState>>().cloned()).filter(|state| state.tracer.is_some());
+    let Some(state) = state else {
+      let future = self.service.call(req);
+      return Box::pin(async move { future.await.map(ServiceResponse::map_into_boxed_body) });
+    };
+
+    let method = req.method().to_string();
+    let uri = req.uri().to_string();
+    let headers = req.headers().clone();
+    let future = self.service.call(req);
+
+    Box::pin(async move {
+      let record = |response: TraceResponse| {
+        if let Some(tracer) = &state.tracer {
+          tracer.record(&TraceEntry::new(&method, &uri, &headers, response));
+        }
+      };
+
+      match future.await {
+        Ok(res) => {
+          let (req, res) = res.into_parts();
+          let (res, body) = res.into_parts();
+          let body = body::to_bytes(body).await.map_err(Into::into)?;
+          record(TraceResponse::new(res.status().as_u16(), res.headers(), &body));
+          Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(body))))
+        }
+        Err(e) => {
+          let res = e.error_response();
+          let message = e.to_string();
+          record(TraceResponse::new(res.status().as_u16(), res.headers(), message.as_bytes()));
+          Err(e)
+        }
+      }
+    })
+  }
+}
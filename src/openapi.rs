@@ -0,0 +1,42 @@
+//! OpenAPI description of both listeners, generated from the routes, query structs and DTOs.
+
+use crate::route;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+  info(title = "Figma Font Helper", description = "Local font server for Figma on Linux"),
+  paths(
+    route::version::handler,
+    route::update::handler,
+    route::fontfiles::handler,
+    route::fontfile::handler,
+    route::fontsubset::handler,
+    route::metrics::handler,
+    route::diagnostics::handler,
+    route::fonts::handler,
+    route::font::handler,
+    route::fontcoverage::handler,
+    route::fontmetadata::handler,
+    route::preview::handler,
+    route::coverage::handler,
+    route::profile::handler,
+    route::profile::activate,
+    route::profile::deactivate,
+    route::status::handler,
+    route::openapi::handler,
+  ),
+  tags(
+    (name = "figma", description = "Served to Figma on 127.0.0.1:44950, and 7335 over TLS"),
+    (name = "admin", description = "Served on the loopback-only admin listener 127.0.0.1:44951"),
+  )
+)]
+struct ApiDoc;
+
+/// The OpenAPI document, as served on `/openapi.json`.
+pub fn document() -> utoipa::openapi::OpenApi {
+  let mut document = ApiDoc::openapi();
+  // the crate has no license to report
+  document.info.license = None;
+  document
+}
@@ -0,0 +1,313 @@
+//! Text previews rendered on the CPU.
+
+use crate::provider::sfnt::{read_u16, read_u32};
+use rustybuzz::{ttf_parser::Tag, UnicodeBuffer};
+use serde::Deserialize;
+use std::fmt::Write;
+use thiserror::Error;
+use tiny_skia::{FillRule, Paint, Path, PathBuilder, PathSegment, Pixmap, Transform};
+use utoipa::ToSchema;
+
+/// Largest font size in pixels.
+const MAX_SIZE: f32 = 512.0;
+/// Largest width or height of a preview in pixels.
+const MAX_DIMENSION: u32 = 4096;
+const MAX_TEXT_LENGTH: usize = 1024;
+const MAX_SIZES: usize = 16;
+const FVAR: Tag = Tag::from_bytes(b"fvar");
+
+#[derive(Error, Debug)]
+pub enum PreviewErr {
+  #[error("Malformed font")]
+  Malformed,
+
+  #[error("Invalid colour `{0}`, expected RRGGBB or RRGGBBAA")]
+  InvalidColor(String),
+
+  #[error("Invalid size `{0}`")]
+  InvalidSize(String),
+
+  #[error("Invalid axis value `{0}`, expected tag:value")]
+  InvalidAxis(String),
+
+  #[error("The font has no `{0}` axis")]
+  UnknownAxis(String),
+
+  #[error("The preview would be larger than {MAX_DIMENSION}x{MAX_DIMENSION} pixels")]
+  TooLarge,
+
+  #[error("Failed to encode the preview: {0}")]
+  Encoding(String),
+}
+
+type Result<T, E = PreviewErr> = std::result::Result<T, E>;
+
+#[derive(Deserialize, Clone, Copy, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewFormat {
+  #[default]
+  Png,
+  Svg,
+}
+
+impl PreviewFormat {
+  pub fn content_type(self) -> &'static str {
+    match self {
+      Self::Png => "image/png",
+      Self::Svg => "image/svg+xml",
+    }
+  }
+}
+
+#[derive(Clone, Copy)]
+pub struct Color {
+  pub red: u8,
+  pub green: u8,
+  pub blue: u8,
+  pub alpha: u8,
+}
+
+impl Color {
+  /// Parses `RRGGBB` or `RRGGBBAA`, with or without a leading `#`.
+  pub fn parse(value: &str) -> Result<Self> {
+    let invalid = || PreviewErr::InvalidColor(value.to_owned());
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    // from_str_radix alone would take a sign in front of the digits
+    if !matches!(hex.len(), 6 | 8) || !hex.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+      return Err(invalid());
+    }
+
+    let channel =
+      |i: usize| hex.get(i * 2..i * 2 + 2).and_then(|digits| u8::from_str_radix(digits, 16).ok());
+    Ok(Self {
+      red: channel(0).ok_or_else(invalid)?,
+      green: channel(1).ok_or_else(invalid)?,
+      blue: channel(2).ok_or_else(invalid)?,
+      alpha: if hex.len() == 8 { channel(3).ok_or_else(invalid)? } else { 0xFF },
+    })
+  }
+}
+
+/// What to render: every line of `text` once per size, in the given order.
+pub struct Preview {
+  pub text: String,
+  pub sizes: Vec<f32>,
+  pub color: Color,
+  /// Axis values applied on top of the named instance of the face.
+  pub axes: Vec<(Tag, f32)>,
+  pub format: PreviewFormat,
+}
+
+impl Preview {
+  /// Parses comma separated font sizes in pixels.
+  pub fn parse_sizes(value: &str) -> Result<Vec<f32>> {
+    let sizes = value
+      .split(',')
+      .map(str::trim)
+      .map(|size| {
+        size
+          .parse::<f32>()
+          .ok()
+          .filter(|size| *size > 0.0 && *size <= MAX_SIZE)
+          .ok_or_else(|| PreviewErr::InvalidSize(size.to_owned()))
+      })
+      .collect::<Result<Vec<_>>>()?;
+
+    if sizes.len() > MAX_SIZES {
+      return Err(PreviewErr::TooLarge);
+    }
+    Ok(sizes)
+  }
+
+  /// Parses comma separated axis values like `wght:700,wdth:87.5`.
+  pub fn parse_axes(value: &str) -> Result<Vec<(Tag, f32)>> {
+    value
+      .split(',')
+      .map(str::trim)
+      .filter(|axis| !axis.is_empty())
+      .map(|axis| {
+        let invalid = || PreviewErr::InvalidAxis(axis.to_owned());
+        let (tag, value) = axis.split_once(':').ok_or_else(invalid)?;
+        if tag.len() != 4 || !tag.is_ascii() {
+          return Err(invalid());
+        }
+        let value =
+          value.parse::<f32>().ok().filter(|value| value.is_finite()).ok_or_else(invalid)?;
+        Ok((Tag::from_bytes(tag.as_bytes().try_into().map_err(|_| invalid())?), value))
+      })
+      .collect()
+  }
+}
+
+/// Renders `preview` with face `index` of `data`, the upper 16 bits selecting a named instance.
+pub fn render(data: &[u8], index: u32, preview: &Preview) -> Result<Vec<u8>> {
+  let mut face = rustybuzz::Face::from_slice(data, index & 0xFFFF).ok_or(PreviewErr::Malformed)?;
+
+  let instance = match index >> 16 {
+    0 => vec![],
+    instance => instance_coordinates(&face, instance - 1).unwrap_or_default(),
+  };
+  for (tag, value) in instance {
+    face.set_variation(tag, value);
+  }
+  for &(tag, value) in &preview.axes {
+    face
+      .set_variation(tag, value)
+      .ok_or_else(|| PreviewErr::UnknownAxis(String::from_utf8_lossy(&tag.to_bytes()).into()))?;
+  }
+
+  let text: String = preview.text.chars().take(MAX_TEXT_LENGTH).collect();
+  let units_per_em = face.units_per_em() as f32;
+  let line_height = (face.ascender() - face.descender() + face.line_gap()) as f32;
+  let margin = preview.sizes.iter().copied().fold(0.0, f32::max) / 4.0;
+
+  let mut builder = PathBuilder::new();
+  let (mut width, mut baseline) = (0.0f32, margin);
+
+  for &size in &preview.sizes {
+    let scale = size / units_per_em;
+
+    for line in text.lines() {
+      let mut buffer = UnicodeBuffer::new();
+      buffer.push_str(line);
+      let glyphs = rustybuzz::shape(&face, &[], buffer);
+
+      baseline += face.ascender() as f32 * scale;
+      let mut pen = margin;
+      for (info, position) in glyphs.glyph_infos().iter().zip(glyphs.glyph_positions()) {
+        let mut outline = Outline {
+          builder: &mut builder,
+          x: pen + position.x_offset as f32 * scale,
+          y: baseline - position.y_offset as f32 * scale,
+          scale,
+        };
+        face.outline_glyph(rustybuzz::ttf_parser::GlyphId(info.glyph_id as u16), &mut outline);
+        pen += position.x_advance as f32 * scale;
+      }
+      width = width.max(pen);
+      baseline += (line_height - face.ascender() as f32) * scale;
+    }
+  }
+
+  let width = (width + margin).ceil() as u32;
+  let height = (baseline + margin).ceil() as u32;
+  if width > MAX_DIMENSION || height > MAX_DIMENSION {
+    return Err(PreviewErr::TooLarge);
+  }
+
+  let path = builder.finish();
+  match preview.format {
+    PreviewFormat::Png => png(path.as_ref(), width.max(1), height.max(1), preview.color),
+    PreviewFormat::Svg => Ok(svg(path.as_ref(), width, height, preview.color).into_bytes()),
+  }
+}
+
+fn png(path: Option<&Path>, width: u32, height: u32, color: Color) -> Result<Vec<u8>> {
+  let mut pixmap = Pixmap::new(width, height).ok_or(PreviewErr::TooLarge)?;
+
+  if let Some(path) = path {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(color.red, color.green, color.blue, color.alpha);
+    paint.anti_alias = true;
+    pixmap.fill_path(path, &paint, FillRule::Winding, Transform::identity(), None);
+  }
+
+  pixmap.encode_png().map_err(|e| PreviewErr::Encoding(e.to_string()))
+}
+
+fn svg(path: Option<&Path>, width: u32, height: u32, color: Color) -> String {
+  let mut d = String::new();
+  for segment in path.into_iter().flat_map(Path::segments) {
+    let _ = match segment {
+      PathSegment::MoveTo(p) => write!(d, "M{} {}", p.x, p.y),
+      PathSegment::LineTo(p) => write!(d, "L{} {}", p.x, p.y),
+      PathSegment::QuadTo(c, p) => write!(d, "Q{} {} {} {}", c.x, c.y, p.x, p.y),
+      PathSegment::CubicTo(c1, c2, p) => {
+        write!(d, "C{} {} {} {} {} {}", c1.x, c1.y, c2.x, c2.y, p.x, p.y)
+      }
+      PathSegment::Close => write!(d, "Z"),
+    };
+  }
+
+  format!(
+    concat!(
+      r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+      r##"<path fill="#{r:02x}{g:02x}{b:02x}" fill-opacity="{a}" d="{d}"/></svg>"##
+    ),
+    w = width,
+    h = height,
+    r = color.red,
+    g = color.green,
+    b = color.blue,
+    a = color.alpha as f32 / 255.0,
+    d = d,
+  )
+}
+
+/// Places glyph outlines, given in font units with y up, on the canvas.
+struct Outline<'a> {
+  builder: &'a mut PathBuilder,
+  x: f32,
+  y: f32,
+  scale: f32,
+}
+
+impl Outline<'_> {
+  fn point(&self, x: f32, y: f32) -> (f32, f32) {
+    (self.x + x * self.scale, self.y - y * self.scale)
+  }
+}
+
+impl rustybuzz::ttf_parser::OutlineBuilder for Outline<'_> {
+  fn move_to(&mut self, x: f32, y: f32) {
+    let (x, y) = self.point(x, y);
+    self.builder.move_to(x, y);
+  }
+
+  fn line_to(&mut self, x: f32, y: f32) {
+    let (x, y) = self.point(x, y);
+    self.builder.line_to(x, y);
+  }
+
+  fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+    let ((x1, y1), (x, y)) = (self.point(x1, y1), self.point(x, y));
+    self.builder.quad_to(x1, y1, x, y);
+  }
+
+  fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+    let ((x1, y1), (x2, y2), (x, y)) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+    self.builder.cubic_to(x1, y1, x2, y2, x, y);
+  }
+
+  fn close(&mut self) {
+    self.builder.close();
+  }
+}
+
+/// Reads the axis values of a named instance out of `fvar`.
+fn instance_coordinates(face: &rustybuzz::Face<'_>, instance: u32) -> Option<Vec<(Tag, f32)>> {
+  let fvar = face.raw_face().table(FVAR)?;
+  let field = |offset: usize| read_u16(fvar, offset);
+
+  let axes_offset = field(4)? as usize;
+  let axis_count = field(8)? as usize;
+  let axis_size = field(10)? as usize;
+  let instance_count = field(12)? as u32;
+  let instance_size = field(14)? as usize;
+
+  if instance >= instance_count {
+    return None;
+  }
+
+  let record = axes_offset + axis_count * axis_size + instance as usize * instance_size;
+  face
+    .variation_axes()
+    .into_iter()
+    .enumerate()
+    .map(|(i, axis)| {
+      // 16.16 fixed point, after the subfamily name ID and the flags
+      let value = read_u32(fvar, record + 4 + i * 4)? as i32 as f32 / 65536.0;
+      Some((axis.tag, value))
+    })
+    .collect()
+}
@@ -0,0 +1,75 @@
+//! Versions of the Figma agent protocol and which one a client is answered with.
+
+use serde::Deserialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Response schemas ffh can produce, oldest first.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ProtocolVersion {
+  /// Font entries with names, weight, stretch and italic.
+  V4 = 4,
+  /// Adds the localized family and style names, `family` and `style` being the English ones.
+  V5 = 5,
+}
+
+impl ProtocolVersion {
+  pub const OLDEST: Self = Self::V4;
+  pub const LATEST: Self = Self::V5;
+
+  /// The newest schema a client declaring `version` understands, the oldest one for older clients.
+  pub fn for_client(version: usize) -> Self {
+    match version {
+      5.. => Self::V5,
+      _ => Self::V4,
+    }
+  }
+}
+
+impl From<ProtocolVersion> for usize {
+  fn from(value: ProtocolVersion) -> Self {
+    value as usize
+  }
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ProtocolConfig {
+  /// Version advertised and answered with whatever clients declare.
+  pub version: Option<usize>,
+}
+
+/// The version negotiated with Figma, which declares the version it expects through
+/// `/figma/update` and can ask for another one per request.
+pub struct Protocol {
+  pinned: Option<ProtocolVersion>,
+  negotiated: AtomicUsize,
+}
+
+impl Protocol {
+  pub fn new(config: &ProtocolConfig) -> Self {
+    let pinned = config.version.map(ProtocolVersion::for_client);
+    let initial = pinned.unwrap_or(ProtocolVersion::OLDEST);
+    Self { pinned, negotiated: AtomicUsize::new(initial.into()) }
+  }
+
+  /// Version advertised by `/figma/version`.
+  pub fn current(&self) -> ProtocolVersion {
+    ProtocolVersion::for_client(self.negotiated.load(Ordering::Relaxed))
+  }
+
+  /// Settles on the newest version both sides understand, unless the version is pinned.
+  pub fn negotiate(&self, declared: usize) -> ProtocolVersion {
+    let version = self.pinned.unwrap_or(ProtocolVersion::for_client(declared));
+    self.negotiated.store(version.into(), Ordering::Relaxed);
+    version
+  }
+
+  /// Version of a single response, `declared` by the request or the negotiated one.
+  pub fn for_request(&self, declared: Option<usize>) -> ProtocolVersion {
+    match (self.pinned, declared) {
+      (Some(pinned), _) => pinned,
+      (None, Some(declared)) => ProtocolVersion::for_client(declared),
+      (None, None) => self.current(),
+    }
+  }
+}
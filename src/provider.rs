@@ -1,7 +1,24 @@
+mod archive;
+mod composite;
+pub mod coverage;
+mod directory;
+pub mod duplicates;
+pub mod embedding;
 mod fontdatabase;
 mod fontprovider;
+pub mod localized;
+pub mod metadata;
 mod platform;
+pub mod postscript;
+pub mod profile;
+pub mod sfnt;
 
+pub use archive::{ArchiveErr, ArchiveFontProvider};
+pub use composite::{active_sources, CompositeFontProvider, FontSource, SourceConfig, SourceKind};
+pub use directory::DirectoryFontProvider;
 pub use fontdatabase::{FontDatabase, FontDatabaseErr};
-pub use fontprovider::{FontDescriptor, FontProvider, FontWeight, FontWidth};
+pub use fontprovider::{
+  is_user_installed, stat, FontDescriptor, FontProvider, FontProviderErr, FontScan, FontWeight,
+  FontWidth, ProviderLibrary, SkipReason, SkippedFont,
+};
 pub use platform::{PlatformFontProvider, PlatformFontProviderErr};
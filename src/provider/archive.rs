@@ -0,0 +1,224 @@
+//! A font provider serving the fonts inside a zip archive, extracted to the cache directory.
+
+use crate::provider::{
+  directory::EXTENSIONS, DirectoryFontProvider, FontProvider, FontProviderErr, FontScan,
+};
+use flate2::read::DeflateDecoder;
+use sha2::{Digest, Sha256};
+use std::{
+  fs,
+  io::{self, Read},
+  path::{Component, Path, PathBuf},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ArchiveErr {
+  #[error(transparent)]
+  Io(#[from] io::Error),
+
+  #[error("Not a zip archive")]
+  NotZip,
+
+  #[error("The archive is truncated")]
+  Truncated,
+
+  #[error("Zip64 archives are not supported")]
+  Zip64,
+
+  #[error("Unsupported compression method {1} of {0}")]
+  UnsupportedCompression(String, u16),
+
+  #[error("{0} is larger than {MAX_ENTRY_SIZE} bytes")]
+  TooLarge(String),
+}
+
+type Result<T, E = ArchiveErr> = std::result::Result<T, E>;
+
+/// Largest font extracted out of an archive.
+const MAX_ENTRY_SIZE: u64 = 256 * 1024 * 1024;
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x02014b50;
+const LOCAL_FILE_HEADER: u32 = 0x04034b50;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// Lists the fonts of a zip archive. They are extracted once per archive content into a directory
+/// of their own, since fonts are handed to Figma by path.
+pub struct ArchiveFontProvider {
+  archive: PathBuf,
+  /// Holds one directory per extracted version of the archive, the current one and the one it
+  /// replaced.
+  dir: PathBuf,
+}
+
+impl ArchiveFontProvider {
+  /// Extracts `archive` below `cache_dir`.
+  pub fn new(archive: impl Into<PathBuf>, cache_dir: &Path) -> Self {
+    let archive = archive.into();
+    let dir = cache_dir.join(short_hash(archive.as_os_str().as_encoded_bytes()));
+    Self { archive, dir }
+  }
+
+  /// Extracts the archive unless its current content already is, and returns the directory.
+  fn extract(&self) -> Result<PathBuf> {
+    let data = fs::read(&self.archive)?;
+    let target = self.dir.join(short_hash(&data));
+    if target.is_dir() {
+      return Ok(target);
+    }
+
+    // a previous extraction may have been interrupted, only complete ones are renamed into place
+    let partial = target.with_extension("partial");
+    if partial.exists() {
+      fs::remove_dir_all(&partial)?;
+    }
+    for entry in entries(&data)? {
+      let Some(name) = font_path(&entry.name) else {
+        continue;
+      };
+      let path = partial.join(name);
+      if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+      }
+      fs::write(path, entry.read(&data)?)?;
+    }
+    fs::create_dir_all(&partial)?;
+    fs::rename(&partial, &target)?;
+
+    // the version being replaced may still be in use, so only the versions before it are removed,
+    // partial extractions of other processes are left alone
+    let mut previous = vec![];
+    for dir in fs::read_dir(&self.dir)? {
+      let dir = dir?;
+      let path = dir.path();
+      if path != target && path.extension().is_none() && dir.file_type()?.is_dir() {
+        previous.push((dir.metadata()?.modified()?, path));
+      }
+    }
+    previous.sort();
+    previous.pop();
+    for (_, old) in previous {
+      fs::remove_dir_all(old)?;
+    }
+    Ok(target)
+  }
+}
+
+impl FontProvider for ArchiveFontProvider {
+  fn get_api_version(&self) -> Result<usize, FontProviderErr> {
+    DirectoryFontProvider::new(&self.dir).get_api_version()
+  }
+
+  fn get_all_fonts(&self) -> Result<FontScan, FontProviderErr> {
+    let dir = self.extract().map_err(|e| FontProviderErr::Archive(self.archive.clone(), e))?;
+    DirectoryFontProvider::new(dir).get_all_fonts()
+  }
+
+  fn get_font_paths(&self) -> Result<Vec<PathBuf>, FontProviderErr> {
+    Ok(vec![self.archive.clone()])
+  }
+}
+
+/// A file in the central directory.
+struct ZipEntry {
+  name: String,
+  method: u16,
+  compressed_size: u64,
+  size: u64,
+  header_offset: usize,
+}
+
+impl ZipEntry {
+  fn read(&self, data: &[u8]) -> Result<Vec<u8>> {
+    if self.size > MAX_ENTRY_SIZE {
+      return Err(ArchiveErr::TooLarge(self.name.clone()));
+    }
+
+    let header = data.get(self.header_offset..).ok_or(ArchiveErr::Truncated)?;
+    if u32_at(header, 0)? != LOCAL_FILE_HEADER {
+      return Err(ArchiveErr::Truncated);
+    }
+    let start = 30 + u16_at(header, 26)? as usize + u16_at(header, 28)? as usize;
+    let compressed = (header.get(start..))
+      .and_then(|rest| rest.get(..self.compressed_size as usize))
+      .ok_or(ArchiveErr::Truncated)?;
+
+    let mut content = Vec::with_capacity(self.size as usize);
+    match self.method {
+      STORED => content.extend_from_slice(compressed),
+      DEFLATED => {
+        DeflateDecoder::new(compressed).take(self.size).read_to_end(&mut content)?;
+      }
+      method => return Err(ArchiveErr::UnsupportedCompression(self.name.clone(), method)),
+    }
+    Ok(content)
+  }
+}
+
+/// Reads the central directory of a zip archive.
+fn entries(data: &[u8]) -> Result<Vec<ZipEntry>> {
+  // the end of central directory record is followed by a comment of at most 64 KiB
+  let search = data.len().saturating_sub(22 + u16::MAX as usize);
+  let end = (search..data.len().saturating_sub(21))
+    .rev()
+    .find(|&offset| u32_at(data, offset).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+    .ok_or(ArchiveErr::NotZip)?;
+
+  let count = u16_at(data, end + 10)?;
+  let offset = u32_at(data, end + 16)?;
+  if count == u16::MAX || offset == u32::MAX {
+    return Err(ArchiveErr::Zip64);
+  }
+
+  let mut entries = vec![];
+  let mut offset = offset as usize;
+  for _ in 0..count {
+    if u32_at(data, offset)? != CENTRAL_DIRECTORY_HEADER {
+      return Err(ArchiveErr::Truncated);
+    }
+    let name_length = u16_at(data, offset + 28)? as usize;
+    let name = data.get(offset + 46..offset + 46 + name_length).ok_or(ArchiveErr::Truncated)?;
+    entries.push(ZipEntry {
+      name: String::from_utf8_lossy(name).into_owned(),
+      method: u16_at(data, offset + 10)?,
+      compressed_size: u32_at(data, offset + 20)? as u64,
+      size: u32_at(data, offset + 24)? as u64,
+      header_offset: u32_at(data, offset + 42)? as usize,
+    });
+    offset +=
+      46 + name_length + u16_at(data, offset + 30)? as usize + u16_at(data, offset + 32)? as usize;
+  }
+  Ok(entries)
+}
+
+/// Relative path a font entry is extracted to, `None` for other files and for names escaping the
+/// extraction directory.
+fn font_path(name: &str) -> Option<PathBuf> {
+  let path = Path::new(name);
+  let is_font = (path.extension().and_then(|extension| extension.to_str()))
+    .is_some_and(|extension| EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()));
+  let is_relative = path.components().all(|component| matches!(component, Component::Normal(_)));
+  // macOS archivers add resource forks next to every file
+  let is_resource_fork = path.starts_with("__MACOSX");
+
+  (is_font && is_relative && !is_resource_fork).then(|| path.to_owned())
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16> {
+  let bytes = data.get(offset..offset + 2).ok_or(ArchiveErr::Truncated)?;
+  Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32> {
+  let bytes = data.get(offset..offset + 4).ok_or(ArchiveErr::Truncated)?;
+  Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn short_hash(data: &[u8]) -> String {
+  let mut hash = format!("{:x}", Sha256::digest(data));
+  hash.truncate(16);
+  hash
+}
@@ -0,0 +1,160 @@
+//! Several font sources merged into a single font list.
+
+use crate::provider::{
+  ArchiveFontProvider, DirectoryFontProvider, FontProvider, FontProviderErr, FontScan,
+  PlatformFontProvider, ProviderLibrary, SkipReason, SkippedFont,
+};
+use serde::Deserialize;
+use std::{borrow::Cow, env, path::PathBuf};
+use strum_macros::IntoStaticStr;
+
+/// Where the fonts of a source come from.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum SourceKind {
+  /// The fonts installed on the system, as known to fontconfig.
+  Fontconfig,
+  /// Every font below a directory.
+  Directory,
+  /// The fonts of a design project, winning over installed copies by default.
+  Project,
+  /// The fonts inside a zip archive.
+  Archive,
+}
+
+impl SourceKind {
+  fn default_priority(self) -> i32 {
+    match self {
+      Self::Project => 10,
+      _ => 0,
+    }
+  }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SourceConfig {
+  pub kind: SourceKind,
+  /// Directory or archive, not used by fontconfig.
+  pub path: Option<PathBuf>,
+  /// Name reported for the fonts of the source, the kind or the file name by default.
+  pub label: Option<String>,
+  /// When a font is found in several sources, the copy from the highest priority wins.
+  pub priority: Option<i32>,
+}
+
+impl SourceConfig {
+  /// Name reported for the fonts of the source.
+  pub fn label(&self) -> String {
+    let file_name = (self.path.as_ref())
+      .and_then(|path| path.file_name())
+      .map(|name| name.to_string_lossy().into_owned());
+    let kind: &'static str = self.kind.into();
+    (self.label.clone()).or(file_name).unwrap_or_else(|| kind.to_owned())
+  }
+}
+
+/// The sources fonts are read from, fontconfig alone when there are none configured.
+pub fn active_sources(sources: &[SourceConfig]) -> Cow<'_, [SourceConfig]> {
+  if sources.is_empty() {
+    let source =
+      SourceConfig { kind: SourceKind::Fontconfig, path: None, label: None, priority: None };
+    return Cow::Owned(vec![source]);
+  }
+  Cow::Borrowed(sources)
+}
+
+/// A provider together with the label and priority given to its fonts.
+pub struct FontSource {
+  pub label: String,
+  pub priority: i32,
+  pub provider: Box<dyn FontProvider>,
+}
+
+/// Merges the fonts of several sources, tagging each font with the source it came from.
+pub struct CompositeFontProvider {
+  sources: Vec<FontSource>,
+}
+
+impl CompositeFontProvider {
+  pub fn new(sources: Vec<FontSource>) -> Self {
+    Self { sources }
+  }
+
+  /// Builds the configured sources, fontconfig alone when there are none.
+  pub fn from_config(sources: &[SourceConfig]) -> Result<Self, FontProviderErr> {
+    let cache_dir = dirs::cache_dir().unwrap_or_else(env::temp_dir).join("ffh").join("archives");
+    let mut built = vec![];
+    for source in active_sources(sources).iter() {
+      let path = || source.path.clone().ok_or(FontProviderErr::MissingPath(source.kind.into()));
+      let provider: Box<dyn FontProvider> = match source.kind {
+        SourceKind::Fontconfig => Box::new(PlatformFontProvider::new()?),
+        SourceKind::Directory | SourceKind::Project => {
+          Box::new(DirectoryFontProvider::new(path()?))
+        }
+        SourceKind::Archive => Box::new(ArchiveFontProvider::new(path()?, &cache_dir)),
+      };
+      built.push(FontSource {
+        label: source.label(),
+        priority: source.priority.unwrap_or(source.kind.default_priority()),
+        provider,
+      });
+    }
+    Ok(Self::new(built))
+  }
+}
+
+impl FontProvider for CompositeFontProvider {
+  /// The lowest version of all sources, Figma must not expect more of any font.
+  fn get_api_version(&self) -> Result<usize, FontProviderErr> {
+    let mut version = None;
+    for source in &self.sources {
+      let source_version = source.provider.get_api_version()?;
+      version = Some(version.map_or(source_version, |version: usize| version.min(source_version)));
+    }
+    Ok(version.unwrap_or_default())
+  }
+
+  fn get_libraries(&self) -> Vec<ProviderLibrary> {
+    let mut libraries: Vec<_> =
+      self.sources.iter().flat_map(|source| source.provider.get_libraries()).collect();
+    libraries.sort();
+    libraries.dedup();
+    libraries
+  }
+
+  /// Sources that fail are reported as skipped instead of failing the whole scan.
+  fn get_all_fonts(&self) -> Result<FontScan, FontProviderErr> {
+    let mut scan = FontScan::default();
+    for source in &self.sources {
+      match source.provider.get_all_fonts() {
+        Ok(mut source_scan) => {
+          for font in &mut source_scan.fonts {
+            font.source.clone_from(&source.label);
+            font.priority = source.priority;
+          }
+          scan.fonts.append(&mut source_scan.fonts);
+          scan.skipped.append(&mut source_scan.skipped);
+        }
+        Err(e) => {
+          log::warn!("Unable to scan the fonts of {}: {e}", source.label);
+          scan.skipped.push(SkippedFont {
+            path: None,
+            reason: SkipReason::SourceUnavailable,
+            detail: Some(format!("{}: {e}", source.label)),
+          });
+        }
+      }
+    }
+    Ok(scan)
+  }
+
+  fn get_font_paths(&self) -> Result<Vec<PathBuf>, FontProviderErr> {
+    let mut paths = vec![];
+    for source in &self.sources {
+      paths.extend(source.provider.get_font_paths()?);
+    }
+    Ok(paths)
+  }
+}
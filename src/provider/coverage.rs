@@ -0,0 +1,160 @@
+//! Sets of Unicode code points, stored as ranges.
+
+use std::{cmp::Ordering, fmt, ops::RangeInclusive};
+use thiserror::Error;
+use ttf_parser::Face;
+
+#[derive(Error, Debug)]
+#[error("Invalid Unicode range `{0}`")]
+pub struct InvalidRange(String);
+
+/// Sorted, non-overlapping and non-adjacent ranges of code points.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Coverage(Vec<RangeInclusive<u32>>);
+
+impl Coverage {
+  /// Collects the characters of `text` and a comma separated list of CSS-like Unicode ranges
+  /// (`U+41`, `U+4E00-9FFF`, `U+30??`).
+  pub fn parse(text: &str, ranges: &str) -> Result<Self, InvalidRange> {
+    let mut all: Vec<_> = text.chars().map(|c| c as u32..=c as u32).collect();
+    for range in ranges.split(',').map(str::trim).filter(|range| !range.is_empty()) {
+      all.push(parse_range(range).ok_or_else(|| InvalidRange(range.to_owned()))?);
+    }
+    Ok(Self::from_ranges(all))
+  }
+
+  /// Builds the set out of code points in any order.
+  pub fn from_codepoints(codepoints: impl IntoIterator<Item = u32>) -> Self {
+    Self::from_ranges(codepoints.into_iter().map(|codepoint| codepoint..=codepoint).collect())
+  }
+
+  /// Builds the set out of the pages of a fontconfig charset, each a code point of the first of
+  /// 256 characters and a bitmap of them in 32-bit words, lowest bit first.
+  pub fn from_charset_pages(pages: impl IntoIterator<Item = (u32, [u32; 8])>) -> Self {
+    let mut codepoints = vec![];
+    for (page, map) in pages {
+      for (i, bits) in map.iter().enumerate() {
+        codepoints.extend(
+          (0..32).filter(|bit| bits & (1 << bit) != 0).map(|bit| page + i as u32 * 32 + bit),
+        );
+      }
+    }
+    Self::from_codepoints(codepoints)
+  }
+
+  /// Reads the code points mapped by the Unicode subtables of the font's `cmap`.
+  pub fn from_cmap(face: &Face<'_>) -> Self {
+    let mut codepoints = vec![];
+    if let Some(cmap) = face.tables().cmap {
+      for subtable in cmap.subtables.into_iter().filter(|subtable| subtable.is_unicode()) {
+        subtable.codepoints(|codepoint| {
+          if subtable.glyph_index(codepoint).is_some_and(|glyph| glyph.0 != 0) {
+            codepoints.push(codepoint);
+          }
+        });
+      }
+    }
+    Self::from_codepoints(codepoints)
+  }
+
+  fn from_ranges(mut all: Vec<RangeInclusive<u32>>) -> Self {
+    all.sort_by_key(|range| *range.start());
+
+    let mut merged: Vec<RangeInclusive<u32>> = vec![];
+    for range in all {
+      match merged.last_mut() {
+        Some(last) if *range.start() <= last.end().saturating_add(1) => {
+          *last = *last.start()..=*range.end().max(last.end());
+        }
+        _ => merged.push(range),
+      }
+    }
+
+    Self(merged)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  pub fn ranges(&self) -> &[RangeInclusive<u32>] {
+    &self.0
+  }
+
+  /// Number of code points in the set.
+  pub fn len(&self) -> u32 {
+    self.0.iter().map(|range| range.end() - range.start() + 1).sum()
+  }
+
+  pub fn contains(&self, codepoint: u32) -> bool {
+    self
+      .0
+      .binary_search_by(|range| {
+        if *range.end() < codepoint {
+          Ordering::Less
+        } else if *range.start() > codepoint {
+          Ordering::Greater
+        } else {
+          Ordering::Equal
+        }
+      })
+      .is_ok()
+  }
+
+  /// Whether every code point of `other` is in the set.
+  pub fn covers(&self, other: &Coverage) -> bool {
+    other.0.iter().all(|range| {
+      let i = self.0.partition_point(|own| own.end() < range.start());
+      self.0.get(i).is_some_and(|own| own.start() <= range.start() && range.end() <= own.end())
+    })
+  }
+}
+
+/// Formats as comma separated Unicode ranges, the inverse of [`Coverage::parse`].
+impl fmt::Display for Coverage {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, range) in self.0.iter().enumerate() {
+      if i > 0 {
+        f.write_str(",")?;
+      }
+      write_range(f, range)?;
+    }
+    Ok(())
+  }
+}
+
+/// Formats a range as `U+XXXX` or `U+XXXX-YYYY`.
+pub fn write_range(f: &mut impl fmt::Write, range: &RangeInclusive<u32>) -> fmt::Result {
+  if range.start() == range.end() {
+    write!(f, "U+{:04X}", range.start())
+  } else {
+    write!(f, "U+{:04X}-{:04X}", range.start(), range.end())
+  }
+}
+
+/// Parses `U+XXXX`, `U+XXXX-YYYY` or a `U+XX??` wildcard range.
+fn parse_range(range: &str) -> Option<RangeInclusive<u32>> {
+  let range = range.strip_prefix("U+").or_else(|| range.strip_prefix("u+"))?;
+  let hex = |digits: &str| {
+    (!digits.is_empty() && digits.len() <= 6)
+      .then(|| u32::from_str_radix(digits, 16).ok())
+      .flatten()
+  };
+
+  let (start, end) = if let Some((start, end)) = range.split_once('-') {
+    (hex(start)?, hex(end)?)
+  } else if range.contains('?') {
+    let digits = range.trim_end_matches('?');
+    if digits.contains('?') || range.len() > 6 {
+      return None;
+    }
+    let shift = 4 * (range.len() - digits.len()) as u32;
+    let base = if digits.is_empty() { 0 } else { hex(digits)? << shift };
+    (base, base | ((1 << shift) - 1))
+  } else {
+    let codepoint = hex(range)?;
+    (codepoint, codepoint)
+  };
+
+  (start <= end && end <= char::MAX as u32).then_some(start..=end)
+}
@@ -0,0 +1,207 @@
+//! A font provider reading a directory of font files, without asking the platform.
+
+use crate::provider::{
+  coverage::Coverage,
+  embedding::Embedding,
+  is_user_installed,
+  localized::{self, LocalizedName},
+  postscript, sfnt, stat, FontDescriptor, FontProvider, FontProviderErr, FontScan, FontWeight,
+  FontWidth, SkipReason, SkippedFont,
+};
+use std::{
+  fs, io,
+  path::{Path, PathBuf},
+};
+use ttf_parser::{name_id, Face, Tag, Width};
+
+/// FreeType API version reported for plain directories, the one of current fontconfig builds.
+const API_VERSION: usize = 35;
+/// Tables read out of font files to describe their faces, the rest is left on disk.
+const FACE_TABLES: [Tag; 7] = [
+  Tag::from_bytes(b"cmap"),
+  Tag::from_bytes(b"head"),
+  Tag::from_bytes(b"hhea"),
+  Tag::from_bytes(b"maxp"),
+  Tag::from_bytes(b"name"),
+  Tag::from_bytes(b"OS/2"),
+  Tag::from_bytes(b"post"),
+];
+/// Extensions of the font files picked up, compared case-insensitively.
+pub(super) const EXTENSIONS: [&str; 4] = ["ttf", "otf", "ttc", "otc"];
+
+/// Lists the fonts found in a directory and its subdirectories.
+pub struct DirectoryFontProvider {
+  dir: PathBuf,
+}
+
+impl DirectoryFontProvider {
+  pub fn new(dir: impl Into<PathBuf>) -> Self {
+    Self { dir: dir.into() }
+  }
+
+  /// Collects the font files below `dir`, skipping the entries and subdirectories that can't be
+  /// read.
+  fn collect_files(
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+    skipped: &mut Vec<SkippedFont>,
+  ) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+      let entry = match entry {
+        Ok(entry) => entry,
+        Err(e) => {
+          skipped.push(unreadable(dir, e));
+          continue;
+        }
+      };
+      let path = entry.path();
+      // the type of the entry itself, so that links to directories can't loop
+      match entry.file_type() {
+        Ok(file_type) if file_type.is_dir() => {
+          if let Err(e) = Self::collect_files(&path, files, skipped) {
+            skipped.push(unreadable(&path, e));
+          }
+        }
+        Ok(_) if has_font_extension(&path) => files.push(path),
+        Ok(_) => {}
+        Err(e) => skipped.push(unreadable(&path, e)),
+      }
+    }
+    Ok(())
+  }
+
+  /// Describes face `index` of the file at `path` out of `data`, a font of its own holding only
+  /// the tables of [`FACE_TABLES`].
+  fn get_font(path: &Path, data: &[u8], index: u32) -> Result<FontDescriptor, SkippedFont> {
+    let skip =
+      |reason, detail: Option<String>| SkippedFont { path: Some(path.to_owned()), reason, detail };
+
+    let face =
+      Face::parse(data, 0).map_err(|e| skip(SkipReason::UnsupportedFormat, Some(e.to_string())))?;
+
+    let mut families = localized::from_name_table(&face, name_id::TYPOGRAPHIC_FAMILY);
+    families.extend(localized::from_name_table(&face, name_id::FAMILY));
+    let mut styles = localized::from_name_table(&face, name_id::TYPOGRAPHIC_SUBFAMILY);
+    styles.extend(localized::from_name_table(&face, name_id::SUBFAMILY));
+
+    let first = |names: &[LocalizedName]| names.first().map(|name| name.name.clone());
+    let family = first(&families)
+      .ok_or_else(|| skip(SkipReason::MissingProperty, Some("family".to_owned())))?;
+    let style = first(&styles).unwrap_or_else(|| "Regular".to_owned());
+
+    let postscript = postscript::read(data, 0)
+      .or_else(|| postscript::synthesize(&family, &style))
+      .ok_or_else(|| skip(SkipReason::MissingPostscriptName, None))?;
+
+    let (modified, size) = stat(path);
+    Ok(FontDescriptor {
+      path: path.to_owned(),
+      index,
+      postscript,
+      family,
+      style,
+      families,
+      styles,
+      weight: weight(face.weight().to_number()),
+      width: width(face.width()),
+      italic: face.is_italic(),
+      revision: sfnt::font_revision(data, 0),
+      coverage: Coverage::from_cmap(&face),
+      embedding: Embedding::from_face(&face),
+      source: "directory".to_owned(),
+      priority: 0,
+      modified,
+      size,
+      user_installed: is_user_installed(path),
+    })
+  }
+}
+
+impl FontProvider for DirectoryFontProvider {
+  fn get_api_version(&self) -> Result<usize, FontProviderErr> {
+    Ok(API_VERSION)
+  }
+
+  fn get_all_fonts(&self) -> Result<FontScan, FontProviderErr> {
+    let mut scan = FontScan::default();
+    let mut files = vec![];
+    Self::collect_files(&self.dir, &mut files, &mut scan.skipped)
+      .map_err(|e| FontProviderErr::Io(self.dir.clone(), e))?;
+    files.sort();
+
+    for path in files {
+      let faces = match sfnt::face_count(&path) {
+        Ok(faces) => faces,
+        Err(e) => {
+          scan.skipped.push(unreadable(&path, e));
+          continue;
+        }
+      };
+      for index in 0..faces {
+        let font = match sfnt::read_face(&path, index, &FACE_TABLES) {
+          Ok(Some(data)) => Self::get_font(&path, &data, index),
+          Ok(None) => Err(SkippedFont {
+            path: Some(path.clone()),
+            reason: SkipReason::UnsupportedFormat,
+            detail: Some("Malformed table directory".to_owned()),
+          }),
+          Err(e) => Err(unreadable(&path, e)),
+        };
+        match font {
+          Ok(font) => scan.fonts.push(font),
+          Err(skipped) => scan.skipped.push(skipped),
+        }
+      }
+    }
+
+    Ok(scan)
+  }
+
+  fn get_font_paths(&self) -> Result<Vec<PathBuf>, FontProviderErr> {
+    Ok(vec![self.dir.clone()])
+  }
+}
+
+fn has_font_extension(path: &Path) -> bool {
+  (path.extension())
+    .and_then(|extension| extension.to_str())
+    .is_some_and(|extension| EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+fn unreadable(path: &Path, e: io::Error) -> SkippedFont {
+  SkippedFont {
+    path: Some(path.to_owned()),
+    reason: SkipReason::Unreadable,
+    detail: Some(e.to_string()),
+  }
+}
+
+/// Rounds `usWeightClass` to the closest weight.
+fn weight(class: u16) -> FontWeight {
+  match class {
+    0..=149 => FontWeight::Thin,
+    150..=249 => FontWeight::ExtraLight,
+    250..=349 => FontWeight::Light,
+    350..=449 => FontWeight::Normal,
+    450..=549 => FontWeight::Medium,
+    550..=649 => FontWeight::SemiBold,
+    650..=749 => FontWeight::Bold,
+    750..=849 => FontWeight::ExtraBold,
+    850..=924 => FontWeight::Black,
+    _ => FontWeight::ExtraBlack,
+  }
+}
+
+fn width(width: Width) -> FontWidth {
+  match width {
+    Width::UltraCondensed => FontWidth::UltraCondensed,
+    Width::ExtraCondensed => FontWidth::ExtraCondensed,
+    Width::Condensed => FontWidth::Condensed,
+    Width::SemiCondensed => FontWidth::SemiCondensed,
+    Width::Normal => FontWidth::Normal,
+    Width::SemiExpanded => FontWidth::SemiExpanded,
+    Width::Expanded => FontWidth::Expanded,
+    Width::ExtraExpanded => FontWidth::ExtraExpanded,
+    Width::UltraExpanded => FontWidth::UltraExpanded,
+  }
+}
@@ -0,0 +1,97 @@
+//! Resolution of the same font being installed in several places.
+
+use crate::provider::FontDescriptor;
+use serde::Deserialize;
+use std::{cmp::Ordering, collections::BTreeMap, path::PathBuf};
+
+/// How to pick the copy that is handed to Figma when a font is installed more than once.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicatePolicy {
+  /// The copy with the highest `head.fontRevision` wins.
+  #[default]
+  Newest,
+  /// Fonts installed in the user's home directory win over system fonts.
+  UserFirst,
+  /// The copy in the directory listed first in `priority` wins.
+  Priority,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct DuplicateConfig {
+  pub policy: DuplicatePolicy,
+  /// Directories in order of preference, used by [`DuplicatePolicy::Priority`].
+  pub priority: Vec<PathBuf>,
+}
+
+/// Fonts sharing PostScript name, family and style, and which of them was kept.
+pub struct FontConflict {
+  pub postscript: String,
+  pub family: String,
+  pub style: String,
+  pub winner: PathBuf,
+  pub losers: Vec<PathBuf>,
+}
+
+/// Keeps a single copy of every font according to the configured policy.
+pub fn resolve(
+  fonts: Vec<FontDescriptor>,
+  config: &DuplicateConfig,
+) -> (Vec<FontDescriptor>, Vec<FontConflict>) {
+  let rank = |font: &FontDescriptor| {
+    config.priority.iter().position(|dir| font.path.starts_with(dir)).unwrap_or(usize::MAX)
+  };
+  let newest = |a: &FontDescriptor, b: &FontDescriptor| {
+    b.revision.partial_cmp(&a.revision).unwrap_or(Ordering::Equal)
+  };
+  let user_first = |a: &FontDescriptor, b: &FontDescriptor| b.user_installed.cmp(&a.user_installed);
+
+  let mut groups = BTreeMap::<(String, String, String), Vec<FontDescriptor>>::new();
+  for font in fonts {
+    groups
+      .entry((font.postscript.clone(), font.family.clone(), font.style.clone()))
+      .or_default()
+      .push(font);
+  }
+
+  let mut kept = vec![];
+  let mut conflicts = vec![];
+  for ((postscript, family, style), mut group) in groups {
+    // a higher priority source always wins, the policy decides between copies of the same priority
+    group.sort_by(|a, b| {
+      let preference = match config.policy {
+        DuplicatePolicy::Newest => newest(a, b).then_with(|| user_first(a, b)),
+        DuplicatePolicy::UserFirst => user_first(a, b).then_with(|| newest(a, b)),
+        DuplicatePolicy::Priority => rank(a).cmp(&rank(b)).then_with(|| newest(a, b)),
+      };
+      (b.priority.cmp(&a.priority))
+        .then(preference)
+        .then_with(|| (&a.path, a.index).cmp(&(&b.path, b.index)))
+    });
+
+    // faces of the winning file are not copies of each other, such as the named instances of a
+    // variable font, they are told apart by their PostScript names later on
+    let winner = group[0].path.clone();
+    let (same_file, copies): (Vec<_>, Vec<_>) =
+      group.into_iter().partition(|font| font.path == winner);
+
+    if !copies.is_empty() {
+      // faces of the same losing file need not be next to each other in the preferred order
+      let mut losers: Vec<_> = copies.into_iter().map(|font| font.path).collect();
+      losers.sort();
+      losers.dedup();
+      log::info!(
+        "{} is installed {} times, using {}",
+        postscript,
+        losers.len() + 1,
+        winner.to_string_lossy()
+      );
+      conflicts.push(FontConflict { postscript, family, style, winner, losers });
+    }
+
+    kept.extend(same_file);
+  }
+
+  (kept, conflicts)
+}
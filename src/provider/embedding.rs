@@ -0,0 +1,143 @@
+//! Embedding permissions of `OS/2.fsType` and what ffh does with fonts that carry them.
+
+use crate::provider::FontDescriptor;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use strum_macros::IntoStaticStr;
+use ttf_parser::{
+  os2::{self, Permissions},
+  Face,
+};
+
+/// Embedding licensing rights of a font, the least restrictive bit of `fsType` being used.
+#[derive(Copy, Clone, Default, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Embedding {
+  /// No restrictions, also assumed for fonts without an `OS/2` table.
+  #[default]
+  Installable,
+  Restricted,
+  PreviewAndPrint,
+  Editable,
+}
+
+impl Embedding {
+  pub fn from_face(face: &Face<'_>) -> Self {
+    Self::from_permissions(face.tables().os2.and_then(|os2| os2.permissions()))
+  }
+
+  /// Reads the permissions out of a raw `OS/2` table.
+  pub fn from_os2(os2: &[u8]) -> Self {
+    Self::from_permissions(os2::Table::parse(os2).and_then(|os2| os2.permissions()))
+  }
+
+  fn from_permissions(permissions: Option<Permissions>) -> Self {
+    match permissions {
+      Some(Permissions::Restricted) => Self::Restricted,
+      Some(Permissions::PreviewAndPrint) => Self::PreviewAndPrint,
+      Some(Permissions::Editable) => Self::Editable,
+      Some(Permissions::Installable) | None => Self::Installable,
+    }
+  }
+}
+
+/// What happens to a font with a given embedding permission.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum EmbeddingAction {
+  /// Listed and served.
+  #[default]
+  Serve,
+  /// Listed and served, but logged and reported as a warning.
+  Warn,
+  /// Listed so that documents show the font name, but its files are not handed out.
+  List,
+  /// Left out of the font list.
+  Hide,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct EmbeddingConfig {
+  pub restricted: EmbeddingAction,
+  pub preview_and_print: EmbeddingAction,
+  pub editable: EmbeddingAction,
+}
+
+impl Default for EmbeddingConfig {
+  /// Restricted fonts may not be embedded at all, so their files are kept to this machine.
+  fn default() -> Self {
+    Self {
+      restricted: EmbeddingAction::List,
+      preview_and_print: EmbeddingAction::Serve,
+      editable: EmbeddingAction::Serve,
+    }
+  }
+}
+
+impl EmbeddingConfig {
+  pub fn action(&self, embedding: Embedding) -> EmbeddingAction {
+    match embedding {
+      Embedding::Installable => EmbeddingAction::Serve,
+      Embedding::Restricted => self.restricted,
+      Embedding::PreviewAndPrint => self.preview_and_print,
+      Embedding::Editable => self.editable,
+    }
+  }
+}
+
+/// The action taken for a font with embedding restrictions.
+pub struct EmbeddingDecision {
+  pub postscript: String,
+  pub path: PathBuf,
+  pub embedding: Embedding,
+  pub action: EmbeddingAction,
+}
+
+/// Applies the configured policy, dropping the fonts that are to be hidden.
+pub fn apply(
+  fonts: Vec<FontDescriptor>,
+  config: &EmbeddingConfig,
+) -> (Vec<FontDescriptor>, Vec<EmbeddingDecision>) {
+  let mut kept = vec![];
+  let mut decisions = vec![];
+
+  for font in fonts {
+    if font.embedding == Embedding::Installable {
+      kept.push(font);
+      continue;
+    }
+
+    let action = config.action(font.embedding);
+    let outcome = match action {
+      EmbeddingAction::Serve => None,
+      EmbeddingAction::Warn => Some("served anyway"),
+      EmbeddingAction::List => Some("listed but not served"),
+      EmbeddingAction::Hide => Some("hidden"),
+    };
+    if let Some(outcome) = outcome {
+      let embedding: &'static str = font.embedding.into();
+      log::warn!("{} has {embedding} embedding permissions, {outcome}", font.postscript);
+    }
+    decisions.push(EmbeddingDecision {
+      postscript: font.postscript.clone(),
+      path: font.path.clone(),
+      embedding: font.embedding,
+      action,
+    });
+    if action != EmbeddingAction::Hide {
+      kept.push(font);
+    }
+  }
+
+  (kept, decisions)
+}
+
+/// Whether the file at `path` may be handed out, which takes every face in it being servable.
+pub fn is_servable(decisions: &[EmbeddingDecision], path: &Path) -> bool {
+  !decisions.iter().any(|decision| {
+    decision.path == path
+      && matches!(decision.action, EmbeddingAction::List | EmbeddingAction::Hide)
+  })
+}
@@ -1,5 +1,24 @@
-use crate::provider::{FontDescriptor, FontProvider, PlatformFontProviderErr};
-use std::{ops::Deref, path::PathBuf};
+use crate::{
+  config::Config,
+  fontcache::Lru,
+  manifest::{Manifest, ManifestErr},
+  provider::{
+    coverage::Coverage,
+    duplicates::{self, FontConflict},
+    embedding::{self, EmbeddingDecision},
+    localized,
+    postscript::{self, PostscriptCollision},
+    CompositeFontProvider, DirectoryFontProvider, FontDescriptor, FontProvider, FontProviderErr,
+    FontSource, SkippedFont,
+  },
+  sanitize::{self, QuarantinedFont},
+};
+use std::{
+  collections::{BTreeMap, HashMap},
+  ops::Deref,
+  path::{Path, PathBuf},
+  sync::{Arc, RwLock},
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,37 +27,254 @@ pub enum FontDatabaseErr {
   Initialization(String),
 
   #[error(transparent)]
-  PlatformFontProvider(#[from] PlatformFontProviderErr),
+  FontProvider(#[from] FontProviderErr),
+
+  #[error("Unknown profile {0}")]
+  UnknownProfile(String),
+
+  #[error(transparent)]
+  Manifest(#[from] ManifestErr),
+
+  #[error("Strict mode needs a manifest path")]
+  MissingManifest,
 }
 
 type Result<T, E = FontDatabaseErr> = std::result::Result<T, E>;
 
+/// Number of coverage queries remembered until the next scan, the least recently used forgotten
+/// first.
+const COVERAGE_QUERIES: u64 = 256;
+
+/// Characters looked for among the fonts of a profile.
+type CoverageQuery = (Option<String>, Coverage);
+
 pub struct FontDatabase {
   provider: Box<dyn FontProvider>,
+  config: Config,
+  /// Fonts allowed in strict mode.
+  manifest: Option<Manifest>,
   fonts: Vec<FontDescriptor>,
+  skipped: Vec<SkippedFont>,
+  conflicts: Vec<FontConflict>,
+  collisions: Vec<PostscriptCollision>,
+  embedding: Vec<EmbeddingDecision>,
+  quarantined: Vec<QuarantinedFont>,
+  /// Fonts listed for every profile, the profile's own fonts included.
+  profiles: HashMap<String, Vec<FontDescriptor>>,
+  /// Profile whose fonts are listed, every font outside of profiles when `None`.
+  active_profile: RwLock<Option<String>>,
+  /// Indices of the fonts of a profile covering a set of characters, valid until the next scan.
+  /// Every query takes up one unit of the cache.
+  covering: Lru<CoverageQuery, Arc<[usize]>>,
 }
 
 impl FontDatabase {
-  pub fn new(provider: Box<dyn FontProvider>) -> Result<Self> {
-    let mut instance = Self { provider, fonts: vec![] };
+  pub fn new(provider: Box<dyn FontProvider>, config: &Config) -> Result<Self> {
+    let manifest = match (&config.manifest.path, config.manifest.strict) {
+      (Some(path), true) => Some(Manifest::load(path)?),
+      (None, true) => return Err(FontDatabaseErr::MissingManifest),
+      (_, false) => None,
+    };
+
+    let mut instance = Self {
+      provider,
+      config: config.clone(),
+      manifest,
+      fonts: vec![],
+      skipped: vec![],
+      conflicts: vec![],
+      collisions: vec![],
+      embedding: vec![],
+      quarantined: vec![],
+      profiles: HashMap::new(),
+      active_profile: RwLock::new(config.profile.clone()),
+      covering: Lru::new(COVERAGE_QUERIES),
+    };
+    if let Some(profile) = &config.profile {
+      if !config.profiles.contains_key(profile) {
+        return Err(FontDatabaseErr::UnknownProfile(profile.clone()));
+      }
+    }
     instance.invalidate()?;
     Ok(instance)
   }
 
   pub fn invalidate(&mut self) -> Result<()> {
-    self.fonts = self.provider.get_all_fonts()?;
+    let mut scan = self.provider.get_all_fonts()?;
+    let (mut fonts, mut quarantined, mut embedding) = prepare(scan.fonts, &self.config);
+    if let Some(manifest) = &self.manifest {
+      let listed = fonts.len();
+      fonts.retain(|font| manifest.admits(font));
+      log::info!(
+        "Strict mode, {} fonts not in the manifest or not matching it left out",
+        listed - fonts.len()
+      );
+    }
+
+    let mut profiles = HashMap::new();
+    for (name, profile) in &self.config.profiles {
+      let sources = (profile.directories.iter())
+        .map(|dir| FontSource {
+          label: name.clone(),
+          priority: i32::MAX,
+          provider: Box::new(DirectoryFontProvider::new(dir)),
+        })
+        .collect();
+      let mut profile_scan = CompositeFontProvider::new(sources).get_all_fonts()?;
+      scan.skipped.append(&mut profile_scan.skipped);
+
+      let (mut listed, profile_quarantined, profile_embedding) =
+        prepare(profile_scan.fonts, &self.config);
+      if let Some(manifest) = &self.manifest {
+        listed.retain(|font| manifest.admits(font));
+      }
+      quarantined.extend(profile_quarantined);
+      embedding.extend(profile_embedding);
+
+      listed.extend(fonts.iter().filter(|font| profile.admits(font)).cloned());
+      let (mut listed, _) = duplicates::resolve(listed, &self.config.duplicates);
+      postscript::disambiguate(&mut listed);
+      profiles.insert(name.clone(), listed);
+    }
+
+    // copies of the same font must be dropped before the remaining name clashes get renamed
+    let (mut fonts, conflicts) = duplicates::resolve(fonts, &self.config.duplicates);
+    self.collisions = postscript::disambiguate(&mut fonts);
+    self.conflicts = conflicts;
+    self.embedding = embedding;
+    self.quarantined = quarantined;
+    self.fonts = fonts;
+    self.profiles = profiles;
+    self.skipped = scan.skipped;
+    self.covering = Lru::new(COVERAGE_QUERIES);
     Ok(())
   }
 
+  /// Name of the profile whose fonts are listed.
+  pub fn active_profile(&self) -> Option<String> {
+    self.active_profile.read().unwrap().clone()
+  }
+
+  /// Lists the fonts of `profile` from now on, or every font outside of profiles for `None`.
+  pub fn set_active_profile(&self, profile: Option<&str>) -> Result<()> {
+    if let Some(profile) = profile {
+      if !self.profiles.contains_key(profile) {
+        return Err(FontDatabaseErr::UnknownProfile(profile.to_owned()));
+      }
+    }
+    match profile {
+      Some(profile) => log::info!("Listing the fonts of profile {profile}"),
+      None => log::info!("Listing every font outside of profiles"),
+    }
+    *self.active_profile.write().unwrap() = profile.map(str::to_owned);
+    Ok(())
+  }
+
+  /// Number of fonts listed per profile, by name.
+  pub fn profiles(&self) -> Vec<(&str, usize)> {
+    let mut profiles: Vec<_> =
+      self.profiles.iter().map(|(name, fonts)| (name.as_str(), fonts.len())).collect();
+    profiles.sort();
+    profiles
+  }
+
+  pub fn skipped(&self) -> &[SkippedFont] {
+    &self.skipped
+  }
+
+  pub fn conflicts(&self) -> &[FontConflict] {
+    &self.conflicts
+  }
+
+  pub fn collisions(&self) -> &[PostscriptCollision] {
+    &self.collisions
+  }
+
+  /// Fonts whose files failed validation.
+  pub fn quarantined(&self) -> &[QuarantinedFont] {
+    &self.quarantined
+  }
+
+  /// Number of listed fonts per source, by label and priority.
+  pub fn sources(&self) -> Vec<(&str, i32, usize)> {
+    let mut sources = BTreeMap::<(&str, i32), usize>::new();
+    for font in &self.fonts {
+      *sources.entry((&font.source, font.priority)).or_default() += 1;
+    }
+    sources.into_iter().map(|((label, priority), fonts)| (label, priority, fonts)).collect()
+  }
+
+  pub fn config(&self) -> &Config {
+    &self.config
+  }
+
+  /// Fonts with embedding restrictions and what was done with them.
+  pub fn embedding(&self) -> &[EmbeddingDecision] {
+    &self.embedding
+  }
+
+  /// Whether the embedding policy allows handing out the file at `path`.
+  pub fn is_servable(&self, path: &Path) -> bool {
+    embedding::is_servable(&self.embedding, path)
+  }
+
+  /// Fonts that map every one of `characters` to a glyph.
+  pub fn covering(&self, characters: &Coverage) -> Vec<&FontDescriptor> {
+    let profile = self.active_profile();
+    let fonts = self.listed(profile.as_deref());
+    let key = (profile, characters.clone());
+
+    let indices = self.covering.get(&key).unwrap_or_else(|| {
+      let indices: Arc<[usize]> = (fonts.iter().enumerate())
+        .filter(|(_, font)| font.coverage.covers(characters))
+        .map(|(i, _)| i)
+        .collect();
+
+      self.covering.insert(key, indices.clone(), 1);
+      indices
+    });
+
+    indices.iter().map(|&i| &fonts[i]).collect()
+  }
+
   pub fn is_path_valid(&self, path: PathBuf) -> bool {
-    self.fonts.iter().any(|f| f.path == path)
+    self.iter().any(|f| f.path == path)
   }
+
+  /// Fonts listed for `profile`.
+  fn listed(&self, profile: Option<&str>) -> &[FontDescriptor] {
+    match profile.and_then(|profile| self.profiles.get(profile)) {
+      Some(fonts) => fonts,
+      None => &self.fonts,
+    }
+  }
+}
+
+/// Picks names in the preferred languages and applies the sanitizer and the embedding policy.
+fn prepare(
+  mut fonts: Vec<FontDescriptor>,
+  config: &Config,
+) -> (Vec<FontDescriptor>, Vec<QuarantinedFont>, Vec<EmbeddingDecision>) {
+  let languages = &config.names.languages;
+  for font in &mut fonts {
+    if let Some(family) = localized::select(&font.families, languages) {
+      font.family = family.to_owned();
+    }
+    if let Some(style) = localized::select(&font.styles, languages) {
+      font.style = style.to_owned();
+    }
+  }
+
+  let (fonts, quarantined) = sanitize::quarantine(fonts, &config.sanitize);
+  let (fonts, embedding) = embedding::apply(fonts, &config.embedding);
+  (fonts, quarantined, embedding)
 }
 
+/// The fonts of the active profile.
 impl Deref for FontDatabase {
-  type Target = Vec<FontDescriptor>;
+  type Target = [FontDescriptor];
 
   fn deref(&self) -> &Self::Target {
-    &self.fonts
+    self.listed(self.active_profile.read().unwrap().as_deref())
   }
 }
@@ -1,11 +1,21 @@
-use super::platform::PlatformFontProviderErr;
-use std::path::PathBuf;
+use super::{
+  archive::ArchiveErr, coverage::Coverage, embedding::Embedding, localized::LocalizedName,
+};
+use std::{
+  error::Error as StdError,
+  fs, io,
+  path::{Path, PathBuf},
+  time::SystemTime,
+};
+use strum_macros::IntoStaticStr;
+use thiserror::Error;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default)]
 pub enum FontWeight {
   Thin = 100,
   ExtraLight = 200,
   Light = 300,
+  #[default]
   Normal = 400,
   Medium = 500,
   SemiBold = 600,
@@ -15,12 +25,13 @@ pub enum FontWeight {
   ExtraBlack = 950,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default)]
 pub enum FontWidth {
   UltraCondensed = 50,
   ExtraCondensed = 63, // 62.5%
   Condensed = 75,
   SemiCondensed = 88, // 87.5%
+  #[default]
   Normal = 100,
   SemiExpanded = 113, // 112.5%
   Expanded = 125,
@@ -28,21 +39,115 @@ pub enum FontWidth {
   UltraExpanded = 200,
 }
 
+#[derive(Clone, Default)]
 pub struct FontDescriptor {
   pub path: PathBuf,
+  /// Face index within the file, the upper 16 bits select a named instance of a variable font.
+  pub index: u32,
   pub postscript: String,
   pub family: String,
   pub style: String,
+  /// Every family name of the face, `family` is picked out of these.
+  pub families: Vec<LocalizedName>,
+  /// Every style name of the face, `style` is picked out of these.
+  pub styles: Vec<LocalizedName>,
   pub weight: FontWeight,
   pub width: FontWidth,
   pub italic: bool,
+  /// `head.fontRevision` of the face, if the file could be read.
+  pub revision: Option<f32>,
+  /// Characters the face maps to glyphs.
+  pub coverage: Coverage,
+  /// Embedding permissions out of `OS/2.fsType`.
+  pub embedding: Embedding,
+  /// Label of the font source the face was found in.
+  pub source: String,
+  /// Priority of that source, the copy from the highest one wins when a font is found twice.
+  pub priority: i32,
+  /// Modification time of the file when it was scanned.
+  pub modified: Option<SystemTime>,
+  /// Size of the file in bytes when it was scanned.
+  pub size: u64,
+  /// Installed in the user's home or a directory managed by ffh rather than for the whole system.
+  pub user_installed: bool,
 }
 
-pub trait FontProvider {
-  fn new() -> Result<Self, PlatformFontProviderErr>
-  where
-    Self: Sized;
-  fn get_api_version(&self) -> Result<usize, PlatformFontProviderErr>;
-  fn get_all_fonts(&self) -> Result<Vec<FontDescriptor>, PlatformFontProviderErr>;
-  fn get_font_paths(&self) -> Result<Vec<PathBuf>, PlatformFontProviderErr>;
+/// Modification time and size of the font file at `path`, for [`FontDescriptor`].
+pub fn stat(path: &Path) -> (Option<SystemTime>, u64) {
+  match fs::metadata(path) {
+    Ok(metadata) => (metadata.modified().ok(), metadata.len()),
+    Err(_) => (None, 0),
+  }
+}
+
+/// Whether the font file at `path` was installed by the user, or by ffh on their behalf.
+pub fn is_user_installed(path: &Path) -> bool {
+  let managed = [dirs::cache_dir(), dirs::data_dir()].map(|dir| dir.map(|dir| dir.join("ffh")));
+  [dirs::home_dir()].iter().chain(&managed).flatten().any(|dir| path.starts_with(dir))
+}
+
+/// Why a font reported by a source did not make it into the font list.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum SkipReason {
+  MissingProperty,
+  MissingPostscriptName,
+  UnsupportedFormat,
+  UnmappableWeight,
+  UnmappableWidth,
+  NonUtf8Path,
+  NonUtf8Name,
+  /// The font file, or the directory holding it, could not be read.
+  Unreadable,
+  /// A whole font source could not be scanned.
+  SourceUnavailable,
+}
+
+pub struct SkippedFont {
+  pub path: Option<PathBuf>,
+  pub reason: SkipReason,
+  /// Additional context, such as the name of the missing property.
+  pub detail: Option<String>,
+}
+
+/// Result of a single pass over the fonts of a provider.
+#[derive(Default)]
+pub struct FontScan {
+  pub fonts: Vec<FontDescriptor>,
+  pub skipped: Vec<SkippedFont>,
+}
+
+/// Errors of any font provider, the platform specific ones boxed.
+#[derive(Error, Debug)]
+pub enum FontProviderErr {
+  #[error("Unable to read {0}: {1}")]
+  Io(PathBuf, #[source] io::Error),
+
+  #[error("Unable to read archive {0}: {1}")]
+  Archive(PathBuf, #[source] ArchiveErr),
+
+  #[error("A {0} source needs a path")]
+  MissingPath(&'static str),
+
+  #[error(transparent)]
+  Platform(Box<dyn StdError + Send + Sync>),
+}
+
+type Result<T, E = FontProviderErr> = std::result::Result<T, E>;
+
+/// Native library a provider scans the fonts with.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProviderLibrary {
+  pub name: &'static str,
+  pub version: String,
+}
+
+pub trait FontProvider: Send + Sync {
+  fn get_api_version(&self) -> Result<usize>;
+  /// Native libraries the provider relies on, none for the ones reading font files directly.
+  fn get_libraries(&self) -> Vec<ProviderLibrary> {
+    vec![]
+  }
+  fn get_all_fonts(&self) -> Result<FontScan>;
+  fn get_font_paths(&self) -> Result<Vec<PathBuf>>;
 }
@@ -0,0 +1,127 @@
+//! Family and style names in the languages a font provides them in.
+
+use serde::Deserialize;
+use ttf_parser::{name::Name, Face, PlatformId};
+
+/// A name together with the language it is written in, as a lowercase BCP 47 tag (`en`, `zh-tw`).
+#[derive(Clone, PartialEq, Eq)]
+pub struct LocalizedName {
+  pub language: String,
+  pub name: String,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct NameConfig {
+  /// Languages to pick family and style names in, most preferred first.
+  pub languages: Vec<String>,
+}
+
+impl Default for NameConfig {
+  fn default() -> Self {
+    Self { languages: vec!["en".to_owned()] }
+  }
+}
+
+/// Picks the name in the most preferred language, falling back to English and then to the first
+/// name of the font.
+///
+/// A preference matches its own tag first and then any tag of the same primary language, so `zh`
+/// matches `zh-tw` and `zh-hk` matches `zh-tw` when the font has no Hong Kong name.
+pub fn select<'a>(names: &'a [LocalizedName], languages: &[String]) -> Option<&'a str> {
+  let find = |matches: &dyn Fn(&str) -> bool| names.iter().find(|name| matches(&name.language));
+  languages
+    .iter()
+    .find_map(|preferred| {
+      let preferred = preferred.to_ascii_lowercase();
+      (find(&|language| language == preferred))
+        .or_else(|| find(&|language| primary(language) == primary(&preferred)))
+    })
+    .or_else(|| find(&|language| primary(language) == "en"))
+    .or_else(|| names.first())
+    .map(|name| name.name.as_str())
+}
+
+/// Primary language subtag of a tag, `zh` for `zh-tw`.
+fn primary(tag: &str) -> &str {
+  tag.split('-').next().unwrap_or(tag)
+}
+
+/// Collects every Unicode record of name `id` whose language is known.
+pub fn from_name_table(face: &Face<'_>, id: u16) -> Vec<LocalizedName> {
+  let mut names: Vec<LocalizedName> = vec![];
+
+  for record in face.names().into_iter().filter(|name| name.name_id == id) {
+    let (Some(language), Some(name)) = (language(&record), record.to_string()) else {
+      continue;
+    };
+
+    let name = LocalizedName { language: language.to_owned(), name };
+    if !names.contains(&name) {
+      names.push(name);
+    }
+  }
+
+  names
+}
+
+/// Language of a name record, when it is one of the languages names are commonly written in.
+pub fn language(record: &Name<'_>) -> Option<&'static str> {
+  match record.platform_id {
+    PlatformId::Windows => windows_language(record.language_id),
+    PlatformId::Macintosh => macintosh_language(record.language_id),
+    _ => None,
+  }
+}
+
+fn windows_language(id: u16) -> Option<&'static str> {
+  match id {
+    0x0404 | 0x0c04 | 0x1404 => Some("zh-tw"),
+    0x0804 | 0x1004 => Some("zh-cn"),
+    _ => match id & 0x03FF {
+      0x01 => Some("ar"),
+      0x05 => Some("cs"),
+      0x06 => Some("da"),
+      0x07 => Some("de"),
+      0x08 => Some("el"),
+      0x09 => Some("en"),
+      0x0A => Some("es"),
+      0x0B => Some("fi"),
+      0x0C => Some("fr"),
+      0x0D => Some("he"),
+      0x0E => Some("hu"),
+      0x10 => Some("it"),
+      0x11 => Some("ja"),
+      0x12 => Some("ko"),
+      0x13 => Some("nl"),
+      0x14 => Some("no"),
+      0x15 => Some("pl"),
+      0x16 => Some("pt"),
+      0x19 => Some("ru"),
+      0x1D => Some("sv"),
+      0x1E => Some("th"),
+      0x1F => Some("tr"),
+      0x22 => Some("uk"),
+      0x2A => Some("vi"),
+      _ => None,
+    },
+  }
+}
+
+fn macintosh_language(id: u16) -> Option<&'static str> {
+  match id {
+    0 => Some("en"),
+    1 => Some("fr"),
+    2 => Some("de"),
+    3 => Some("it"),
+    4 => Some("nl"),
+    5 => Some("sv"),
+    6 => Some("es"),
+    11 => Some("ja"),
+    19 => Some("zh-tw"),
+    23 => Some("ko"),
+    32 => Some("ru"),
+    33 => Some("zh-cn"),
+    _ => None,
+  }
+}
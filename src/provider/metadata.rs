@@ -0,0 +1,151 @@
+//! Descriptive data of a face, read from its `name`, `OS/2`, `head` and `hhea` tables.
+
+use super::localized;
+use strum_macros::IntoStaticStr;
+use thiserror::Error;
+use ttf_parser::{Face, Tag};
+
+const OS2: Tag = Tag::from_bytes(b"OS/2");
+const GLYF: Tag = Tag::from_bytes(b"glyf");
+const CFF: Tag = Tag::from_bytes(b"CFF ");
+const CFF2: Tag = Tag::from_bytes(b"CFF2");
+
+#[derive(Error, Debug)]
+pub enum MetadataErr {
+  #[error("Malformed font: {0}")]
+  Malformed(#[from] ttf_parser::FaceParsingError),
+}
+
+type Result<T, E = MetadataErr> = std::result::Result<T, E>;
+
+#[derive(Copy, Clone, IntoStaticStr)]
+pub enum OutlineFormat {
+  #[strum(serialize = "TrueType")]
+  TrueType,
+  #[strum(serialize = "CFF")]
+  Cff,
+  #[strum(serialize = "CFF2")]
+  Cff2,
+}
+
+/// A record of the `name` table.
+pub struct NameRecord {
+  pub id: u16,
+  /// Lowercase BCP 47 tag, when the language is known.
+  pub language: Option<&'static str>,
+  pub value: String,
+}
+
+impl NameRecord {
+  /// What the name ID stands for, for the IDs predefined by OpenType.
+  pub fn kind(&self) -> Option<&'static str> {
+    NAME_KINDS.get(self.id as usize).copied()
+  }
+}
+
+/// Meaning of the name IDs 0 to 25.
+const NAME_KINDS: [&str; 26] = [
+  "copyright",
+  "family",
+  "subfamily",
+  "unique_id",
+  "full_name",
+  "version",
+  "postscript",
+  "trademark",
+  "manufacturer",
+  "designer",
+  "description",
+  "vendor_url",
+  "designer_url",
+  "license",
+  "license_url",
+  "reserved",
+  "typographic_family",
+  "typographic_subfamily",
+  "compatible_full_name",
+  "sample_text",
+  "postscript_cid",
+  "wws_family",
+  "wws_subfamily",
+  "light_background_palette",
+  "dark_background_palette",
+  "variations_postscript_prefix",
+];
+
+/// Vertical metrics in font units, as used for line layout.
+pub struct VerticalMetrics {
+  pub units_per_em: u16,
+  pub ascender: i16,
+  pub descender: i16,
+  pub line_gap: i16,
+  pub x_height: Option<i16>,
+  pub cap_height: Option<i16>,
+}
+
+pub struct FontMetadata {
+  pub names: Vec<NameRecord>,
+  /// Four character vendor ID out of `OS/2`.
+  pub vendor: Option<String>,
+  /// PANOSE classification out of `OS/2`.
+  pub panose: Option<[u8; 10]>,
+  pub metrics: VerticalMetrics,
+  /// `None` for fonts with bitmap glyphs only.
+  pub outlines: Option<OutlineFormat>,
+  pub tables: Vec<String>,
+}
+
+/// Reads the metadata of face `index` of `data`.
+pub fn inspect(data: &[u8], index: u32) -> Result<FontMetadata> {
+  let face = Face::parse(data, index & 0xFFFF)?;
+  let raw = face.raw_face();
+
+  let mut names: Vec<NameRecord> = vec![];
+  for record in face.names() {
+    let Some(value) = record.to_string() else {
+      continue;
+    };
+    let language = localized::language(&record);
+    // the same name is usually stored once for Macintosh and once for Windows
+    let duplicate = names
+      .iter()
+      .any(|name| name.id == record.name_id && name.language == language && name.value == value);
+    if !duplicate {
+      names.push(NameRecord { id: record.name_id, language, value });
+    }
+  }
+  names.sort_by_key(|name| name.id);
+
+  let os2 = raw.table(OS2);
+  let vendor = os2
+    .and_then(|os2| os2.get(58..62))
+    .map(|vendor| String::from_utf8_lossy(vendor).trim_end().to_owned())
+    .filter(|vendor| !vendor.is_empty());
+  let panose = os2.and_then(|os2| os2.get(32..42)).and_then(|panose| panose.try_into().ok());
+
+  let outlines = if raw.table(GLYF).is_some() {
+    Some(OutlineFormat::TrueType)
+  } else if raw.table(CFF2).is_some() {
+    Some(OutlineFormat::Cff2)
+  } else if raw.table(CFF).is_some() {
+    Some(OutlineFormat::Cff)
+  } else {
+    None
+  };
+
+  Ok(FontMetadata {
+    names,
+    vendor,
+    panose,
+    metrics: VerticalMetrics {
+      units_per_em: face.units_per_em(),
+      ascender: face.ascender(),
+      descender: face.descender(),
+      line_gap: face.line_gap(),
+      x_height: face.x_height(),
+      cap_height: face.capital_height(),
+    },
+    outlines,
+    tables: raw.table_records.into_iter().map(|record| record.tag.to_string()).collect(),
+  })
+}
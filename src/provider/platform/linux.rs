@@ -1,11 +1,23 @@
 #![allow(unsafe_code)]
 
-use crate::provider::{FontDescriptor, FontProvider, FontWeight, FontWidth};
+use crate::provider::{
+  coverage::Coverage,
+  embedding::Embedding,
+  is_user_installed,
+  localized::{self, LocalizedName},
+  postscript, sfnt, stat, FontDescriptor, FontProvider, FontProviderErr, FontScan, FontWeight,
+  FontWidth, ProviderLibrary, SkipReason, SkippedFont,
+};
 
 use std::{
+  cell::OnceCell,
   convert::TryFrom,
   ffi::{CStr, OsStr},
-  os::raw::{c_char, c_int, c_uchar},
+  fs,
+  os::{
+    raw::{c_char, c_int, c_uchar},
+    unix::ffi::OsStrExt,
+  },
   path::{Path, PathBuf},
   ptr,
   slice::from_raw_parts,
@@ -14,6 +26,7 @@ use std::{
 };
 use strum_macros::Display;
 use thiserror::Error;
+use ttf_parser::{name_id, Face, Tag};
 
 #[repr(u32)]
 #[derive(PartialEq)]
@@ -70,6 +83,11 @@ struct FcObjectSet {
   _private: [u8; 0],
 }
 
+#[repr(C)]
+struct FcCharSet {
+  _private: [u8; 0],
+}
+
 #[repr(C)]
 struct FcFontSet {
   nfont: c_int,
@@ -78,18 +96,30 @@ struct FcFontSet {
 }
 
 type FcChar8 = c_uchar;
+type FcChar32 = u32;
 type FcBool = c_int;
 const FC_FAMILY: &[u8] = b"family\0";
 const FC_STYLE: &[u8] = b"style\0";
+const FC_FAMILYLANG: &[u8] = b"familylang\0";
+const FC_STYLELANG: &[u8] = b"stylelang\0";
 const FC_FILE: &[u8] = b"file\0";
 const FC_WEIGHT: &[u8] = b"weight\0";
 const FC_WIDTH: &[u8] = b"width\0";
 const FC_SLANT: &[u8] = b"slant\0";
+const FC_INDEX: &[u8] = b"index\0";
+const FC_CHARSET: &[u8] = b"charset\0";
 const FC_POSTSCRIPT_NAME: &[u8] = b"postscriptname\0";
+const FC_FONTVERSION: &[u8] = b"fontversion\0";
+const FC_CHARSET_MAP_SIZE: usize = 256 / 32;
+const FC_CHARSET_DONE: FcChar32 = FcChar32::MAX;
 const FC_SLANT_ITALIC: c_int = 100;
+const OS2: Tag = Tag::from_bytes(b"OS/2");
+/// First fontconfig release guarding its configuration with internal locks, 2.10.0.
+const FC_THREAD_SAFE_VERSION: c_int = 21000;
 
 #[link(name = "fontconfig")]
 extern "C" {
+  fn FcGetVersion() -> c_int;
   fn FcInitLoadConfigAndFonts() -> *mut FcConfig;
   fn FcConfigDestroy(config: *const FcConfig);
 
@@ -123,6 +153,24 @@ extern "C" {
     i: &mut c_int,
   ) -> FcResult;
 
+  fn FcPatternGetCharSet(
+    p: *const FcPattern,
+    object: *const FcChar8,
+    n: c_int,
+    c: &mut *const FcCharSet,
+  ) -> FcResult;
+
+  fn FcCharSetFirstPage(
+    a: *const FcCharSet,
+    map: &mut [FcChar32; FC_CHARSET_MAP_SIZE],
+    next: &mut FcChar32,
+  ) -> FcChar32;
+  fn FcCharSetNextPage(
+    a: *const FcCharSet,
+    map: &mut [FcChar32; FC_CHARSET_MAP_SIZE],
+    next: &mut FcChar32,
+  ) -> FcChar32;
+
   fn FcStrListFirst(list: *const FcStrList);
   fn FcStrListNext(list: *const FcStrList) -> *const FcChar8;
   fn FcStrListDone(list: *const FcStrList);
@@ -151,20 +199,211 @@ pub enum PlatformFontProviderErr {
 
 type Result<T, E = PlatformFontProviderErr> = std::result::Result<T, E>;
 
+impl From<PlatformFontProviderErr> for FontProviderErr {
+  fn from(value: PlatformFontProviderErr) -> Self {
+    Self::Platform(Box::new(value))
+  }
+}
+
 pub struct PlatformFontProvider {
   config: *const FcConfig,
   pattern: *const FcPattern,
   object_set: *const FcObjectSet,
 }
 
+// fontconfig guards its configuration with internal locks since 2.10, which `new` checks, and
+// the pattern/object set are never mutated after construction.
+unsafe impl Send for PlatformFontProvider {}
+unsafe impl Sync for PlatformFontProvider {}
+
 impl PlatformFontProvider {
+  /// Formats a version encoded as major * 10000 + minor * 100 + revision.
+  fn format_version(version: c_int) -> String {
+    format!("{}.{}.{}", version / 10000, version / 100 % 100, version % 100)
+  }
+
   fn get_extension_from_filename(filename: &str) -> Option<&str> {
     Path::new(filename).extension().and_then(OsStr::to_str)
   }
+
+  fn is_supported_format(path: &Path) -> bool {
+    matches!(
+      path.to_str().and_then(Self::get_extension_from_filename),
+      Some("ttf" | "otf" | "ttc" | "otc")
+    )
+  }
+
+  fn get_string<'a>(pattern: &'a FcPattern, object: &[u8], n: c_int) -> Option<&'a CStr> {
+    let mut value: *const c_char = ptr::null();
+    if unsafe { FcPatternGetString(pattern, object.as_ptr(), n, &mut value) } != FcResult::Match
+      || value.is_null()
+    {
+      return None;
+    }
+    Some(unsafe { CStr::from_ptr(value) })
+  }
+
+  fn get_integer(pattern: &FcPattern, object: &[u8]) -> Option<c_int> {
+    let mut value: c_int = 0;
+    if unsafe { FcPatternGetInteger(pattern, object.as_ptr(), 0, &mut value) } != FcResult::Match {
+      return None;
+    }
+    Some(value)
+  }
+
+  /// Reads the characters fontconfig found in the font, page by page.
+  fn get_coverage(pattern: &FcPattern) -> Option<Coverage> {
+    let mut charset: *const FcCharSet = ptr::null();
+    if unsafe { FcPatternGetCharSet(pattern, FC_CHARSET.as_ptr(), 0, &mut charset) }
+      != FcResult::Match
+      || charset.is_null()
+    {
+      return None;
+    }
+
+    let mut pages = vec![];
+    let mut map = [0; FC_CHARSET_MAP_SIZE];
+    let mut next = 0;
+    let mut page = unsafe { FcCharSetFirstPage(charset, &mut map, &mut next) };
+    while page != FC_CHARSET_DONE {
+      pages.push((page, map));
+      page = unsafe { FcCharSetNextPage(charset, &mut map, &mut next) };
+    }
+
+    Some(Coverage::from_charset_pages(pages))
+  }
+
+  /// Collects all values of `object` along with the languages stored in `lang_object`.
+  fn get_localized(pattern: &FcPattern, object: &[u8], lang_object: &[u8]) -> Vec<LocalizedName> {
+    let mut names = vec![];
+    for n in 0.. {
+      let Some(name) = Self::get_string(pattern, object, n) else {
+        break;
+      };
+      let language = Self::get_string(pattern, lang_object, n).and_then(|lang| lang.to_str().ok());
+
+      if let (Ok(name), Some(language)) = (name.to_str(), language) {
+        names
+          .push(LocalizedName { language: language.to_ascii_lowercase(), name: name.to_owned() });
+      }
+    }
+    names
+  }
+
+  /// Builds the descriptor out of what fontconfig knows, reading `file` only for what it lacks.
+  fn get_font(
+    pattern: &FcPattern,
+    path: &Path,
+    file: &OnceCell<Option<Vec<u8>>>,
+  ) -> Result<FontDescriptor, SkippedFont> {
+    let skip =
+      |reason, detail: Option<String>| SkippedFont { path: Some(path.to_owned()), reason, detail };
+    let missing = |property: &str| skip(SkipReason::MissingProperty, Some(property.to_owned()));
+
+    if path.to_str().is_none() {
+      return Err(skip(SkipReason::NonUtf8Path, None));
+    }
+
+    if !Self::is_supported_format(path) {
+      return Err(skip(SkipReason::UnsupportedFormat, None));
+    }
+
+    let family_raw = Self::get_string(pattern, FC_FAMILY, 0).ok_or_else(|| missing("family"))?;
+    let style_raw = Self::get_string(pattern, FC_STYLE, 0);
+    let weight_raw = Self::get_integer(pattern, FC_WEIGHT).ok_or_else(|| missing("weight"))?;
+    let width_raw = Self::get_integer(pattern, FC_WIDTH).ok_or_else(|| missing("width"))?;
+    let slant_raw = Self::get_integer(pattern, FC_SLANT).ok_or_else(|| missing("slant"))?;
+    let index = Self::get_integer(pattern, FC_INDEX).unwrap_or(0) as u32;
+
+    let weight = FcWeight::try_from(weight_raw)
+      .map_err(|e| skip(SkipReason::UnmappableWeight, Some(e.to_string())))?;
+    let width = FcWidth::try_from(width_raw)
+      .map_err(|e| skip(SkipReason::UnmappableWidth, Some(e.to_string())))?;
+
+    let to_string = |value: &CStr| {
+      value
+        .to_str()
+        .map(ToOwned::to_owned)
+        .map_err(|e| skip(SkipReason::NonUtf8Name, Some(e.to_string())))
+    };
+
+    let family = to_string(family_raw)?;
+    let style = match style_raw {
+      Some(style_raw) => to_string(style_raw)?,
+      None => weight.to_string(),
+    };
+    let data = || file.get_or_init(|| fs::read(path).ok()).as_deref();
+    let face = || data().and_then(|data| Face::parse(data, index & 0xFFFF).ok());
+    let mut families = Self::get_localized(pattern, FC_FAMILY, FC_FAMILYLANG);
+    let mut styles = Self::get_localized(pattern, FC_STYLE, FC_STYLELANG);
+
+    // older fontconfig versions do not record the languages of the names
+    if let Some(face) = face().filter(|_| families.is_empty()) {
+      families = localized::from_name_table(&face, name_id::TYPOGRAPHIC_FAMILY);
+      families.extend(localized::from_name_table(&face, name_id::FAMILY));
+    }
+    if let Some(face) = face().filter(|_| styles.is_empty()) {
+      styles = localized::from_name_table(&face, name_id::TYPOGRAPHIC_SUBFAMILY);
+      styles.extend(localized::from_name_table(&face, name_id::SUBFAMILY));
+    }
+
+    let coverage = Self::get_coverage(pattern)
+      .or_else(|| face().as_ref().map(Coverage::from_cmap))
+      .unwrap_or_default();
+
+    // fontconfig gives every named instance of a variable font the name of the default one
+    let postscript = Self::get_string(pattern, FC_POSTSCRIPT_NAME, 0)
+      .filter(|_| index >> 16 == 0)
+      .and_then(|name| name.to_str().ok())
+      .map(postscript::sanitize)
+      .filter(|name| !name.is_empty())
+      .or_else(|| data().and_then(|data| postscript::read(data, index)))
+      .or_else(|| postscript::synthesize(&family, &style))
+      .ok_or_else(|| skip(SkipReason::MissingPostscriptName, None))?;
+
+    // head.fontRevision as 16.16 fixed point
+    let revision = Self::get_integer(pattern, FC_FONTVERSION)
+      .map(|version| version as f32 / 65536.0)
+      .or_else(|| data().and_then(|data| sfnt::font_revision(data, index)));
+    let embedding = match sfnt::read_table(path, index & 0xFFFF, OS2) {
+      Ok(Some(os2)) => Embedding::from_os2(&os2),
+      _ => Embedding::default(),
+    };
+
+    let (modified, size) = stat(path);
+    Ok(FontDescriptor {
+      path: path.to_owned(),
+      index,
+      postscript,
+      family,
+      style,
+      families,
+      styles,
+      weight: FontWeight::from(weight),
+      width: FontWidth::from(width),
+      italic: slant_raw == FC_SLANT_ITALIC,
+      revision,
+      coverage,
+      embedding,
+      source: "fontconfig".to_owned(),
+      priority: 0,
+      modified,
+      size,
+      user_installed: is_user_installed(path),
+    })
+  }
 }
 
-impl FontProvider for PlatformFontProvider {
-  fn new() -> Result<Self> {
+impl PlatformFontProvider {
+  pub fn new() -> Result<Self> {
+    let version = unsafe { FcGetVersion() };
+    if version < FC_THREAD_SAFE_VERSION {
+      return Err(PlatformFontProviderErr::Initialization(format!(
+        "fontconfig {} is not thread-safe, 2.10 or newer is needed",
+        Self::format_version(version)
+      )));
+    }
+
     let config = unsafe { FcInitLoadConfigAndFonts() };
     if config.is_null() {
       return Err(PlatformFontProviderErr::Initialization(
@@ -180,12 +419,17 @@ impl FontProvider for PlatformFontProvider {
     let object_set = unsafe {
       FcObjectSetBuild(
         FC_FILE.as_ptr(),
-        FC_POSTSCRIPT_NAME.as_ptr(),
+        FC_INDEX.as_ptr(),
         FC_FAMILY.as_ptr(),
         FC_STYLE.as_ptr(),
+        FC_FAMILYLANG.as_ptr(),
+        FC_STYLELANG.as_ptr(),
         FC_WEIGHT.as_ptr(),
         FC_WIDTH.as_ptr(),
         FC_SLANT.as_ptr(),
+        FC_CHARSET.as_ptr(),
+        FC_POSTSCRIPT_NAME.as_ptr(),
+        FC_FONTVERSION.as_ptr(),
         ptr::null::<*const FcChar8>(),
       )
     };
@@ -199,103 +443,69 @@ impl FontProvider for PlatformFontProvider {
 
     Ok(Self { config, pattern, object_set })
   }
+}
 
-  fn get_api_version(&self) -> Result<usize> {
+impl FontProvider for PlatformFontProvider {
+  fn get_api_version(&self) -> Result<usize, FontProviderErr> {
     // TODO: Implement this
     Ok(35)
   }
 
-  fn get_all_fonts(&self) -> Result<Vec<FontDescriptor>> {
-    let mut fonts: Vec<FontDescriptor> = vec![];
+  fn get_libraries(&self) -> Vec<ProviderLibrary> {
+    let version = Self::format_version(unsafe { FcGetVersion() });
+    vec![ProviderLibrary { name: "fontconfig", version }]
+  }
+
+  fn get_all_fonts(&self) -> Result<FontScan, FontProviderErr> {
+    let mut scan = FontScan::default();
     let font_set: *const FcFontSet =
       unsafe { FcFontList(self.config, self.pattern, self.object_set) };
 
     if font_set.is_null() {
-      return Err(PlatformFontProviderErr::FontListEmpty("FcFontList failed".to_owned()));
+      return Err(PlatformFontProviderErr::FontListEmpty("FcFontList failed".to_owned()).into());
     }
 
+    // named instances of a variable font share the file, so keep the last one around in case
+    // fontconfig is missing something that has to be read from it
+    let mut file: Option<(PathBuf, OnceCell<Option<Vec<u8>>>)> = None;
+
     for pattern in unsafe { from_raw_parts((*font_set).fonts, (*font_set).nfont as usize) }
       .iter()
       .filter_map(|f| unsafe { f.as_ref() })
     {
-      let mut path_raw: *const c_char = ptr::null();
-      let mut family_raw: *const c_char = ptr::null();
-      let mut style_raw: *const c_char = ptr::null();
-      let mut psname_raw: *const c_char = ptr::null();
-      let mut weight_raw: c_int = 0;
-      let mut width_raw: c_int = 0;
-      let mut slant_raw: c_int = 0;
-
-      if unsafe { FcPatternGetString(pattern, FC_FILE.as_ptr(), 0, &mut path_raw) }
-        == FcResult::Match
-        && unsafe { FcPatternGetString(pattern, FC_FAMILY.as_ptr(), 0, &mut family_raw) }
-          == FcResult::Match
-        && unsafe { FcPatternGetString(pattern, FC_STYLE.as_ptr(), 0, &mut style_raw) }
-          == FcResult::Match
-        && unsafe { FcPatternGetString(pattern, FC_POSTSCRIPT_NAME.as_ptr(), 0, &mut psname_raw) }
-          == FcResult::Match
-        && unsafe { FcPatternGetInteger(pattern, FC_WEIGHT.as_ptr(), 0, &mut weight_raw) }
-          == FcResult::Match
-        && unsafe { FcPatternGetInteger(pattern, FC_WIDTH.as_ptr(), 0, &mut width_raw) }
-          == FcResult::Match
-        && unsafe { FcPatternGetInteger(pattern, FC_SLANT.as_ptr(), 0, &mut slant_raw) }
-          == FcResult::Match
-      {
-        if path_raw.is_null() {
-          continue;
-        }
-
-        let weight: FcWeight = FcWeight::try_from(weight_raw)?;
-        let width: FcWidth = FcWidth::try_from(width_raw)?;
-        let path = unsafe { CStr::from_ptr(path_raw) }.to_str()?.to_owned();
-
-        match Self::get_extension_from_filename(path.as_str()) {
-          Some("ttf") => {}
-          Some("otf") => {}
-          _ => continue,
-        };
-
-        let family = if family_raw.is_null() {
-          "".to_owned()
-        } else {
-          unsafe { CStr::from_ptr(family_raw) }.to_str()?.to_owned()
-        };
-
-        let style = if style_raw.is_null() {
-          weight.to_string()
-        } else {
-          unsafe { CStr::from_ptr(style_raw) }.to_str()?.to_owned()
-        };
-
-        let postscript = if psname_raw.is_null() {
-          format!("{}-{}", family, style).to_owned()
-        } else {
-          unsafe { CStr::from_ptr(psname_raw) }.to_str()?.to_owned()
-        };
-
-        fonts.push(FontDescriptor {
-          path: PathBuf::from(path),
-          postscript,
-          family,
-          style,
-          weight: FontWeight::from(weight),
-          width: FontWidth::from(width),
-          italic: slant_raw == FC_SLANT_ITALIC,
+      let Some(path) = Self::get_string(pattern, FC_FILE, 0) else {
+        scan.skipped.push(SkippedFont {
+          path: None,
+          reason: SkipReason::MissingProperty,
+          detail: Some("file".to_owned()),
         });
+        continue;
+      };
+
+      let path = Path::new(OsStr::from_bytes(path.to_bytes()));
+      let file = match &file {
+        Some((cached, data)) if cached == path => data,
+        _ => &file.insert((path.to_owned(), OnceCell::new())).1,
+      };
+      match Self::get_font(pattern, path, file) {
+        Ok(font) => scan.fonts.push(font),
+        Err(skipped) => scan.skipped.push(skipped),
       }
     }
 
     unsafe { FcFontSetDestroy(font_set) }
 
-    Ok(fonts)
+    Ok(scan)
   }
 
-  fn get_font_paths(&self) -> Result<Vec<PathBuf>, PlatformFontProviderErr> {
+  fn get_font_paths(&self) -> Result<Vec<PathBuf>, FontProviderErr> {
     let mut result: Vec<PathBuf> = vec![];
     let paths = unsafe { FcConfigGetFontDirs(self.config) };
 
     if paths.is_null() {
-      return Err(PlatformFontProviderErr::FontDirsEmpty("FcConfigGetFontDirs failed".to_owned()));
+      return Err(
+        PlatformFontProviderErr::FontDirsEmpty("FcConfigGetFontDirs failed".to_owned()).into(),
+      );
     }
 
     unsafe { FcStrListFirst(paths) };
@@ -306,7 +516,8 @@ impl FontProvider for PlatformFontProvider {
         break;
       }
 
-      result.push(PathBuf::from(unsafe { CStr::from_ptr(next as *const c_char) }.to_str()?));
+      let path = unsafe { CStr::from_ptr(next as *const c_char) }.to_str();
+      result.push(PathBuf::from(path.map_err(PlatformFontProviderErr::from)?));
     }
 
     unsafe { FcStrListDone(paths) };
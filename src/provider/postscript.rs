@@ -0,0 +1,153 @@
+//! PostScript name lookup and synthesis following Adobe Technical Note #5902.
+
+use crate::provider::{
+  sfnt::{find_name, read_u16},
+  FontDescriptor,
+};
+use std::{collections::BTreeMap, path::PathBuf};
+use ttf_parser::{name_id, Face, Tag};
+
+/// Longest PostScript name allowed by Adobe Technical Note #5902.
+const MAX_LENGTH: usize = 63;
+const FVAR: Tag = Tag::from_bytes(b"fvar");
+
+/// A PostScript name used by more than one font, together with the names handed out.
+pub struct PostscriptCollision {
+  pub postscript: String,
+  pub fonts: Vec<(PathBuf, String)>,
+}
+
+/// Reads the PostScript name of the face at `index` from the raw font data.
+///
+/// The upper 16 bits of `index` select a named instance of a variable font, like fontconfig does.
+/// When the font does not define the name, one is built from its English family and style names.
+pub fn read(data: &[u8], index: u32) -> Option<String> {
+  let face = Face::parse(data, index & 0xFFFF).ok()?;
+
+  match index >> 16 {
+    0 => find_name(&face, name_id::POST_SCRIPT_NAME)
+      .map(|name| sanitize(&name))
+      .filter(|name| !name.is_empty())
+      .or_else(|| {
+        let family = find_name(&face, name_id::TYPOGRAPHIC_FAMILY)
+          .or_else(|| find_name(&face, name_id::FAMILY))?;
+        let style = find_name(&face, name_id::TYPOGRAPHIC_SUBFAMILY)
+          .or_else(|| find_name(&face, name_id::SUBFAMILY))
+          .unwrap_or_default();
+        synthesize(&family, &style)
+      }),
+    instance => read_instance(&face, instance - 1),
+  }
+}
+
+/// Builds a PostScript name out of a family and style name.
+pub fn synthesize(family: &str, style: &str) -> Option<String> {
+  let family = alphanumeric(family);
+  let style = alphanumeric(style);
+
+  if family.is_empty() {
+    return None;
+  }
+
+  let name = if style.is_empty() { family } else { format!("{family}-{style}") };
+  Some(shorten(name))
+}
+
+/// Renames fonts sharing a PostScript name so that every name in `fonts` is unique.
+///
+/// In each group the font with the lowest path and face index keeps its name, the others get a
+/// suffix derived from their path, so the outcome does not depend on the scan order.
+pub fn disambiguate(fonts: &mut [FontDescriptor]) -> Vec<PostscriptCollision> {
+  let mut groups = BTreeMap::<String, Vec<usize>>::new();
+  for (i, font) in fonts.iter().enumerate() {
+    groups.entry(font.postscript.clone()).or_default().push(i);
+  }
+
+  let mut collisions = vec![];
+  for (postscript, mut group) in groups.into_iter().filter(|(_, group)| group.len() > 1) {
+    group.sort_by(|&a, &b| (&fonts[a].path, fonts[a].index).cmp(&(&fonts[b].path, fonts[b].index)));
+
+    for &i in group.iter().skip(1) {
+      let font = &mut fonts[i];
+      let suffix =
+        format!("_{:08X}", hash(&format!("{}#{}", font.path.to_string_lossy(), font.index)));
+      let mut name = postscript.clone();
+      name.truncate(MAX_LENGTH - suffix.len());
+      name.push_str(&suffix);
+      font.postscript = name;
+    }
+
+    log::warn!("PostScript name {} is used by {} fonts", postscript, group.len());
+    collisions.push(PostscriptCollision {
+      fonts: group.iter().map(|&i| (fonts[i].path.clone(), fonts[i].postscript.clone())).collect(),
+      postscript,
+    });
+  }
+
+  collisions
+}
+
+fn read_instance(face: &Face<'_>, instance: u32) -> Option<String> {
+  let fvar = face.raw_face().table(FVAR)?;
+  let field = |offset: usize| read_u16(fvar, offset);
+
+  let axes_offset = field(4)? as usize;
+  let axis_count = field(8)? as usize;
+  let axis_size = field(10)? as usize;
+  let instance_count = field(12)? as u32;
+  let instance_size = field(14)? as usize;
+
+  if instance >= instance_count {
+    return None;
+  }
+
+  let record = axes_offset + axis_count * axis_size + instance as usize * instance_size;
+  let coordinates_end = record + 4 + axis_count * 4;
+
+  // the postScriptNameID field is optional and signalled by the record size
+  if instance_size >= axis_count * 4 + 6 {
+    if let Some(name) = field(coordinates_end)
+      .filter(|&id| id != 0xFFFF)
+      .and_then(|id| find_name(face, id))
+      .map(|name| sanitize(&name))
+      .filter(|name| !name.is_empty())
+    {
+      return Some(name);
+    }
+  }
+
+  let prefix = find_name(face, name_id::VARIATIONS_POST_SCRIPT_NAME_PREFIX)
+    .or_else(|| find_name(face, name_id::TYPOGRAPHIC_FAMILY))
+    .or_else(|| find_name(face, name_id::FAMILY))?;
+  let subfamily = find_name(face, field(record)?).unwrap_or_default();
+
+  synthesize(&prefix, &subfamily)
+}
+
+/// Keeps only the characters allowed in a PostScript name.
+pub fn sanitize(name: &str) -> String {
+  let name: String =
+    name.chars().filter(|c| matches!(c, '!'..='~') && !"[](){}<>/%".contains(*c)).collect();
+  shorten(name)
+}
+
+fn alphanumeric(name: &str) -> String {
+  name.chars().filter(char::is_ascii_alphanumeric).collect()
+}
+
+/// Applies the "last resort" rule for names longer than 63 characters.
+pub fn shorten(name: String) -> String {
+  if name.len() <= MAX_LENGTH {
+    return name;
+  }
+
+  let suffix = format!("-{:08X}...", hash(&name));
+  let mut prefix = name;
+  prefix.truncate(MAX_LENGTH - suffix.len());
+  prefix + &suffix
+}
+
+/// 32-bit FNV-1a, stable across runs and platforms.
+fn hash(value: &str) -> u32 {
+  value.bytes().fold(0x811c_9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
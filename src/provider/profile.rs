@@ -0,0 +1,52 @@
+//! Named font sets of client projects, one of which is handed to Figma at a time.
+
+use crate::provider::FontDescriptor;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProfileConfig {
+  /// Fonts of the project, pinned versions included. They win over every other copy while the
+  /// profile is active and are not listed otherwise.
+  pub directories: Vec<PathBuf>,
+  /// PostScript or family names of the other fonts listed, `*` matching any characters. Every font
+  /// is listed when empty.
+  pub allow: Vec<String>,
+  /// PostScript or family names of fonts never listed, winning over `allow`.
+  pub deny: Vec<String>,
+}
+
+impl ProfileConfig {
+  /// Whether a font that is not part of the profile's own directories is listed.
+  pub fn admits(&self, font: &FontDescriptor) -> bool {
+    let matches = |pattern: &String| {
+      wildcard_match(pattern, &font.postscript) || wildcard_match(pattern, &font.family)
+    };
+    (self.allow.is_empty() || self.allow.iter().any(matches)) && !self.deny.iter().any(matches)
+  }
+}
+
+/// Matches `name` against `pattern`, `*` matching any run of characters, ignoring case.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+  let pattern = pattern.to_lowercase();
+  let name = name.to_lowercase();
+  let mut parts = pattern.split('*');
+
+  // there always is a first part, empty when the pattern starts with `*`
+  let first = parts.next().unwrap_or_default();
+  let Some(mut rest) = name.strip_prefix(first) else {
+    return false;
+  };
+  let Some(last) = parts.next_back() else {
+    return rest.is_empty();
+  };
+
+  for part in parts {
+    match rest.find(part) {
+      Some(start) => rest = &rest[start + part.len()..],
+      None => return false,
+    }
+  }
+  rest.len() >= last.len() && rest.ends_with(last)
+}
@@ -0,0 +1,194 @@
+//! Helpers for reading values straight out of TrueType/OpenType data.
+
+use std::{
+  fs::File,
+  io::{self, Read, Seek, SeekFrom},
+  path::Path,
+};
+use ttf_parser::{Face, PlatformId, Tag};
+
+/// Windows language ID of US English.
+const LANGUAGE_EN_US: u16 = 0x0409;
+const HEAD: Tag = Tag::from_bytes(b"head");
+const TTCF: u32 = u32::from_be_bytes(*b"ttcf");
+
+// composite glyph flags
+pub const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+pub const WE_HAVE_A_SCALE: u16 = 0x0008;
+pub const MORE_COMPONENTS: u16 = 0x0020;
+pub const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+pub const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+pub const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+/// Reads a big-endian `u16` at `offset`.
+pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+  data.get(offset..offset + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Reads a big-endian `u32` at `offset`.
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+  data
+    .get(offset..offset + 4)
+    .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Looks up a name record, preferring US English over the other languages.
+pub fn find_name(face: &Face<'_>, id: u16) -> Option<String> {
+  let names = face.names().into_iter().filter(|name| name.name_id == id);
+
+  let mut fallback = None;
+  for name in names {
+    let english = match name.platform_id {
+      PlatformId::Windows => name.language_id == LANGUAGE_EN_US,
+      PlatformId::Macintosh => name.language_id == 0,
+      _ => false,
+    };
+
+    let value = match name.platform_id {
+      // Mac Roman matches ASCII for everything names are usually made of
+      PlatformId::Macintosh if name.encoding_id == 0 => {
+        Some(name.name.iter().map(|&c| if c.is_ascii() { c as char } else { '?' }).collect())
+      }
+      _ => name.to_string(),
+    };
+
+    match value {
+      Some(value) if english => return Some(value),
+      Some(value) if fallback.is_none() => fallback = Some(value),
+      _ => {}
+    }
+  }
+
+  fallback
+}
+
+/// Reads `head.fontRevision` of the face at `index`.
+pub fn font_revision(data: &[u8], index: u32) -> Option<f32> {
+  let face = Face::parse(data, index & 0xFFFF).ok()?;
+  let head = face.raw_face().table(HEAD)?;
+  // 16.16 fixed point right after the table version
+  read_u32(head, 4).map(|revision| revision as i32 as f32 / 65536.0)
+}
+
+/// Reads `len` bytes at `offset` of `file`, fewer when the file ends before.
+fn read_at(file: &mut File, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+  file.seek(SeekFrom::Start(offset))?;
+  let mut data = vec![];
+  file.take(len).read_to_end(&mut data)?;
+  Ok(data)
+}
+
+/// Reads the sfnt version and the 16-byte table records of face `index` of `file`.
+fn read_directory(file: &mut File, index: u32) -> io::Result<Option<(u32, Vec<u8>)>> {
+  let directory = match read_u32(&read_at(file, 0, 4)?, 0) {
+    Some(TTCF) => match read_u32(&read_at(file, 12 + 4 * index as u64, 4)?, 0) {
+      Some(offset) => offset as u64,
+      None => return Ok(None),
+    },
+    Some(_) => 0,
+    None => return Ok(None),
+  };
+
+  let header = read_at(file, directory, 12)?;
+  let (Some(version), Some(count)) = (read_u32(&header, 0), read_u16(&header, 4)) else {
+    return Ok(None);
+  };
+  Ok(Some((version, read_at(file, directory + 12, count as u64 * 16)?)))
+}
+
+/// Number of faces in the file at `path`, reading only its header.
+pub fn face_count(path: &Path) -> io::Result<u32> {
+  let header = read_at(&mut File::open(path)?, 0, 12)?;
+  Ok(ttf_parser::fonts_in_collection(&header).unwrap_or(1))
+}
+
+/// Reads the table `tag` of face `index` straight from the file at `path`, without reading the
+/// rest of the file.
+pub fn read_table(path: &Path, index: u32, tag: Tag) -> io::Result<Option<Vec<u8>>> {
+  let mut file = File::open(path)?;
+  let Some((_, records)) = read_directory(&mut file, index)? else {
+    return Ok(None);
+  };
+  for record in records.as_chunks::<16>().0 {
+    if record[..4] == tag.to_bytes() {
+      let (Some(offset), Some(len)) = (read_u32(record, 8), read_u32(record, 12)) else {
+        return Ok(None);
+      };
+      return read_at(&mut file, offset as u64, len as u64).map(Some);
+    }
+  }
+  Ok(None)
+}
+
+/// Reads the tables `tags` of face `index` of the file at `path`, the ones it has, into a font of
+/// its own, without reading the rest of the file.
+pub fn read_face(path: &Path, index: u32, tags: &[Tag]) -> io::Result<Option<Vec<u8>>> {
+  let mut file = File::open(path)?;
+  let Some((version, records)) = read_directory(&mut file, index)? else {
+    return Ok(None);
+  };
+  let mut tables = vec![];
+  for record in records.as_chunks::<16>().0 {
+    let tag = Tag::from_bytes(&[record[0], record[1], record[2], record[3]]);
+    if tags.contains(&tag) {
+      let (Some(offset), Some(len)) = (read_u32(record, 8), read_u32(record, 12)) else {
+        return Ok(None);
+      };
+      tables.push((tag, read_at(&mut file, offset as u64, len as u64)?));
+    }
+  }
+  Ok(Some(write_sfnt(version, tables)))
+}
+
+/// Assembles an sfnt out of `tables`, sorted by tag and with fresh checksums.
+pub fn write_sfnt(version: u32, mut tables: Vec<(Tag, Vec<u8>)>) -> Vec<u8> {
+  tables.sort_by_key(|(tag, _)| tag.to_bytes());
+
+  let count = tables.len() as u16;
+  let entry_selector = 15 - count.max(1).leading_zeros() as u16;
+  let search_range: u16 = 16 << entry_selector;
+
+  let mut font = vec![];
+  font.extend(version.to_be_bytes());
+  font.extend(count.to_be_bytes());
+  font.extend(search_range.to_be_bytes());
+  font.extend(entry_selector.to_be_bytes());
+  font.extend((count * 16 - search_range).to_be_bytes());
+
+  let mut offset = 12 + 16 * tables.len();
+  let mut head_offset = None;
+  for (tag, table) in &mut tables {
+    if *tag == HEAD {
+      // the adjustment is computed over the whole font with this field zeroed
+      table[8..12].fill(0);
+      head_offset = Some(offset);
+    }
+
+    font.extend(tag.to_bytes());
+    font.extend(checksum(table).to_be_bytes());
+    font.extend((offset as u32).to_be_bytes());
+    font.extend((table.len() as u32).to_be_bytes());
+    offset += table.len().next_multiple_of(4);
+  }
+
+  for (_, table) in &tables {
+    font.extend_from_slice(table);
+    font.resize(font.len().next_multiple_of(4), 0);
+  }
+
+  if let Some(head) = head_offset {
+    let adjustment = 0xB1B0_AFBA_u32.wrapping_sub(checksum(&font));
+    font[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+  }
+
+  font
+}
+
+/// Sums the data as big-endian `u32`s, padding the last one with zeros.
+pub fn checksum(data: &[u8]) -> u32 {
+  data.chunks(4).fold(0u32, |sum, chunk| {
+    let mut word = [0; 4];
+    word[..chunk.len()].copy_from_slice(chunk);
+    sum.wrapping_add(u32::from_be_bytes(word))
+  })
+}
@@ -1,7 +1,15 @@
+mod coverage;
 mod fontfile;
 mod fontfiles;
+mod fontsubset;
+mod preview;
 mod update;
+mod version;
 
+pub use coverage::CoverageQuery;
 pub use fontfile::FontFileQuery;
 pub use fontfiles::FontFilesQuery;
+pub use fontsubset::FontSubsetQuery;
+pub use preview::PreviewQuery;
 pub use update::UpdateQuery;
+pub use version::VersionQuery;
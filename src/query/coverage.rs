@@ -0,0 +1,11 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CoverageQuery {
+  #[serde(default)]
+  pub text: String,
+  #[serde(default)]
+  pub unicodes: String,
+}
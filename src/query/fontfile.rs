@@ -1,9 +1,12 @@
 use serde::Deserialize;
 use std::path::PathBuf;
+use utoipa::IntoParams;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct FontFileQuery {
   #[serde(rename = "freetype_minimum_api_version")]
   pub ft_min_ver: usize,
+  #[param(value_type = String)]
   pub file: PathBuf,
 }
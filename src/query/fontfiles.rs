@@ -1,8 +1,12 @@
 use serde::Deserialize;
+use utoipa::IntoParams;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct FontFilesQuery {
   #[serde(rename = "freetype_minimum_api_version")]
   pub ft_min_ver: usize,
   pub isolate: bool,
+  /// Protocol version to answer with instead of the negotiated one.
+  pub version: Option<usize>,
 }
@@ -0,0 +1,12 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FontSubsetQuery {
+  pub postscript: String,
+  #[serde(default)]
+  pub text: String,
+  #[serde(default)]
+  pub unicodes: String,
+}
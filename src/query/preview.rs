@@ -0,0 +1,32 @@
+use crate::preview::PreviewFormat;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PreviewQuery {
+  #[serde(default = "default_text")]
+  pub text: String,
+  #[serde(default = "default_size")]
+  pub size: f32,
+  /// Comma separated sizes of a waterfall, replacing `size`.
+  pub sizes: Option<String>,
+  #[serde(default = "default_color")]
+  pub color: String,
+  #[serde(default)]
+  pub axes: String,
+  #[serde(default)]
+  pub format: PreviewFormat,
+}
+
+fn default_text() -> String {
+  "The quick brown fox jumps over the lazy dog".to_owned()
+}
+
+fn default_size() -> f32 {
+  32.0
+}
+
+fn default_color() -> String {
+  "000000".to_owned()
+}
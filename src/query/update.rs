@@ -1,6 +1,9 @@
 use serde::Deserialize;
+use utoipa::IntoParams;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct UpdateQuery {
+  /// Protocol version the client expects.
   pub version: usize,
 }
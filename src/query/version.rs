@@ -0,0 +1,9 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VersionQuery {
+  /// Protocol version the client expects, the negotiated one is reported when missing.
+  pub version: Option<usize>,
+}
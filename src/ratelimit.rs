@@ -0,0 +1,144 @@
+//! Token buckets per Figma route and a cap on concurrent font downloads, so that a runaway tab
+//! cannot keep ffh busy.
+
+use serde::Deserialize;
+use std::{
+  collections::{BTreeMap, HashMap},
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+  },
+  time::Instant,
+};
+use thiserror::Error;
+
+/// Routes streaming fonts, counted against the download cap.
+pub const DOWNLOAD_ROUTES: [&str; 2] = ["/figma/font-file", "/figma/font-subset"];
+
+#[derive(Error, Debug)]
+pub enum RateLimitErr {
+  #[error("Too many requests to {route}, retry after {retry_after}s")]
+  Rate { route: String, retry_after: u64 },
+
+  #[error("Too many font downloads at once, at most {max}")]
+  Downloads { max: usize },
+}
+
+impl RateLimitErr {
+  /// Seconds until the request may succeed.
+  pub fn retry_after(&self) -> u64 {
+    match self {
+      Self::Rate { retry_after, .. } => *retry_after,
+      Self::Downloads { .. } => 1,
+    }
+  }
+}
+
+/// A token bucket, refilled with `rate` tokens per second up to `burst` tokens.
+#[derive(Deserialize, Copy, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RouteLimit {
+  pub rate: f64,
+  pub burst: u32,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LimitConfig {
+  pub enabled: bool,
+  /// Limits by route path, routes left out are not limited.
+  pub routes: BTreeMap<String, RouteLimit>,
+  /// Font downloads served at once, unlimited when 0.
+  pub max_downloads: usize,
+}
+
+impl Default for LimitConfig {
+  fn default() -> Self {
+    let routes = [
+      // every call serializes the whole font list
+      ("/figma/font-files", RouteLimit { rate: 2.0, burst: 10 }),
+      // documents load their fonts all at once
+      ("/figma/font-file", RouteLimit { rate: 50.0, burst: 200 }),
+      ("/figma/font-subset", RouteLimit { rate: 20.0, burst: 50 }),
+    ];
+    let routes = routes.into_iter().map(|(route, limit)| (route.to_owned(), limit)).collect();
+    Self { enabled: true, routes, max_downloads: 8 }
+  }
+}
+
+struct TokenBucket {
+  limit: RouteLimit,
+  /// Tokens left and when they were counted.
+  tokens: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+  fn new(limit: RouteLimit) -> Self {
+    Self { limit, tokens: Mutex::new((limit.burst as f64, Instant::now())) }
+  }
+
+  /// Takes a token, or returns the seconds until the next one.
+  fn take(&self) -> Result<(), u64> {
+    let mut tokens = self.tokens.lock().unwrap();
+    let (left, counted) = &mut *tokens;
+    let now = Instant::now();
+    let refilled = now.duration_since(*counted).as_secs_f64() * self.limit.rate;
+    *left = (*left + refilled).min(self.limit.burst as f64);
+    *counted = now;
+
+    if *left >= 1.0 {
+      *left -= 1.0;
+      Ok(())
+    } else {
+      // a rate of 0 never refills, saturating to u64::MAX
+      Err(((1.0 - *left) / self.limit.rate).ceil().max(1.0) as u64)
+    }
+  }
+}
+
+/// A font download in progress, ending when dropped.
+pub struct DownloadPermit(Arc<AtomicUsize>);
+
+impl Drop for DownloadPermit {
+  fn drop(&mut self) {
+    self.0.fetch_sub(1, Ordering::Relaxed);
+  }
+}
+
+pub struct RateLimiter {
+  buckets: HashMap<String, TokenBucket>,
+  downloads: Arc<AtomicUsize>,
+  max_downloads: usize,
+}
+
+impl RateLimiter {
+  pub fn new(config: &LimitConfig) -> Self {
+    let buckets = (config.routes.iter())
+      .filter(|_| config.enabled)
+      .map(|(route, limit)| (route.clone(), TokenBucket::new(*limit)))
+      .collect();
+    let max_downloads = if config.enabled { config.max_downloads } else { 0 };
+    Self { buckets, downloads: Arc::default(), max_downloads }
+  }
+
+  /// Admits a request to `route`, with a permit to hold while a download is streamed.
+  pub fn admit(&self, route: &str) -> Result<Option<DownloadPermit>, RateLimitErr> {
+    let permit = if self.max_downloads > 0 && DOWNLOAD_ROUTES.contains(&route) {
+      let active = self.downloads.fetch_add(1, Ordering::Relaxed);
+      // dropping the permit gives the download back when over the cap
+      let permit = DownloadPermit(self.downloads.clone());
+      if active >= self.max_downloads {
+        return Err(RateLimitErr::Downloads { max: self.max_downloads });
+      }
+      Some(permit)
+    } else {
+      None
+    };
+
+    if let Some(bucket) = self.buckets.get(route) {
+      let route = route.to_owned();
+      bucket.take().map_err(|retry_after| RateLimitErr::Rate { route, retry_after })?;
+    }
+    Ok(permit)
+  }
+}
@@ -1,4 +1,79 @@
+pub mod coverage;
+pub mod diagnostics;
+pub mod font;
+pub mod fontcoverage;
 pub mod fontfile;
 pub mod fontfiles;
+pub mod fontmetadata;
+pub mod fonts;
+pub mod fontsubset;
+pub mod metrics;
+pub mod notfound;
+pub mod openapi;
+pub mod preview;
+pub mod profile;
+pub mod status;
 pub mod update;
 pub mod version;
+
+use crate::error::ApiError;
+use actix_web::web;
+
+/// Registers the routes served to Figma.
+pub fn figma(cfg: &mut web::ServiceConfig) {
+  cfg
+    // answer malformed queries with ErrorDTO
+    .app_data(query_config())
+    // register version
+    .service(version::handler)
+    // register font_file
+    .service(fontfile::handler)
+    // register font_subset
+    .service(fontsubset::handler)
+    // register font_files
+    .service(fontfiles::handler)
+    // register update
+    .service(update::handler);
+}
+
+/// Figma routes that negotiate their encoding themselves, font files coming with gzip copies of
+/// their own and being served in ranges.
+pub const UNCOMPRESSED: &[&str] = &["/figma/font-file"];
+
+/// Registers the routes of the loopback-only admin listener.
+pub fn admin(cfg: &mut web::ServiceConfig) {
+  cfg
+    // answer malformed queries with ErrorDTO
+    .app_data(query_config())
+    // register metrics
+    .service(metrics::handler)
+    // register diagnostics
+    .service(diagnostics::handler)
+    // register fonts
+    .service(fonts::handler)
+    // register font
+    .service(font::handler)
+    // register font_coverage
+    .service(fontcoverage::handler)
+    // register font_metadata
+    .service(fontmetadata::handler)
+    // register preview
+    .service(preview::handler)
+    // register coverage
+    .service(coverage::handler)
+    // register profile
+    .service(profile::handler)
+    // register activate_profile
+    .service(profile::activate)
+    // register deactivate_profile
+    .service(profile::deactivate)
+    // register status
+    .service(status::handler)
+    // register openapi
+    .service(openapi::handler);
+}
+
+/// Query extractor settings answering parse failures with an [`ApiError`].
+pub fn query_config() -> web::QueryConfig {
+  web::QueryConfig::default().error_handler(ApiError::query)
+}
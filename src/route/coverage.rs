@@ -0,0 +1,32 @@
+use crate::{
+  dto::{ErrorDTO, FontDTO},
+  error::{ApiError, ErrorCode},
+  provider::coverage::Coverage,
+  query::CoverageQuery,
+  ServerState,
+};
+use actix_web::{get, web};
+
+/// coverage handler, lists the fonts covering every requested character
+#[utoipa::path(
+  operation_id = "coverage",
+  tag = "admin",
+  params(CoverageQuery),
+  responses(
+    (status = 200, description = "Fonts covering every character", body = Vec<FontDTO>),
+    (status = 400, description = "Invalid or missing characters", body = ErrorDTO),
+  ),
+)]
+#[get("/coverage")]
+pub async fn handler(
+  web::Query(query): web::Query<CoverageQuery>,
+  state: web::Data<ServerState>,
+) -> Result<web::Json<Vec<FontDTO>>, ApiError> {
+  let characters = Coverage::parse(&query.text, &query.unicodes)
+    .map_err(|e| ApiError::new(ErrorCode::InvalidRequest, e))?;
+  if characters.is_empty() {
+    return Err(ApiError::new(ErrorCode::InvalidRequest, "Neither text nor unicodes given"));
+  }
+
+  Ok(web::Json(state.database.covering(&characters).into_iter().map(FontDTO::from).collect()))
+}
@@ -0,0 +1,19 @@
+use crate::{dto::DiagnosticsDTO, ServerState};
+use actix_web::{get, web, Result};
+
+/// diagnostics handler
+#[utoipa::path(
+  operation_id = "diagnostics",
+  tag = "admin",
+  responses(
+    (
+      status = 200,
+      description = "Fonts left out of the font list and why",
+      body = DiagnosticsDTO,
+    ),
+  ),
+)]
+#[get("/diagnostics")]
+pub async fn handler(state: web::Data<ServerState>) -> Result<web::Json<DiagnosticsDTO>> {
+  Ok(web::Json(DiagnosticsDTO::from(&state.database)))
+}
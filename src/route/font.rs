@@ -0,0 +1,28 @@
+use crate::{
+  dto::{ErrorDTO, FontDTO},
+  error::{ApiError, ErrorCode},
+  ServerState,
+};
+use actix_web::{get, web};
+
+/// font handler, fonts are identified by their PostScript name
+#[utoipa::path(
+  operation_id = "font",
+  tag = "admin",
+  params(("postscript" = String, Path, description = "PostScript name of the font")),
+  responses(
+    (status = 200, description = "The font", body = FontDTO),
+    (status = 404, description = "Font not found", body = ErrorDTO),
+  ),
+)]
+#[get("/fonts/{postscript}")]
+pub async fn handler(
+  postscript: web::Path<String>,
+  state: web::Data<ServerState>,
+) -> Result<web::Json<FontDTO>, ApiError> {
+  if let Some(desc) = state.database.iter().find(|f| f.postscript == *postscript) {
+    Ok(web::Json(FontDTO::from(desc)))
+  } else {
+    Err(ErrorCode::FontNotFound.into())
+  }
+}
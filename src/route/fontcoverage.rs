@@ -0,0 +1,28 @@
+use crate::{
+  dto::{CoverageDTO, ErrorDTO},
+  error::{ApiError, ErrorCode},
+  ServerState,
+};
+use actix_web::{get, web};
+
+/// font_coverage handler
+#[utoipa::path(
+  operation_id = "font_coverage",
+  tag = "admin",
+  params(("postscript" = String, Path, description = "PostScript name of the font")),
+  responses(
+    (status = 200, description = "Unicode ranges the font maps to glyphs", body = CoverageDTO),
+    (status = 404, description = "Font not found", body = ErrorDTO),
+  ),
+)]
+#[get("/fonts/{postscript}/coverage")]
+pub async fn handler(
+  postscript: web::Path<String>,
+  state: web::Data<ServerState>,
+) -> Result<web::Json<CoverageDTO>, ApiError> {
+  if let Some(desc) = state.database.iter().find(|f| f.postscript == *postscript) {
+    Ok(web::Json(CoverageDTO::from(desc)))
+  } else {
+    Err(ErrorCode::FontNotFound.into())
+  }
+}
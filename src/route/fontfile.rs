@@ -1,20 +1,73 @@
-use crate::{query::FontFileQuery, ServerState};
-use actix_files as fs;
-use actix_web::{error, get, web, Result};
+use crate::{
+  dto::ErrorDTO,
+  error::{ApiError, ErrorCode},
+  fontresponse::FontFile,
+  query::FontFileQuery,
+  ServerState,
+};
+use actix_web::{get, web, HttpRequest, HttpResponse};
 
 /// font_file handler
+#[utoipa::path(
+  operation_id = "font_file",
+  tag = "figma",
+  params(FontFileQuery),
+  responses(
+    (status = 200, description = "The font file", content_type = "font/ttf", body = Vec<u8>),
+    (
+      status = 206,
+      description = "A range of the font file",
+      content_type = "font/ttf",
+      body = Vec<u8>,
+    ),
+    (status = 304, description = "The font did not change"),
+    (status = 400, description = "Invalid query or unsupported FreeType version", body = ErrorDTO),
+    (
+      status = 403,
+      description = "The embedding permissions do not allow serving the font",
+      body = ErrorDTO,
+    ),
+    (status = 404, description = "No listed font has this path", body = ErrorDTO),
+    (status = 422, description = "The font failed validation", body = ErrorDTO),
+  ),
+)]
 #[get("/figma/font-file")]
 pub async fn handler(
+  req: HttpRequest,
   web::Query(query): web::Query<FontFileQuery>,
   state: web::Data<ServerState>,
-) -> Result<fs::NamedFile> {
+) -> Result<HttpResponse, ApiError> {
   if state.font_provider_api_version < query.ft_min_ver {
-    return Err(error::ErrorBadRequest("Unsupported FreeType version"));
+    return Err(ErrorCode::UnsupportedFreetype.into());
   }
 
   if let Some(desc) = state.database.iter().find(|f| f.path == query.file) {
-    Ok(fs::NamedFile::open(&desc.path)?)
+    if !state.database.is_servable(&desc.path) {
+      return Err(ErrorCode::EmbeddingRestricted.into());
+    }
+
+    state.usage.record(&desc.postscript, &desc.family);
+
+    let path = desc.path.clone();
+    let file_state = state.clone();
+    let sanitize = state.database.config().sanitize.clone();
+    let file = web::block(move || {
+      let (hashes, cache) = (&file_state.content_hashes, &file_state.font_cache);
+      // clean copies are validated while they are built
+      if sanitize.clean_copy {
+        return FontFile::clean(&path, hashes, cache);
+      }
+      if sanitize.enabled {
+        file_state.validations.check(&path, &path.metadata()?)?;
+      }
+      Ok(FontFile::open(&path, hashes, cache)?)
+    })
+    .await??;
+
+    let (response, bytes) = file.into_response(&req);
+    state.metrics.record_font_file_bytes(bytes);
+    Ok(response)
   } else {
-    Err(error::ErrorNotFound("File not found"))
+    Err(ErrorCode::FileNotFound.into())
   }
 }
@@ -1,41 +1,94 @@
 use crate::{
-  dto::{FontDescriptorDTO, FontFilesDTO},
+  dto::{AnyFontFilesDTO, ErrorDTO, FontDescriptorDTO, FontDescriptorV5DTO, FontFilesDTO},
+  error::{ApiError, ErrorCode},
+  protocol::ProtocolVersion,
+  provider::{localized, stat, FontDescriptor},
   query::FontFilesQuery,
   ServerState,
 };
-use actix_web::{error, get, web, Result};
-use std::{collections::HashMap, path::PathBuf};
+use actix_web::{get, web};
+use std::{collections::HashMap, time::UNIX_EPOCH};
 
 /// font_files handler
+#[utoipa::path(
+  operation_id = "font_files",
+  tag = "figma",
+  params(FontFilesQuery),
+  responses(
+    (
+      status = 200,
+      description = "Fonts by file, in the protocol version asked for",
+      body = AnyFontFilesDTO,
+    ),
+    (status = 400, description = "Invalid query or unsupported FreeType version", body = ErrorDTO),
+  ),
+)]
 #[get("/figma/font-files")]
 pub async fn handler(
   web::Query(query): web::Query<FontFilesQuery>,
   state: web::Data<ServerState>,
-) -> Result<web::Json<FontFilesDTO>> {
+) -> Result<web::Json<AnyFontFilesDTO>, ApiError> {
   if state.font_provider_api_version < query.ft_min_ver {
-    return Err(error::ErrorBadRequest("Unsupported FreeType version"));
+    return Err(ErrorCode::UnsupportedFreetype.into());
   }
 
-  let mut fonts = FontFilesDTO {
-    version: state.figma_api_version,
-    font_files: HashMap::<PathBuf, Vec<FontDescriptorDTO>>::new(),
-  };
+  let version = state.protocol.for_request(query.version);
+  Ok(web::Json(match version {
+    ProtocolVersion::V4 => AnyFontFilesDTO::V4(font_files(&state, version, v4)),
+    ProtocolVersion::V5 => AnyFontFilesDTO::V5(font_files(&state, version, v5)),
+  }))
+}
+
+fn font_files<D>(
+  state: &ServerState,
+  version: ProtocolVersion,
+  dto: impl Fn(&FontDescriptor) -> D,
+) -> FontFilesDTO<D> {
+  let mut fonts = FontFilesDTO { version: version.into(), font_files: HashMap::new() };
 
   for descriptor in state.database.iter() {
-    let font_desc = FontDescriptorDTO {
-      postscript: descriptor.postscript.clone(),
-      family: descriptor.family.clone(),
-      style: descriptor.style.clone(),
-      weight: descriptor.weight as usize,
-      stretch: descriptor.width as usize,
-      italic: descriptor.italic,
-    };
-
-    if let Some(font_vec) = fonts.font_files.get_mut(&descriptor.path) {
+    let font_desc = dto(descriptor);
+    let path = descriptor.path.to_string_lossy();
+
+    if let Some(font_vec) = fonts.font_files.get_mut(path.as_ref()) {
       font_vec.push(font_desc);
     } else {
-      fonts.font_files.insert(descriptor.path.clone(), vec![font_desc]);
+      fonts.font_files.insert(path.into_owned(), vec![font_desc]);
     }
   }
-  Ok(web::Json(fonts))
+  fonts
+}
+
+fn v4(descriptor: &FontDescriptor) -> FontDescriptorDTO {
+  FontDescriptorDTO {
+    postscript: descriptor.postscript.clone(),
+    family: descriptor.family.clone(),
+    style: descriptor.style.clone(),
+    weight: descriptor.weight as usize,
+    stretch: descriptor.width as usize,
+    italic: descriptor.italic,
+    source: descriptor.source.clone(),
+  }
+}
+
+fn v5(descriptor: &FontDescriptor) -> FontDescriptorV5DTO {
+  let english = [String::from("en")];
+  let mut base = v4(descriptor);
+  base.family =
+    localized::select(&descriptor.families, &english).unwrap_or(&base.family).to_owned();
+  base.style = localized::select(&descriptor.styles, &english).unwrap_or(&base.style).to_owned();
+
+  // files updated in place since the scan report their current time, so that Figma reloads them
+  let (modified, _) = stat(&descriptor.path);
+  let modified_at = (modified.or(descriptor.modified))
+    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+    .map(|modified| modified.as_secs());
+
+  FontDescriptorV5DTO {
+    base,
+    localized_family: descriptor.family.clone(),
+    localized_style: descriptor.style.clone(),
+    modified_at,
+    user_installed: descriptor.user_installed,
+  }
 }
@@ -0,0 +1,36 @@
+use crate::{
+  dto::{ErrorDTO, FontMetadataDTO},
+  error::{ApiError, ErrorCode},
+  provider::metadata,
+  ServerState,
+};
+use actix_web::{get, web};
+
+/// font_metadata handler
+#[utoipa::path(
+  operation_id = "font_metadata",
+  tag = "admin",
+  params(("postscript" = String, Path, description = "PostScript name of the font")),
+  responses(
+    (status = 200, description = "Names, metrics and tables of the font", body = FontMetadataDTO),
+    (status = 404, description = "Font not found", body = ErrorDTO),
+    (status = 422, description = "The font could not be parsed", body = ErrorDTO),
+  ),
+)]
+#[get("/fonts/{postscript}/metadata")]
+pub async fn handler(
+  postscript: web::Path<String>,
+  state: web::Data<ServerState>,
+) -> Result<web::Json<FontMetadataDTO>, ApiError> {
+  let Some(desc) = state.database.iter().find(|f| f.postscript == *postscript) else {
+    return Err(ErrorCode::FontNotFound.into());
+  };
+  let (path, index) = (desc.path.clone(), desc.index);
+
+  let metadata =
+    web::block(move || std::fs::read(path).map(|data| metadata::inspect(&data, index)))
+      .await??
+      .map_err(|e| ApiError::new(ErrorCode::UnprocessableFont, e))?;
+
+  Ok(web::Json(FontMetadataDTO::new(desc, &metadata)))
+}
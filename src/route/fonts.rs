@@ -0,0 +1,13 @@
+use crate::{dto::FontDTO, ServerState};
+use actix_web::{get, web, Result};
+
+/// fonts handler
+#[utoipa::path(
+  operation_id = "fonts",
+  tag = "admin",
+  responses((status = 200, description = "Indexed fonts", body = Vec<FontDTO>)),
+)]
+#[get("/fonts")]
+pub async fn handler(state: web::Data<ServerState>) -> Result<web::Json<Vec<FontDTO>>> {
+  Ok(web::Json(state.database.iter().map(FontDTO::from).collect()))
+}
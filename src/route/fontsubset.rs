@@ -0,0 +1,111 @@
+use crate::{
+  dto::ErrorDTO,
+  error::{ApiError, ErrorCode},
+  fontresponse::{self, CACHE_CONTROL},
+  provider::coverage::Coverage,
+  query::FontSubsetQuery,
+  sanitize::SanitizeErr,
+  subset::{self, SubsetKey},
+  ServerState,
+};
+use actix_web::{
+  get,
+  http::{
+    header::{self, EntityTag},
+    StatusCode,
+  },
+  web, HttpRequest, HttpResponse,
+};
+use std::sync::Arc;
+
+/// font_subset handler, fonts are identified by their PostScript name
+#[utoipa::path(
+  operation_id = "font_subset",
+  tag = "figma",
+  params(FontSubsetQuery),
+  responses(
+    (
+      status = 200,
+      description = "The font with only the requested glyphs",
+      content_type = "font/ttf",
+      body = Vec<u8>,
+    ),
+    (status = 304, description = "The subset did not change"),
+    (status = 400, description = "Invalid query or characters", body = ErrorDTO),
+    (
+      status = 403,
+      description = "The embedding permissions do not allow serving the font",
+      body = ErrorDTO,
+    ),
+    (status = 404, description = "Font not found", body = ErrorDTO),
+    (
+      status = 422,
+      description = "The font failed validation or could not be subset",
+      body = ErrorDTO,
+    ),
+  ),
+)]
+#[get("/figma/font-subset")]
+pub async fn handler(
+  req: HttpRequest,
+  web::Query(query): web::Query<FontSubsetQuery>,
+  state: web::Data<ServerState>,
+) -> Result<HttpResponse, ApiError> {
+  let characters = Coverage::parse(&query.text, &query.unicodes)
+    .map_err(|e| ApiError::new(ErrorCode::InvalidRequest, e))?;
+  if characters.is_empty() {
+    return Err(ApiError::new(ErrorCode::InvalidRequest, "Neither text nor unicodes given"));
+  }
+
+  let Some(desc) = state.database.iter().find(|f| f.postscript == query.postscript) else {
+    return Err(ErrorCode::FontNotFound.into());
+  };
+  if !state.database.is_servable(&desc.path) {
+    return Err(ErrorCode::EmbeddingRestricted.into());
+  }
+  let (path, index) = (desc.path.clone(), desc.index);
+
+  let hash_state = state.clone();
+  let hash_path = path.clone();
+  let source = web::block(move || {
+    let metadata = hash_path.metadata()?;
+    hash_state.content_hashes.etag(&hash_path, &metadata)
+  })
+  .await??;
+
+  let key = SubsetKey { source: source.tag().to_owned(), index, characters };
+  let etag = EntityTag::new_strong(key.etag());
+
+  let mut res = HttpResponse::Ok();
+  res
+    .insert_header((header::ETAG, etag.to_string()))
+    .insert_header((header::CACHE_CONTROL, CACHE_CONTROL));
+  if fontresponse::is_not_modified(&req, &etag) {
+    return Ok(res.status(StatusCode::NOT_MODIFIED).finish());
+  }
+
+  let subset = match state.subsets.get(&key) {
+    Some(subset) => subset,
+    None => {
+      let characters = key.characters.clone();
+      let subset_state = state.clone();
+      let sanitize = state.database.config().sanitize.enabled;
+      let subset = web::block(move || {
+        if sanitize {
+          subset_state.validations.check(&path, &path.metadata()?)?;
+        }
+        let data = std::fs::read(&path)?;
+        Ok::<_, SanitizeErr>(subset::subset(&data, index, &characters))
+      })
+      .await??
+      .map_err(|e| ApiError::new(ErrorCode::UnprocessableFont, e))?;
+
+      let subset = Arc::new(subset);
+      state.subsets.insert(key, subset.clone());
+      subset
+    }
+  };
+
+  state.metrics.record_font_subset_bytes(subset.data.len() as u64);
+  Ok(res.insert_header((header::CONTENT_TYPE, subset.content_type)).body(subset.data.clone()))
+}
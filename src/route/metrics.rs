@@ -0,0 +1,22 @@
+use crate::ServerState;
+use actix_web::{get, web, HttpResponse};
+
+/// metrics handler
+#[utoipa::path(
+  operation_id = "metrics",
+  tag = "admin",
+  responses(
+    (
+      status = 200,
+      description = "Prometheus metrics",
+      content_type = "text/plain",
+      body = String,
+    ),
+  ),
+)]
+#[get("/metrics")]
+pub async fn handler(state: web::Data<ServerState>) -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type("text/plain; version=0.0.4; charset=utf-8")
+    .body(state.metrics.render(&state.database, &state.font_cache))
+}
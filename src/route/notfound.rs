@@ -0,0 +1,7 @@
+use crate::error::{ApiError, ErrorCode};
+use actix_web::HttpResponse;
+
+/// not_found handler, answers the paths no route matches
+pub async fn handler() -> Result<HttpResponse, ApiError> {
+  Err(ErrorCode::NotFound.into())
+}
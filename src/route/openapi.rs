@@ -0,0 +1,13 @@
+use crate::openapi;
+use actix_web::{get, HttpResponse};
+
+/// openapi handler
+#[utoipa::path(
+  operation_id = "openapi",
+  tag = "admin",
+  responses((status = 200, description = "This document", content_type = "application/json")),
+)]
+#[get("/openapi.json")]
+pub async fn handler() -> HttpResponse {
+  HttpResponse::Ok().json(openapi::document())
+}
@@ -0,0 +1,66 @@
+use crate::{
+  dto::ErrorDTO,
+  error::{ApiError, ErrorCode},
+  preview::{self, Color, Preview},
+  query::PreviewQuery,
+  ServerState,
+};
+use actix_web::{get, http::header, web, HttpResponse};
+
+/// preview handler, renders sample text with a font
+#[utoipa::path(
+  operation_id = "preview",
+  tag = "admin",
+  params(("postscript" = String, Path, description = "PostScript name of the font"), PreviewQuery),
+  responses(
+    (
+      status = 200,
+      description = "The text rendered in the font",
+      content_type = "image/png",
+      body = Vec<u8>,
+    ),
+    (status = 400, description = "Invalid size, color or axes", body = ErrorDTO),
+    (
+      status = 403,
+      description = "The embedding permissions do not allow serving the font",
+      body = ErrorDTO,
+    ),
+    (status = 404, description = "Font not found", body = ErrorDTO),
+    (status = 422, description = "The font could not be rendered", body = ErrorDTO),
+  ),
+)]
+#[get("/fonts/{postscript}/preview")]
+pub async fn handler(
+  postscript: web::Path<String>,
+  web::Query(query): web::Query<PreviewQuery>,
+  state: web::Data<ServerState>,
+) -> Result<HttpResponse, ApiError> {
+  let sizes = match &query.sizes {
+    Some(sizes) => Preview::parse_sizes(sizes),
+    None => Preview::parse_sizes(&query.size.to_string()),
+  };
+  let invalid = |e| ApiError::new(ErrorCode::InvalidRequest, e);
+  let preview = Preview {
+    text: query.text,
+    sizes: sizes.map_err(invalid)?,
+    color: Color::parse(&query.color).map_err(invalid)?,
+    axes: Preview::parse_axes(&query.axes).map_err(invalid)?,
+    format: query.format,
+  };
+
+  let Some(desc) = state.database.iter().find(|f| f.postscript == *postscript) else {
+    return Err(ErrorCode::FontNotFound.into());
+  };
+  if !state.database.is_servable(&desc.path) {
+    return Err(ErrorCode::EmbeddingRestricted.into());
+  }
+  let (path, index) = (desc.path.clone(), desc.index);
+
+  let content_type = preview.format.content_type();
+  let image =
+    web::block(move || std::fs::read(path).map(|data| preview::render(&data, index, &preview)))
+      .await??
+      .map_err(|e| ApiError::new(ErrorCode::UnprocessableFont, e))?;
+
+  Ok(HttpResponse::Ok().insert_header((header::CONTENT_TYPE, content_type)).body(image))
+}
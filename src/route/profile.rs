@@ -0,0 +1,61 @@
+use crate::{
+  dto::{ErrorDTO, ProfileDTO, ProfilesDTO},
+  error::{ApiError, ErrorCode},
+  provider::FontDatabaseErr,
+  ServerState,
+};
+use actix_web::{delete, get, put, web};
+
+fn profiles(state: &ServerState) -> ProfilesDTO {
+  ProfilesDTO {
+    active: state.database.active_profile(),
+    profiles: (state.database.profiles().into_iter())
+      .map(|(name, fonts)| ProfileDTO { name: name.to_owned(), fonts })
+      .collect(),
+  }
+}
+
+/// profile handler
+#[utoipa::path(
+  operation_id = "profile",
+  tag = "admin",
+  responses((status = 200, description = "Profiles and the active one", body = ProfilesDTO)),
+)]
+#[get("/profile")]
+pub async fn handler(state: web::Data<ServerState>) -> Result<web::Json<ProfilesDTO>, ApiError> {
+  Ok(web::Json(profiles(&state)))
+}
+
+/// activate_profile handler, Figma gets the fonts of the profile on its next font list request
+#[utoipa::path(
+  operation_id = "activate_profile",
+  tag = "admin",
+  params(("name" = String, Path, description = "Name of the profile")),
+  responses(
+    (status = 200, description = "Profiles and the active one", body = ProfilesDTO),
+    (status = 404, description = "Unknown profile", body = ErrorDTO),
+  ),
+)]
+#[put("/profile/{name}")]
+pub async fn activate(
+  name: web::Path<String>,
+  state: web::Data<ServerState>,
+) -> Result<web::Json<ProfilesDTO>, ApiError> {
+  state.database.set_active_profile(Some(&name)).map_err(|e| match e {
+    FontDatabaseErr::UnknownProfile(_) => ApiError::new(ErrorCode::UnknownProfile, e),
+    e => ApiError::new(ErrorCode::Internal, e),
+  })?;
+  Ok(web::Json(profiles(&state)))
+}
+
+/// deactivate_profile handler
+#[utoipa::path(
+  operation_id = "deactivate_profile",
+  tag = "admin",
+  responses((status = 200, description = "Profiles, none of them active", body = ProfilesDTO)),
+)]
+#[delete("/profile")]
+pub async fn deactivate(state: web::Data<ServerState>) -> Result<web::Json<ProfilesDTO>, ApiError> {
+  state.database.set_active_profile(None).map_err(|e| ApiError::new(ErrorCode::Internal, e))?;
+  Ok(web::Json(profiles(&state)))
+}
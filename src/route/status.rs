@@ -0,0 +1,59 @@
+use crate::{
+  dto::{BuildDTO, LibraryDTO, ListenersDTO, ProviderDTO, ScanDTO, SourceStatusDTO, StatusDTO},
+  provider::active_sources,
+  ServerState,
+};
+use actix_web::{get, web, Result};
+use chrono::{DateTime, Local};
+use std::{collections::HashSet, time::SystemTime};
+
+fn rfc3339(time: SystemTime) -> String {
+  DateTime::<Local>::from(time).to_rfc3339()
+}
+
+/// status handler
+#[utoipa::path(
+  operation_id = "status",
+  tag = "admin",
+  responses(
+    (status = 200, description = "Build, font sources, last scan and listeners", body = StatusDTO),
+  ),
+)]
+#[get("/status")]
+pub async fn handler(state: web::Data<ServerState>) -> Result<web::Json<StatusDTO>> {
+  let sources = active_sources(&state.database.config().sources);
+  let families: HashSet<_> = state.database.iter().map(|font| font.family.as_str()).collect();
+  let listeners = state.listeners.get();
+
+  Ok(web::Json(StatusDTO {
+    build: BuildDTO {
+      version: env!("CARGO_PKG_VERSION").to_owned(),
+      git_hash: option_env!("FFH_GIT_HASH").unwrap_or("unknown").to_owned(),
+    },
+    protocol: state.protocol.current().into(),
+    provider: ProviderDTO {
+      api_version: state.font_provider_api_version,
+      sources: (sources.iter())
+        .map(|source| SourceStatusDTO { label: source.label(), kind: source.kind.into() })
+        .collect(),
+      libraries: (state.provider_libraries.iter())
+        .map(|library| LibraryDTO {
+          name: library.name.to_owned(),
+          version: library.version.clone(),
+        })
+        .collect(),
+    },
+    scan: ScanDTO {
+      scanned_at: rfc3339(state.scanned_at),
+      duration: state.scan_duration.as_secs_f64(),
+      fonts: state.database.len(),
+      families: families.len(),
+    },
+    profile: state.database.active_profile(),
+    listeners: ListenersDTO {
+      figma: listeners.map(|listeners| listeners.figma.clone()).unwrap_or_default(),
+      admin: listeners.map(|listeners| listeners.admin.clone()).unwrap_or_default(),
+      certificate_expiry: listeners.and_then(|listeners| listeners.certificate_expiry).map(rfc3339),
+    },
+  }))
+}
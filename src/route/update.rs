@@ -1,15 +1,29 @@
-use crate::{dto::VersionDTO, query::UpdateQuery, ServerState};
+use crate::{
+  dto::{ErrorDTO, VersionDTO},
+  query::UpdateQuery,
+  ServerState,
+};
 use actix_web::{get, web, Result};
 
-/// update handler
+/// update handler, Figma declares the protocol version it expects
+#[utoipa::path(
+  operation_id = "update",
+  tag = "figma",
+  params(UpdateQuery),
+  responses(
+    (status = 200, description = "Negotiated protocol version", body = VersionDTO),
+    (status = 400, description = "Invalid query", body = ErrorDTO),
+  ),
+)]
 #[get("/figma/update")]
 pub async fn handler(
   web::Query(query): web::Query<UpdateQuery>,
   state: web::Data<ServerState>,
 ) -> Result<web::Json<VersionDTO>> {
-  if query.version > state.figma_api_version {
-    // inform about the update somehow
+  let version = state.protocol.negotiate(query.version);
+  if query.version > usize::from(version) {
+    log::info!("Figma expects protocol version {}, answering with {version:?}", query.version);
   }
 
-  Ok(web::Json(VersionDTO { version: state.figma_api_version }))
+  Ok(web::Json(VersionDTO { version: version.into() }))
 }
@@ -1,8 +1,25 @@
-use crate::{dto::VersionDTO, ServerState};
+use crate::{
+  dto::{ErrorDTO, VersionDTO},
+  query::VersionQuery,
+  ServerState,
+};
 use actix_web::{get, web, Result};
 
 /// version handler
+#[utoipa::path(
+  operation_id = "version",
+  tag = "figma",
+  params(VersionQuery),
+  responses(
+    (status = 200, description = "Protocol version answered with", body = VersionDTO),
+    (status = 400, description = "Invalid query", body = ErrorDTO),
+  ),
+)]
 #[get("/figma/version")]
-pub async fn handler(state: web::Data<ServerState>) -> Result<web::Json<VersionDTO>> {
-  Ok(web::Json(VersionDTO { version: state.figma_api_version }))
+pub async fn handler(
+  web::Query(query): web::Query<VersionQuery>,
+  state: web::Data<ServerState>,
+) -> Result<web::Json<VersionDTO>> {
+  let version = state.protocol.for_request(query.version);
+  Ok(web::Json(VersionDTO { version: version.into() }))
 }
@@ -0,0 +1,533 @@
+//! Structural validation of fonts before they are handed to the browser, in the spirit of the
+//! OpenType Sanitizer.
+
+use crate::provider::{
+  sfnt::{
+    checksum, read_u16, read_u32, write_sfnt, ARG_1_AND_2_ARE_WORDS, MORE_COMPONENTS,
+    WE_HAVE_AN_X_AND_Y_SCALE, WE_HAVE_A_SCALE, WE_HAVE_A_TWO_BY_TWO, WE_HAVE_INSTRUCTIONS,
+  },
+  FontDescriptor,
+};
+use serde::Deserialize;
+use std::{
+  collections::{BTreeSet, HashMap},
+  fs, io,
+  ops::Range,
+  path::{Path, PathBuf},
+};
+use thiserror::Error;
+use ttf_parser::{Face, Tag};
+
+const TTCF: u32 = u32::from_be_bytes(*b"ttcf");
+const OTTO: u32 = u32::from_be_bytes(*b"OTTO");
+const TRUE: u32 = u32::from_be_bytes(*b"true");
+const HEAD_MAGIC: u32 = 0x5F0F_3CF5;
+
+const CMAP: Tag = Tag::from_bytes(b"cmap");
+const DSIG: Tag = Tag::from_bytes(b"DSIG");
+const GLYF: Tag = Tag::from_bytes(b"glyf");
+const HEAD: Tag = Tag::from_bytes(b"head");
+const HHEA: Tag = Tag::from_bytes(b"hhea");
+const HMTX: Tag = Tag::from_bytes(b"hmtx");
+const LOCA: Tag = Tag::from_bytes(b"loca");
+const MAXP: Tag = Tag::from_bytes(b"maxp");
+
+/// Tables registered with OpenType and the common AAT ones, the rest is dropped from clean copies.
+const KNOWN_TABLES: &[&[u8; 4]] = &[
+  b"avar", b"BASE", b"CBDT", b"CBLC", b"CFF ", b"CFF2", b"cmap", b"COLR", b"CPAL", b"cvar",
+  b"cvt ", b"EBDT", b"EBLC", b"EBSC", b"fpgm", b"fvar", b"gasp", b"GDEF", b"glyf", b"GPOS",
+  b"GSUB", b"gvar", b"hdmx", b"head", b"hhea", b"hmtx", b"HVAR", b"JSTF", b"kern", b"loca",
+  b"LTSH", b"MATH", b"maxp", b"MERG", b"meta", b"MVAR", b"name", b"OS/2", b"PCLT", b"post",
+  b"prep", b"sbix", b"STAT", b"SVG ", b"VDMX", b"vhea", b"vmtx", b"VORG", b"VVAR", b"ankr",
+  b"feat", b"kerx", b"morx", b"trak",
+];
+
+// simple glyph flags
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const REPEAT_FLAG: u8 = 0x08;
+const X_IS_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE: u8 = 0x20;
+
+#[derive(Error, Debug)]
+pub enum SanitizeErr {
+  #[error("Unknown sfnt version")]
+  UnknownVersion,
+
+  #[error("Truncated {0}")]
+  Truncated(&'static str),
+
+  #[error("Table `{0}` lies outside of the file")]
+  OutOfBounds(Tag),
+
+  /// Only reported as a warning, the font is kept.
+  #[error("Table `{0}` is not 4-byte aligned")]
+  Misaligned(Tag),
+
+  #[error("Table `{0}` is listed twice")]
+  DuplicateTable(Tag),
+
+  #[error("Tables `{0}` and `{1}` overlap")]
+  Overlap(Tag, Tag),
+
+  /// Only reported as a warning, many installed fonts carry stale checksums.
+  #[error("Checksum mismatch in `{0}`")]
+  Checksum(Tag),
+
+  #[error("Missing `{0}` table")]
+  MissingTable(Tag),
+
+  #[error("Invalid `{0}` table: {1}")]
+  InvalidTable(Tag, &'static str),
+
+  #[error("Glyph {0} is malformed: {1}")]
+  InvalidGlyph(u16, &'static str),
+
+  #[error("Unparsable face: {0}")]
+  Unparsable(#[from] ttf_parser::FaceParsingError),
+
+  #[error("Unable to read the font: {0}")]
+  Io(#[from] io::Error),
+
+  /// The file already failed validation for this reason.
+  #[error("{0}")]
+  Failed(String),
+}
+
+type Result<T, E = SanitizeErr> = std::result::Result<T, E>;
+
+/// Tables of a face and where they are in the file.
+type TableDirectory = Vec<(Tag, Range<usize>)>;
+
+#[derive(Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SanitizeConfig {
+  /// Validate each font file the first time it is requested, refusing to serve the failing ones.
+  pub enabled: bool,
+  /// Validate every font at startup instead and leave the failing ones out of the font list,
+  /// reading every file.
+  pub at_startup: bool,
+  /// Serve a re-serialized copy of the font rather than the original bytes, requires `enabled`.
+  pub clean_copy: bool,
+}
+
+impl Default for SanitizeConfig {
+  fn default() -> Self {
+    Self { enabled: true, at_startup: false, clean_copy: false }
+  }
+}
+
+/// A font left out of the font list because its file failed validation.
+pub struct QuarantinedFont {
+  pub postscript: String,
+  pub path: PathBuf,
+  pub reason: String,
+}
+
+/// Validates the file of every font, moving the fonts in failing files to the quarantine.
+pub fn quarantine(
+  fonts: Vec<FontDescriptor>,
+  config: &SanitizeConfig,
+) -> (Vec<FontDescriptor>, Vec<QuarantinedFont>) {
+  if !config.enabled || !config.at_startup {
+    return (fonts, vec![]);
+  }
+
+  let mut results = HashMap::<PathBuf, Option<String>>::new();
+  let mut kept = vec![];
+  let mut quarantined = vec![];
+
+  for font in fonts {
+    let failure = (results.entry(font.path.clone()))
+      .or_insert_with(|| check_file(&font.path).err().map(|e| e.to_string()));
+
+    match failure {
+      Some(reason) => {
+        log::warn!("Quarantined {} ({}): {}", font.postscript, font.path.to_string_lossy(), reason);
+        quarantined.push(QuarantinedFont {
+          postscript: font.postscript,
+          path: font.path,
+          reason: reason.clone(),
+        });
+      }
+      None => kept.push(font),
+    }
+  }
+
+  (kept, quarantined)
+}
+
+/// Reads and validates a font file, logging the warnings.
+pub fn check_file(path: &Path) -> Result<()> {
+  for warning in check(&fs::read(path)?)? {
+    log::warn!("{}: {}", path.to_string_lossy(), warning);
+  }
+  Ok(())
+}
+
+/// Validates every face of a font file.
+///
+/// Structural errors fail the check, the problems browsers tolerate, such as stale checksums and
+/// misaligned tables, are returned as warnings.
+pub fn check(data: &[u8]) -> Result<Vec<SanitizeErr>> {
+  let mut warnings = vec![];
+  for (index, offset) in face_offsets(data)?.into_iter().enumerate() {
+    let tables = table_directory(data, offset, &mut warnings)?;
+    check_face(data, &tables)?;
+    Face::parse(data, index as u32)?;
+  }
+  Ok(warnings)
+}
+
+/// Validates `data` and re-serializes it with fresh checksums and only the known tables.
+pub fn clean(data: &[u8]) -> Result<Vec<u8>> {
+  check(data)?;
+
+  let faces = face_offsets(data)?;
+  let mut directories = vec![];
+  for &offset in &faces {
+    let version = read_u32(data, offset).ok_or(SanitizeErr::Truncated("table directory"))?;
+    // checksums are recomputed and tables realigned, so the warnings do not matter
+    let tables: Vec<_> = table_directory(data, offset, &mut vec![])?
+      .into_iter()
+      .filter(|(tag, _)| *tag != DSIG && KNOWN_TABLES.contains(&&tag.to_bytes()))
+      .collect();
+    directories.push((version, tables));
+  }
+
+  if read_u32(data, 0) != Some(TTCF) {
+    let (version, tables) = directories.remove(0);
+    let tables = tables.into_iter().map(|(tag, range)| (tag, data[range].to_vec())).collect();
+    return Ok(write_sfnt(version, tables));
+  }
+
+  Ok(write_collection(data, &directories))
+}
+
+/// Offsets of the table directories, one per face.
+fn face_offsets(data: &[u8]) -> Result<Vec<usize>> {
+  let version = read_u32(data, 0).ok_or(SanitizeErr::Truncated("header"))?;
+  match version {
+    TTCF => {
+      let count = read_u32(data, 8).ok_or(SanitizeErr::Truncated("collection header"))? as usize;
+      if count == 0 || 12 + count * 4 > data.len() {
+        return Err(SanitizeErr::Truncated("collection header"));
+      }
+      (0..count)
+        .map(|i| read_u32(data, 12 + i * 4).map(|offset| offset as usize))
+        .collect::<Option<_>>()
+        .ok_or(SanitizeErr::Truncated("collection header"))
+    }
+    0x0001_0000 | OTTO | TRUE => Ok(vec![0]),
+    _ => Err(SanitizeErr::UnknownVersion),
+  }
+}
+
+/// Reads and validates the table records of the face at `offset`.
+fn table_directory(
+  data: &[u8],
+  offset: usize,
+  warnings: &mut Vec<SanitizeErr>,
+) -> Result<TableDirectory> {
+  let truncated = || SanitizeErr::Truncated("table directory");
+  let version = read_u32(data, offset).ok_or_else(truncated)?;
+  if !matches!(version, 0x0001_0000 | OTTO | TRUE) {
+    return Err(SanitizeErr::UnknownVersion);
+  }
+
+  let count = read_u16(data, offset + 4).ok_or_else(truncated)? as usize;
+  if count == 0 || offset + 12 + count * 16 > data.len() {
+    return Err(truncated());
+  }
+
+  let mut tables: TableDirectory = vec![];
+  for i in 0..count {
+    let record = offset + 12 + i * 16;
+    let tag = Tag::from_bytes(data[record..record + 4].try_into().map_err(|_| truncated())?);
+    let expected = read_u32(data, record + 4).ok_or_else(truncated)?;
+    let start = read_u32(data, record + 8).ok_or_else(truncated)? as usize;
+    let length = read_u32(data, record + 12).ok_or_else(truncated)? as usize;
+
+    let range = start..start.checked_add(length).ok_or(SanitizeErr::OutOfBounds(tag))?;
+    if range.end > data.len() {
+      return Err(SanitizeErr::OutOfBounds(tag));
+    }
+    if !start.is_multiple_of(4) {
+      warnings.push(SanitizeErr::Misaligned(tag));
+    }
+    if tables.iter().any(|(other, _)| *other == tag) {
+      return Err(SanitizeErr::DuplicateTable(tag));
+    }
+
+    let mut table = data[range.clone()].to_vec();
+    if tag == HEAD && table.len() >= 12 {
+      // the checksum of `head` is computed with checkSumAdjustment zeroed
+      table[8..12].fill(0);
+    }
+    if checksum(&table) != expected {
+      warnings.push(SanitizeErr::Checksum(tag));
+    }
+
+    tables.push((tag, range));
+  }
+
+  let mut sorted: Vec<_> = tables.iter().filter(|(_, range)| !range.is_empty()).collect();
+  sorted.sort_by_key(|(_, range)| range.start);
+  for pair in sorted.windows(2) {
+    if pair[0].1.end > pair[1].1.start {
+      return Err(SanitizeErr::Overlap(pair[0].0, pair[1].0));
+    }
+  }
+
+  Ok(tables)
+}
+
+/// Checks the tables every face needs and the consistency of the TrueType outlines.
+fn check_face(data: &[u8], tables: &[(Tag, Range<usize>)]) -> Result<()> {
+  let table = |tag: Tag| {
+    tables.iter().find(|(other, _)| *other == tag).map(|(_, range)| &data[range.clone()])
+  };
+  let required = |tag: Tag| table(tag).ok_or(SanitizeErr::MissingTable(tag));
+
+  let head = required(HEAD)?;
+  if head.len() < 54 || read_u32(head, 12) != Some(HEAD_MAGIC) {
+    return Err(SanitizeErr::InvalidTable(HEAD, "bad magic number"));
+  }
+  if !(16..=16384).contains(&read_u16(head, 18).unwrap_or(0)) {
+    return Err(SanitizeErr::InvalidTable(HEAD, "unitsPerEm out of range"));
+  }
+  let long_loca = match read_u16(head, 50) {
+    Some(0) => false,
+    Some(1) => true,
+    _ => return Err(SanitizeErr::InvalidTable(HEAD, "unknown indexToLocFormat")),
+  };
+
+  let maxp = required(MAXP)?;
+  let glyph_count = read_u16(maxp, 4).ok_or(SanitizeErr::InvalidTable(MAXP, "truncated"))?;
+  if glyph_count == 0 {
+    return Err(SanitizeErr::InvalidTable(MAXP, "no glyphs"));
+  }
+
+  let hhea = required(HHEA)?;
+  let metrics = read_u16(hhea, 34).ok_or(SanitizeErr::InvalidTable(HHEA, "truncated"))?;
+  if metrics == 0 || metrics > glyph_count {
+    return Err(SanitizeErr::InvalidTable(HHEA, "numberOfHMetrics out of range"));
+  }
+  let hmtx_length = metrics as usize * 4 + (glyph_count - metrics) as usize * 2;
+  if required(HMTX)?.len() < hmtx_length {
+    return Err(SanitizeErr::InvalidTable(HMTX, "truncated"));
+  }
+
+  required(CMAP)?;
+
+  // CFF and bitmap data is left to the parser
+  if let Some(glyf) = table(GLYF) {
+    check_glyf(glyf, required(LOCA)?, glyph_count, long_loca)?;
+  }
+
+  Ok(())
+}
+
+fn check_glyf(glyf: &[u8], loca: &[u8], glyph_count: u16, long: bool) -> Result<()> {
+  let count = glyph_count as usize + 1;
+  let offsets: Vec<usize> = (0..count)
+    .map(|i| {
+      if long {
+        read_u32(loca, i * 4).map(|offset| offset as usize)
+      } else {
+        read_u16(loca, i * 2).map(|offset| offset as usize * 2)
+      }
+    })
+    .collect::<Option<_>>()
+    .ok_or(SanitizeErr::InvalidTable(LOCA, "shorter than maxp.numGlyphs"))?;
+
+  if offsets.windows(2).any(|pair| pair[0] > pair[1]) {
+    return Err(SanitizeErr::InvalidTable(LOCA, "offsets out of order"));
+  }
+  if offsets[count - 1] > glyf.len() {
+    return Err(SanitizeErr::InvalidTable(LOCA, "offsets past the end of glyf"));
+  }
+
+  for glyph in 0..glyph_count {
+    let data = &glyf[offsets[glyph as usize]..offsets[glyph as usize + 1]];
+    if !data.is_empty() {
+      check_glyph(data, glyph_count).map_err(|reason| SanitizeErr::InvalidGlyph(glyph, reason))?;
+    }
+  }
+
+  Ok(())
+}
+
+fn check_glyph(data: &[u8], glyph_count: u16) -> Result<(), &'static str> {
+  let contours = read_u16(data, 0).ok_or("truncated header")? as i16;
+  if data.len() < 10 {
+    return Err("truncated header");
+  }
+
+  if contours < 0 {
+    return check_composite(data, glyph_count);
+  }
+
+  let contours = contours as usize;
+  let mut points = 0;
+  for i in 0..contours {
+    let end = read_u16(data, 10 + i * 2).ok_or("truncated contours")? as usize + 1;
+    if end <= points && i > 0 {
+      return Err("contour end points out of order");
+    }
+    points = end;
+  }
+
+  let instructions = 10 + contours * 2;
+  let length = read_u16(data, instructions).ok_or("truncated instructions")? as usize;
+  let mut offset = instructions + 2 + length;
+
+  // walk the flags to learn how many bytes the coordinates take
+  let (mut x_bytes, mut y_bytes, mut flagged) = (0, 0, 0);
+  while flagged < points {
+    let flag = *data.get(offset).ok_or("truncated flags")?;
+    offset += 1;
+    let repeat = if flag & REPEAT_FLAG != 0 {
+      offset += 1;
+      *data.get(offset - 1).ok_or("truncated flags")? as usize + 1
+    } else {
+      1
+    };
+
+    let coordinate = |short: u8, same: u8| match (flag & short != 0, flag & same != 0) {
+      (true, _) => 1,
+      (false, true) => 0,
+      (false, false) => 2,
+    };
+    x_bytes += coordinate(X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE) * repeat;
+    y_bytes += coordinate(Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE) * repeat;
+    flagged += repeat;
+  }
+
+  if flagged > points {
+    return Err("flags repeated past the last point");
+  }
+  if offset + x_bytes + y_bytes > data.len() {
+    return Err("truncated coordinates");
+  }
+  Ok(())
+}
+
+fn check_composite(data: &[u8], glyph_count: u16) -> Result<(), &'static str> {
+  let mut offset = 10;
+  let mut instructions = false;
+  let mut components = BTreeSet::new();
+
+  loop {
+    let flags = read_u16(data, offset).ok_or("truncated component")?;
+    let component = read_u16(data, offset + 2).ok_or("truncated component")?;
+    if component >= glyph_count {
+      return Err("component out of range");
+    }
+    components.insert(component);
+    instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+
+    offset += 4 + if flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 };
+    if flags & WE_HAVE_A_SCALE != 0 {
+      offset += 2;
+    } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+      offset += 4;
+    } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+      offset += 8;
+    }
+    if offset > data.len() {
+      return Err("truncated component");
+    }
+
+    if flags & MORE_COMPONENTS == 0 {
+      break;
+    }
+  }
+
+  if instructions {
+    let length = read_u16(data, offset).ok_or("truncated instructions")? as usize;
+    if offset + 2 + length > data.len() {
+      return Err("truncated instructions");
+    }
+  }
+  Ok(())
+}
+
+/// Writes a font collection, keeping tables shared between faces shared.
+fn write_collection(data: &[u8], directories: &[(u32, TableDirectory)]) -> Vec<u8> {
+  let header = 12 + 4 * directories.len();
+  let directory_sizes: Vec<usize> =
+    directories.iter().map(|(_, tables)| 12 + 16 * tables.len()).collect();
+
+  // lay out every distinct table once, after all of the table directories
+  let mut offset = header + directory_sizes.iter().sum::<usize>();
+  let mut placed = HashMap::<Range<usize>, usize>::new();
+  let mut order = vec![];
+  for (_, tables) in directories {
+    for (_, range) in tables {
+      if !placed.contains_key(range) {
+        placed.insert(range.clone(), offset);
+        order.push(range.clone());
+        offset += range.len().next_multiple_of(4);
+      }
+    }
+  }
+
+  let mut font = vec![];
+  font.extend(TTCF.to_be_bytes());
+  font.extend(0x0001_0000u32.to_be_bytes());
+  font.extend((directories.len() as u32).to_be_bytes());
+  let mut directory_offset = header;
+  for size in &directory_sizes {
+    font.extend((directory_offset as u32).to_be_bytes());
+    directory_offset += size;
+  }
+
+  // checksum of each face, its table directory and its tables, and where its `head` table goes
+  let mut faces = vec![];
+  for (version, tables) in directories {
+    let mut tables = tables.clone();
+    tables.sort_by_key(|(tag, _)| tag.to_bytes());
+    let start = font.len();
+    let mut sum = 0u32;
+    let mut head = None;
+
+    let count = tables.len() as u16;
+    let entry_selector = 15 - count.max(1).leading_zeros() as u16;
+    let search_range: u16 = 16 << entry_selector;
+    font.extend(version.to_be_bytes());
+    font.extend(count.to_be_bytes());
+    font.extend(search_range.to_be_bytes());
+    font.extend(entry_selector.to_be_bytes());
+    font.extend((count * 16 - search_range).to_be_bytes());
+
+    for (tag, range) in &tables {
+      let mut table = data[range.clone()].to_vec();
+      if *tag == HEAD {
+        table[8..12].fill(0);
+        head = Some(placed[range]);
+      }
+      font.extend(tag.to_bytes());
+      font.extend(checksum(&table).to_be_bytes());
+      font.extend((placed[range] as u32).to_be_bytes());
+      font.extend((range.len() as u32).to_be_bytes());
+      sum = sum.wrapping_add(checksum(&table));
+    }
+    faces.push((sum.wrapping_add(checksum(&font[start..])), head));
+  }
+
+  for range in order {
+    font.extend_from_slice(&data[range]);
+    font.resize(font.len().next_multiple_of(4), 0);
+  }
+
+  // computed per face as for a single font, a `head` shared by several faces keeps the last one
+  for (sum, head) in faces {
+    if let Some(head) = head {
+      let adjustment = 0xB1B0_AFBA_u32.wrapping_sub(sum);
+      font[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+  }
+
+  font
+}
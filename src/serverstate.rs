@@ -1,32 +1,108 @@
-use crate::provider::{
-  FontDatabase, FontDatabaseErr, FontProvider, PlatformFontProvider, PlatformFontProviderErr,
+use crate::{
+  config::Config,
+  fontcache::FontCache,
+  fontresponse::{ContentHashes, Validations},
+  metrics::Metrics,
+  protocol::Protocol,
+  provider::{
+    CompositeFontProvider, FontDatabase, FontDatabaseErr, FontProvider, FontProviderErr,
+    ProviderLibrary,
+  },
+  ratelimit::RateLimiter,
+  subset::SubsetCache,
+  trace::{TraceErr, Tracer},
+  usage::UsageStats,
+};
+use std::{
+  sync::OnceLock,
+  time::{Duration, Instant, SystemTime},
 };
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ServerStateErr {
   #[error(transparent)]
-  ProviderError(#[from] PlatformFontProviderErr),
+  ProviderError(#[from] FontProviderErr),
 
   #[error(transparent)]
   DatabaseError(#[from] FontDatabaseErr),
+
+  #[error(transparent)]
+  Trace(#[from] TraceErr),
 }
 
 type Result<T, E = ServerStateErr> = std::result::Result<T, E>;
 
+/// Where the server listens, known once the listeners are bound.
+pub struct Listeners {
+  /// URLs of the listeners Figma talks to.
+  pub figma: Vec<String>,
+  /// URLs of the admin listener.
+  pub admin: Vec<String>,
+  /// Expiry of the certificate served over https, if any.
+  pub certificate_expiry: Option<SystemTime>,
+}
+
 pub struct ServerState {
-  pub figma_api_version: usize,
+  pub protocol: Protocol,
   pub font_provider_api_version: usize,
+  /// Native libraries of the font sources.
+  pub provider_libraries: Vec<ProviderLibrary>,
   pub database: FontDatabase,
+  pub metrics: Metrics,
+  pub content_hashes: ContentHashes,
+  /// Files validated on their first request, see [`crate::sanitize::SanitizeConfig`].
+  pub validations: Validations,
+  pub subsets: SubsetCache,
+  pub font_cache: FontCache,
+  pub usage: UsageStats,
+  /// Records the requests of Figma when a trace file is configured.
+  pub tracer: Option<Tracer>,
+  pub limiter: RateLimiter,
+  /// When the fonts were scanned and how long it took.
+  pub scanned_at: SystemTime,
+  pub scan_duration: Duration,
+  pub listeners: OnceLock<Listeners>,
 }
 
 impl ServerState {
-  pub fn new() -> Result<Self, ServerStateErr> {
-    let font_provider = Box::new(PlatformFontProvider::new()?);
+  /// Serves the fonts of the configured sources.
+  pub fn new(config: &Config) -> Result<Self, ServerStateErr> {
+    let font_provider = CompositeFontProvider::from_config(&config.sources)?;
+    Self::with_provider(Box::new(font_provider), config)
+  }
+
+  /// Serves the fonts of any provider, such as a directory of fixtures in tests.
+  pub fn with_provider(
+    font_provider: Box<dyn FontProvider>,
+    config: &Config,
+  ) -> Result<Self, ServerStateErr> {
+    let font_provider_api_version = font_provider.get_api_version()?;
+    let provider_libraries = font_provider.get_libraries();
+    let metrics = Metrics::default();
+
+    let scanned_at = SystemTime::now();
+    let started = Instant::now();
+    let database = FontDatabase::new(font_provider, config)?;
+    let scan_duration = started.elapsed();
+    metrics.record_scan(scan_duration, database.skipped());
+
     Ok(Self {
-      figma_api_version: 4,
-      font_provider_api_version: font_provider.get_api_version()?,
-      database: FontDatabase::new(font_provider)?,
+      protocol: Protocol::new(&config.protocol),
+      font_provider_api_version,
+      provider_libraries,
+      database,
+      metrics,
+      content_hashes: ContentHashes::default(),
+      validations: Validations::default(),
+      subsets: SubsetCache::default(),
+      font_cache: FontCache::new(&config.cache),
+      usage: UsageStats::new(&config.usage),
+      tracer: config.trace.path.as_deref().map(Tracer::new).transpose()?,
+      limiter: RateLimiter::new(&config.limits),
+      scanned_at,
+      scan_duration,
+      listeners: OnceLock::new(),
     })
   }
 }
@@ -0,0 +1,179 @@
+//! Font subsetting that keeps glyph IDs stable.
+//!
+//! Glyphs that are not needed for the requested characters keep their ID but lose their outlines.
+//! Everything that merely refers to glyphs (GSUB, GPOS, GDEF, hmtx, kern, ...) therefore stays
+//! valid and is copied as is, so the subset shapes exactly like the full font.
+
+mod cff;
+mod closure;
+mod cmap;
+mod glyf;
+
+use crate::{
+  fontcache::Lru,
+  provider::{
+    coverage::Coverage,
+    sfnt::{read_u16, write_sfnt},
+  },
+};
+use actix_web::web::Bytes;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use thiserror::Error;
+use ttf_parser::{Face, Tag};
+
+/// Upper bound of the subset bytes kept in memory.
+const CACHE_BYTES: u64 = 64 * 1024 * 1024;
+
+const CFF: Tag = Tag::from_bytes(b"CFF ");
+const CFF2: Tag = Tag::from_bytes(b"CFF2");
+const CMAP: Tag = Tag::from_bytes(b"cmap");
+const COLR: Tag = Tag::from_bytes(b"COLR");
+const DSIG: Tag = Tag::from_bytes(b"DSIG");
+const GLYF: Tag = Tag::from_bytes(b"glyf");
+const GVAR: Tag = Tag::from_bytes(b"gvar");
+const HEAD: Tag = Tag::from_bytes(b"head");
+const LOCA: Tag = Tag::from_bytes(b"loca");
+
+#[derive(Error, Debug)]
+pub enum SubsetErr {
+  #[error("Malformed font: {0}")]
+  Malformed(&'static str),
+
+  #[error("Fonts with {0} outlines can't be subset")]
+  UnsupportedOutlines(&'static str),
+}
+
+type Result<T, E = SubsetErr> = std::result::Result<T, E>;
+
+/// Identifies a subset: the source file version, the face in it and the characters.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SubsetKey {
+  pub source: String,
+  pub index: u32,
+  pub characters: Coverage,
+}
+
+impl SubsetKey {
+  /// A strong entity tag for the subset, known before the subset is built.
+  pub fn etag(&self) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(self.source.as_bytes());
+    hasher.update(self.index.to_be_bytes());
+    for range in self.characters.ranges() {
+      hasher.update(range.start().to_be_bytes());
+      hasher.update(range.end().to_be_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+  }
+}
+
+/// A built subset.
+pub struct Subset {
+  pub data: Bytes,
+  pub content_type: &'static str,
+}
+
+/// The most recently used subsets, up to [`CACHE_BYTES`], least recently used evicted first.
+pub struct SubsetCache {
+  entries: Lru<SubsetKey, Arc<Subset>>,
+}
+
+impl Default for SubsetCache {
+  fn default() -> Self {
+    Self { entries: Lru::new(CACHE_BYTES) }
+  }
+}
+
+impl SubsetCache {
+  pub fn get(&self, key: &SubsetKey) -> Option<Arc<Subset>> {
+    self.entries.get(key)
+  }
+
+  pub fn insert(&self, key: SubsetKey, subset: Arc<Subset>) {
+    let size = subset.data.len() as u64;
+    self.entries.insert(key, subset, size);
+  }
+}
+
+/// Builds a standalone font out of face `index` of `data` that covers `characters`.
+///
+/// The upper 16 bits of `index` select a named instance, which is subset as the whole variable
+/// font so its other instances keep working.
+pub fn subset(data: &[u8], index: u32, characters: &Coverage) -> Result<Subset> {
+  let face = Face::parse(data, index & 0xFFFF).map_err(|_| SubsetErr::Malformed("unparsable"))?;
+  let raw = face.raw_face();
+
+  if raw.table(CFF2).is_some() {
+    return Err(SubsetErr::UnsupportedOutlines("CFF2"));
+  }
+  if raw.table(COLR).and_then(|colr| read_u16(colr, 0)).is_some_and(|version| version > 0) {
+    return Err(SubsetErr::UnsupportedOutlines("COLRv1"));
+  }
+
+  let mapping = closure::mapping(&face, characters);
+  let mut glyphs = closure::glyphs(&face, &mapping);
+
+  let mut tables: Vec<(Tag, Vec<u8>)> = vec![];
+  let mut long_loca = None;
+
+  if let (Some(glyf), Some(loca)) = (raw.table(GLYF), raw.table(LOCA)) {
+    let offsets = glyf::offsets(&face, loca).ok_or(SubsetErr::Malformed("loca"))?;
+    glyf::add_components(glyf, &offsets, &mut glyphs);
+
+    let (glyf, loca, long) = glyf::subset(glyf, &offsets, &glyphs);
+    tables.push((GLYF, glyf));
+    tables.push((LOCA, loca));
+    long_loca = Some(long);
+
+    if let Some(gvar) = raw.table(GVAR) {
+      tables.push((GVAR, glyf::subset_gvar(gvar, &glyphs).ok_or(SubsetErr::Malformed("gvar"))?));
+    }
+  }
+
+  let outlines_cff = match raw.table(CFF) {
+    Some(cff) => {
+      tables.push((CFF, cff::subset(cff, &glyphs).ok_or(SubsetErr::Malformed("CFF"))?));
+      true
+    }
+    None => false,
+  };
+
+  tables.push((CMAP, cmap::build(&mapping)));
+
+  let mut head = raw.table(HEAD).ok_or(SubsetErr::Malformed("head"))?.to_vec();
+  if head.len() < 54 {
+    return Err(SubsetErr::Malformed("head"));
+  }
+  if let Some(long) = long_loca {
+    head[50..52].copy_from_slice(&(long as u16).to_be_bytes());
+  }
+  tables.push((HEAD, head));
+
+  for record in raw.table_records {
+    if record.tag == DSIG || tables.iter().any(|(tag, _)| *tag == record.tag) {
+      continue;
+    }
+    if let Some(table) = raw.table(record.tag) {
+      tables.push((record.tag, table.to_vec()));
+    }
+  }
+
+  let (version, content_type) = if outlines_cff {
+    (u32::from_be_bytes(*b"OTTO"), "font/otf")
+  } else {
+    (0x0001_0000, "font/ttf")
+  };
+
+  Ok(Subset { data: Bytes::from(write_sfnt(version, tables)), content_type })
+}
+
+/// Appends a big-endian `u16`.
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+  out.extend(value.to_be_bytes());
+}
+
+/// Appends a big-endian `u32`.
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+  out.extend(value.to_be_bytes());
+}
@@ -0,0 +1,362 @@
+//! CFF outlines.
+//!
+//! The table is laid out anew: the leading INDEXes are copied, everything reached through an
+//! offset is copied after them, and all offsets are written as 5-byte integers so that DICT sizes
+//! don't depend on where things end up.
+
+use crate::provider::sfnt::read_u16;
+use std::collections::{BTreeSet, HashMap};
+
+const CHARSET: u16 = 15;
+const ENCODING: u16 = 16;
+const CHAR_STRINGS: u16 = 17;
+const PRIVATE: u16 = 18;
+const SUBRS: u16 = 19;
+const FD_ARRAY: u16 = 1236;
+const FD_SELECT: u16 = 1237;
+
+/// Charstring of a glyph without outline.
+const EMPTY_GLYPH: &[u8] = &[14]; // endchar
+
+/// Empties the charstrings of every glyph not in `glyphs`.
+pub fn subset(cff: &[u8], glyphs: &BTreeSet<u16>) -> Option<Vec<u8>> {
+  let header_size = *cff.get(2)? as usize;
+  let names_end = index_end(cff, header_size)?;
+  let top_dicts = index(cff, names_end)?;
+  let top_end = index_end(cff, names_end)?;
+  let strings_end = index_end(cff, top_end)?;
+  let leading_end = index_end(cff, strings_end)?;
+
+  let top = Dict::parse(top_dicts.first()?)?;
+  let char_strings = index(cff, top.int(CHAR_STRINGS).and_then(to_offset)?)?;
+  let glyph_count = char_strings.len();
+
+  // blobs copied verbatim, with the offset they get in the new table
+  let mut blobs: Vec<(u16, Vec<u8>)> = vec![];
+  let blob =
+    |offset: usize, len: Option<usize>| Some(cff.get(offset..offset.checked_add(len?)?)?.to_vec());
+  // offsets 0 to 2 select predefined charsets and encodings
+  if let Some(charset) = top.int(CHARSET).filter(|&offset| offset > 2).map(|offset| offset as usize)
+  {
+    blobs.push((CHARSET, blob(charset, charset_len(cff, charset, glyph_count))?));
+  }
+  if let Some(encoding) =
+    top.int(ENCODING).filter(|&offset| offset > 1).map(|offset| offset as usize)
+  {
+    blobs.push((ENCODING, blob(encoding, encoding_len(cff, encoding))?));
+  }
+  if let Some(fd_select) = top.int(FD_SELECT) {
+    let fd_select = to_offset(fd_select)?;
+    blobs.push((FD_SELECT, blob(fd_select, fd_select_len(cff, fd_select, glyph_count))?));
+  }
+
+  let char_strings: Vec<&[u8]> = char_strings
+    .into_iter()
+    .enumerate()
+    .map(|(glyph, data)| if glyphs.contains(&(glyph as u16)) { data } else { EMPTY_GLYPH })
+    .collect();
+  blobs.push((CHAR_STRINGS, write_index(&char_strings)));
+
+  let font_dicts = match top.int(FD_ARRAY) {
+    Some(offset) => {
+      index(cff, to_offset(offset)?)?.into_iter().map(Dict::parse).collect::<Option<Vec<_>>>()?
+    }
+    None => vec![],
+  };
+
+  // private DICTs with their subroutines right behind them
+  let mut privates = vec![];
+  for dict in std::iter::once(&top).chain(&font_dicts) {
+    privates.push(match dict.private() {
+      Some((size, offset)) => Some(private(cff, size, offset)?),
+      None => None,
+    });
+  }
+
+  let mut relocations = Relocations {
+    offsets: blobs.iter().map(|(op, _)| (*op, 0)).collect(),
+    private: privates[0].as_ref().map(|_| (0, 0)),
+  };
+  if !font_dicts.is_empty() {
+    relocations.offsets.insert(FD_ARRAY, 0);
+  }
+
+  let top_size = write_index(&[top.write(&relocations).as_slice()]).len();
+  let mut offset = names_end + top_size + (leading_end - top_end);
+
+  for (op, blob) in &blobs {
+    relocations.offsets.insert(*op, offset as i32);
+    offset += blob.len();
+  }
+
+  let mut private_offsets = vec![];
+  for private in &privates {
+    private_offsets.push(private.as_ref().map(|(dict, subrs)| {
+      let private_offset = offset;
+      offset += dict.len() + subrs.len();
+      (dict.len() as i32, private_offset as i32)
+    }));
+  }
+  relocations.private = private_offsets[0];
+
+  let font_dicts: Vec<Vec<u8>> = font_dicts
+    .iter()
+    .zip(&private_offsets[1..])
+    .map(|(dict, private)| dict.write(&Relocations { private: *private, ..Default::default() }))
+    .collect();
+  if !font_dicts.is_empty() {
+    relocations.offsets.insert(FD_ARRAY, offset as i32);
+  }
+
+  let top = top.write(&relocations);
+
+  let mut out = cff.get(..names_end)?.to_vec();
+  out.extend(write_index(&[top.as_slice()]));
+  out.extend_from_slice(cff.get(top_end..leading_end)?);
+  for (_, blob) in blobs {
+    out.extend(blob);
+  }
+  for (dict, subrs) in privates.into_iter().flatten() {
+    out.extend(dict);
+    out.extend(subrs);
+  }
+  if !font_dicts.is_empty() {
+    out.extend(write_index(&font_dicts.iter().map(Vec::as_slice).collect::<Vec<_>>()));
+  }
+
+  Some(out)
+}
+
+/// Copies a private DICT, pointing its subroutines right behind it.
+fn private(cff: &[u8], size: i32, offset: i32) -> Option<(Vec<u8>, Vec<u8>)> {
+  let (size, offset) = (to_offset(size)?, to_offset(offset)?);
+  let dict = Dict::parse(cff.get(offset..offset.checked_add(size)?)?)?;
+
+  let subrs = match dict.int(SUBRS) {
+    Some(subrs) => {
+      let start = offset.checked_add(to_offset(subrs)?)?;
+      cff.get(start..index_end(cff, start)?)?.to_vec()
+    }
+    None => vec![],
+  };
+
+  let mut relocations = Relocations::default();
+  if !subrs.is_empty() {
+    // relative to the private DICT, whose size doesn't depend on the value
+    relocations.offsets.insert(SUBRS, 0);
+    let size = dict.write(&relocations).len();
+    relocations.offsets.insert(SUBRS, size as i32);
+  }
+
+  Some((dict.write(&relocations), subrs))
+}
+
+/// An offset or size out of a DICT operand, negative ones are malformed.
+fn to_offset(value: i32) -> Option<usize> {
+  usize::try_from(value).ok()
+}
+
+/// New values for offset operators, written as 5-byte integers.
+#[derive(Default)]
+struct Relocations {
+  offsets: HashMap<u16, i32>,
+  private: Option<(i32, i32)>,
+}
+
+/// A DICT as a list of operators with their raw operands.
+struct Dict<'a> {
+  entries: Vec<(u16, Vec<&'a [u8]>)>,
+}
+
+impl<'a> Dict<'a> {
+  fn parse(data: &'a [u8]) -> Option<Self> {
+    let mut entries = vec![];
+    let mut operands = vec![];
+    let mut i = 0;
+
+    while i < data.len() {
+      let len = match data[i] {
+        12 => {
+          entries.push((1200 + *data.get(i + 1)? as u16, std::mem::take(&mut operands)));
+          i += 2;
+          continue;
+        }
+        op @ 0..=21 => {
+          entries.push((op as u16, std::mem::take(&mut operands)));
+          i += 1;
+          continue;
+        }
+        28 => 3,
+        29 => 5,
+        30 => 1 + data[i + 1..].iter().position(|b| b & 0x0F == 0x0F || b & 0xF0 == 0xF0)? + 1,
+        32..=246 => 1,
+        247..=254 => 2,
+        _ => return None,
+      };
+      operands.push(data.get(i..i + len)?);
+      i += len;
+    }
+
+    Some(Self { entries })
+  }
+
+  fn operands(&self, op: u16) -> Option<&[&'a [u8]]> {
+    self.entries.iter().find(|(entry, _)| *entry == op).map(|(_, operands)| operands.as_slice())
+  }
+
+  fn int(&self, op: u16) -> Option<i32> {
+    self.operands(op)?.last().and_then(|operand| decode_int(operand))
+  }
+
+  /// Size and offset of the private DICT.
+  fn private(&self) -> Option<(i32, i32)> {
+    match self.operands(PRIVATE)? {
+      [size, offset] => Some((decode_int(size)?, decode_int(offset)?)),
+      _ => None,
+    }
+  }
+
+  fn write(&self, relocations: &Relocations) -> Vec<u8> {
+    let mut out = vec![];
+
+    for (op, operands) in &self.entries {
+      match (*op, relocations.offsets.get(op), relocations.private) {
+        (PRIVATE, _, Some((size, offset))) => {
+          encode_int(&mut out, size);
+          encode_int(&mut out, offset);
+        }
+        (_, Some(offset), _) => encode_int(&mut out, *offset),
+        _ => operands.iter().for_each(|operand| out.extend_from_slice(operand)),
+      }
+
+      if *op >= 1200 {
+        out.extend([12, (*op - 1200) as u8]);
+      } else {
+        out.push(*op as u8);
+      }
+    }
+
+    out
+  }
+}
+
+fn decode_int(operand: &[u8]) -> Option<i32> {
+  let b = |i: usize| operand.get(i).map(|&b| b as i32);
+  Some(match b(0)? {
+    28 => read_u16(operand, 1)? as i16 as i32,
+    29 => i32::from_be_bytes(operand.get(1..5)?.try_into().ok()?),
+    b0 @ 32..=246 => b0 - 139,
+    b0 @ 247..=250 => (b0 - 247) * 256 + b(1)? + 108,
+    b0 @ 251..=254 => -(b0 - 251) * 256 - b(1)? - 108,
+    _ => return None,
+  })
+}
+
+fn encode_int(out: &mut Vec<u8>, value: i32) {
+  out.push(29);
+  out.extend(value.to_be_bytes());
+}
+
+/// Reads the items of the INDEX at `offset`.
+fn index(cff: &[u8], offset: usize) -> Option<Vec<&[u8]>> {
+  let count = read_u16(cff, offset)? as usize;
+  if count == 0 {
+    return Some(vec![]);
+  }
+
+  let off_size = *cff.get(offset + 2)? as usize;
+  let read_offset = |i: usize| {
+    let start = offset + 3 + i * off_size;
+    let bytes = cff.get(start..start + off_size)?;
+    Some(bytes.iter().fold(0usize, |value, &b| value << 8 | b as usize))
+  };
+  // offsets are 1-based, relative to the byte before the data
+  let data = offset + 3 + (count + 1) * off_size - 1;
+
+  (0..count).map(|i| cff.get(data + read_offset(i)?..data + read_offset(i + 1)?)).collect()
+}
+
+fn index_end(cff: &[u8], offset: usize) -> Option<usize> {
+  let count = read_u16(cff, offset)? as usize;
+  if count == 0 {
+    return Some(offset + 2);
+  }
+
+  let items = index(cff, offset)?;
+  let off_size = *cff.get(offset + 2)? as usize;
+  Some(offset + 3 + (count + 1) * off_size + items.iter().map(|item| item.len()).sum::<usize>())
+}
+
+fn write_index(items: &[&[u8]]) -> Vec<u8> {
+  let mut out = (items.len() as u16).to_be_bytes().to_vec();
+  if items.is_empty() {
+    return out;
+  }
+
+  let data_len: usize = items.iter().map(|item| item.len()).sum();
+  let off_size = match data_len + 1 {
+    0..=0xFF => 1,
+    0x100..=0xFFFF => 2,
+    0x1_0000..=0xFF_FFFF => 3,
+    _ => 4,
+  };
+  out.push(off_size as u8);
+
+  let mut offset = 1;
+  for len in std::iter::once(0).chain(items.iter().map(|item| item.len())) {
+    offset += len;
+    out.extend(&(offset as u32).to_be_bytes()[4 - off_size..]);
+  }
+  for item in items {
+    out.extend_from_slice(item);
+  }
+
+  out
+}
+
+fn charset_len(cff: &[u8], offset: usize, glyph_count: usize) -> Option<usize> {
+  let remaining = glyph_count.saturating_sub(1);
+  match *cff.get(offset)? {
+    0 => Some(1 + remaining * 2),
+    format @ (1 | 2) => {
+      let range_size = if format == 1 { 3 } else { 4 };
+      let (mut covered, mut len) = (0, 1);
+      while covered < remaining {
+        let left = if format == 1 {
+          *cff.get(offset + len + 2)? as usize
+        } else {
+          read_u16(cff, offset + len + 2)? as usize
+        };
+        covered += left + 1;
+        len += range_size;
+      }
+      Some(len)
+    }
+    _ => None,
+  }
+}
+
+fn encoding_len(cff: &[u8], offset: usize) -> Option<usize> {
+  let format = *cff.get(offset)?;
+  let count = *cff.get(offset + 1)? as usize;
+  let len = match format & 0x7F {
+    0 => 2 + count,
+    1 => 2 + count * 2,
+    _ => return None,
+  };
+
+  // supplements follow when the high bit is set
+  if format & 0x80 != 0 {
+    Some(len + 1 + *cff.get(offset + len)? as usize * 3)
+  } else {
+    Some(len)
+  }
+}
+
+fn fd_select_len(cff: &[u8], offset: usize, glyph_count: usize) -> Option<usize> {
+  match *cff.get(offset)? {
+    0 => Some(1 + glyph_count),
+    3 => Some(1 + 2 + read_u16(cff, offset + 1)? as usize * 3 + 2),
+    _ => None,
+  }
+}
@@ -0,0 +1,134 @@
+//! Finds every glyph a subset needs.
+
+use crate::provider::{
+  coverage::Coverage,
+  sfnt::{read_u16, read_u32},
+};
+use std::collections::{BTreeMap, BTreeSet};
+use ttf_parser::{gsub::SubstitutionSubtable, Face, GlyphId, Tag};
+
+const COLR: Tag = Tag::from_bytes(b"COLR");
+
+/// Maps the requested characters the font supports to their glyphs.
+pub fn mapping(face: &Face<'_>, characters: &Coverage) -> BTreeMap<u32, u16> {
+  let mut mapping = BTreeMap::new();
+
+  let Some(cmap) = face.tables().cmap else {
+    return mapping;
+  };
+
+  for subtable in cmap.subtables.into_iter().filter(|subtable| subtable.is_unicode()) {
+    subtable.codepoints(|codepoint| {
+      if mapping.contains_key(&codepoint) || !characters.contains(codepoint) {
+        return;
+      }
+      if let Some(glyph) = char::from_u32(codepoint).and_then(|c| face.glyph_index(c)) {
+        mapping.insert(codepoint, glyph.0);
+      }
+    });
+  }
+
+  mapping
+}
+
+/// Collects `.notdef`, the mapped glyphs and everything they can be substituted with or are
+/// painted from. Composite glyph components are added separately as they depend on the outlines.
+pub fn glyphs(face: &Face<'_>, mapping: &BTreeMap<u32, u16>) -> BTreeSet<u16> {
+  let mut glyphs: BTreeSet<u16> = mapping.values().copied().collect();
+  glyphs.insert(0);
+
+  if let Some(gsub) = face.tables().gsub {
+    // applying every lookup everywhere over-approximates contextual substitutions, which is fine
+    loop {
+      let mut added = vec![];
+      for lookup in gsub.lookups {
+        for subtable in lookup.subtables.into_iter::<SubstitutionSubtable<'_>>() {
+          substitutes(&subtable, &glyphs, &mut added);
+        }
+      }
+
+      let before = glyphs.len();
+      glyphs.extend(added);
+      if glyphs.len() == before {
+        break;
+      }
+    }
+  }
+
+  if let Some(colr) = face.raw_face().table(COLR) {
+    add_color_layers(colr, &mut glyphs);
+  }
+
+  glyphs
+}
+
+/// Pushes every glyph `subtable` can turn `glyphs` into.
+fn substitutes(subtable: &SubstitutionSubtable<'_>, glyphs: &BTreeSet<u16>, out: &mut Vec<u16>) {
+  use ttf_parser::gsub::SingleSubstitution;
+
+  for &glyph in glyphs {
+    let Some(index) = subtable.coverage().get(GlyphId(glyph)) else {
+      continue;
+    };
+
+    match subtable {
+      SubstitutionSubtable::Single(SingleSubstitution::Format1 { delta, .. }) => {
+        out.push(glyph.wrapping_add(*delta as u16));
+      }
+      SubstitutionSubtable::Single(SingleSubstitution::Format2 { substitutes, .. }) => {
+        out.extend(substitutes.get(index).map(|glyph| glyph.0));
+      }
+      SubstitutionSubtable::Multiple(multiple) => {
+        if let Some(sequence) = multiple.sequences.get(index) {
+          out.extend(sequence.substitutes.into_iter().map(|glyph| glyph.0));
+        }
+      }
+      SubstitutionSubtable::Alternate(alternate) => {
+        if let Some(set) = alternate.alternate_sets.get(index) {
+          out.extend(set.alternates.into_iter().map(|glyph| glyph.0));
+        }
+      }
+      SubstitutionSubtable::Ligature(ligature) => {
+        if let Some(set) = ligature.ligature_sets.get(index) {
+          for ligature in set {
+            if ligature.components.into_iter().all(|glyph| glyphs.contains(&glyph.0)) {
+              out.push(ligature.glyph.0);
+            }
+          }
+        }
+      }
+      SubstitutionSubtable::ReverseChainSingle(reverse) => {
+        out.extend(reverse.substitutes.get(index).map(|glyph| glyph.0));
+      }
+      // they only invoke other lookups, which are applied anyway
+      SubstitutionSubtable::Context(_) | SubstitutionSubtable::ChainContext(_) => {}
+    }
+  }
+}
+
+/// Adds the layers of COLRv0 color glyphs.
+fn add_color_layers(colr: &[u8], glyphs: &mut BTreeSet<u16>) {
+  let field = |offset: usize| read_u16(colr, offset);
+  let (Some(base_count), Some(base_offset), Some(layers_offset)) =
+    (field(2), read_u32(colr, 4), read_u32(colr, 8))
+  else {
+    return;
+  };
+  let (base_offset, layers_offset) = (base_offset as usize, layers_offset as usize);
+
+  let mut layers = vec![];
+  for record in (0..base_count as usize).map(|i| base_offset + i * 6) {
+    let (Some(glyph), Some(first), Some(count)) =
+      (field(record), field(record + 2), field(record + 4))
+    else {
+      return;
+    };
+    if glyphs.contains(&glyph) {
+      for layer in first..first.saturating_add(count) {
+        layers.extend(field(layers_offset + layer as usize * 4));
+      }
+    }
+  }
+
+  glyphs.extend(layers);
+}
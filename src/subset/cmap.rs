@@ -0,0 +1,109 @@
+//! Character to glyph mapping of a subset.
+
+use super::{push_u16, push_u32};
+use std::collections::BTreeMap;
+
+/// Largest number of segments that still fits the 16-bit length of a format 4 subtable.
+const MAX_SEGMENTS: usize = (0xFFFF - 16) / 8;
+
+/// Builds a `cmap` with a format 4 subtable for the BMP, when it fits, and a format 12 one.
+pub fn build(mapping: &BTreeMap<u32, u16>) -> Vec<u8> {
+  let mut subtables = vec![];
+  if let Some(format4) = format4(mapping) {
+    subtables.push((1u16, format4));
+  }
+  subtables.push((10, format12(mapping)));
+
+  let mut cmap = vec![];
+  push_u16(&mut cmap, 0);
+  push_u16(&mut cmap, subtables.len() as u16);
+
+  let mut offset = 4 + 8 * subtables.len();
+  for (encoding, subtable) in &subtables {
+    push_u16(&mut cmap, 3);
+    push_u16(&mut cmap, *encoding);
+    push_u32(&mut cmap, offset as u32);
+    offset += subtable.len();
+  }
+  for (_, subtable) in subtables {
+    cmap.extend(subtable);
+  }
+
+  cmap
+}
+
+/// Groups consecutive code points mapped to consecutive glyphs into `(first, last, glyph)`.
+fn groups(mapping: impl Iterator<Item = (u32, u16)>) -> Vec<(u32, u32, u16)> {
+  let mut groups: Vec<(u32, u32, u16)> = vec![];
+
+  for (codepoint, glyph) in mapping {
+    match groups.last_mut() {
+      Some((first, last, start))
+        if *last + 1 == codepoint && *start as u32 + (codepoint - *first) == glyph as u32 =>
+      {
+        *last = codepoint;
+      }
+      _ => groups.push((codepoint, codepoint, glyph)),
+    }
+  }
+
+  groups
+}
+
+fn format4(mapping: &BTreeMap<u32, u16>) -> Option<Vec<u8>> {
+  let mut segments = groups(mapping.range(..0xFFFF).map(|(&codepoint, &glyph)| (codepoint, glyph)));
+  // the mandatory last segment maps U+FFFF to .notdef
+  segments.push((0xFFFF, 0xFFFF, 0));
+
+  if segments.len() > MAX_SEGMENTS {
+    return None;
+  }
+
+  let count = segments.len() as u16;
+  let entry_selector = 15 - count.leading_zeros() as u16;
+  let search_range = 2 << entry_selector;
+
+  let mut table = vec![];
+  push_u16(&mut table, 4);
+  push_u16(&mut table, 16 + 8 * count);
+  push_u16(&mut table, 0);
+  push_u16(&mut table, count * 2);
+  push_u16(&mut table, search_range);
+  push_u16(&mut table, entry_selector);
+  push_u16(&mut table, count * 2 - search_range);
+  for (_, last, _) in &segments {
+    push_u16(&mut table, *last as u16);
+  }
+  push_u16(&mut table, 0);
+  for (first, _, _) in &segments {
+    push_u16(&mut table, *first as u16);
+  }
+  for (first, _, glyph) in &segments {
+    // glyph = code point + delta, modulo 65536
+    let delta = if *first == 0xFFFF { 1 } else { glyph.wrapping_sub(*first as u16) };
+    push_u16(&mut table, delta);
+  }
+  for _ in &segments {
+    push_u16(&mut table, 0);
+  }
+
+  Some(table)
+}
+
+fn format12(mapping: &BTreeMap<u32, u16>) -> Vec<u8> {
+  let groups = groups(mapping.iter().map(|(&codepoint, &glyph)| (codepoint, glyph)));
+
+  let mut table = vec![];
+  push_u16(&mut table, 12);
+  push_u16(&mut table, 0);
+  push_u32(&mut table, 16 + 12 * groups.len() as u32);
+  push_u32(&mut table, 0);
+  push_u32(&mut table, groups.len() as u32);
+  for (first, last, glyph) in groups {
+    push_u32(&mut table, first);
+    push_u32(&mut table, last);
+    push_u32(&mut table, glyph as u32);
+  }
+
+  table
+}
@@ -0,0 +1,145 @@
+//! TrueType outlines and their variations.
+
+use super::{push_u16, push_u32};
+use crate::provider::sfnt::{
+  read_u16, read_u32, ARG_1_AND_2_ARE_WORDS, MORE_COMPONENTS, WE_HAVE_AN_X_AND_Y_SCALE,
+  WE_HAVE_A_SCALE, WE_HAVE_A_TWO_BY_TWO,
+};
+use std::collections::BTreeSet;
+use ttf_parser::{Face, Tag};
+
+const HEAD: Tag = Tag::from_bytes(b"head");
+
+/// Reads the `numGlyphs + 1` glyph offsets out of `loca`.
+pub fn offsets(face: &Face<'_>, loca: &[u8]) -> Option<Vec<u32>> {
+  let long = read_u16(face.raw_face().table(HEAD)?, 50)? != 0;
+  let count = face.number_of_glyphs() as usize + 1;
+
+  (0..count)
+    .map(|i| {
+      if long {
+        read_u32(loca, i * 4)
+      } else {
+        read_u16(loca, i * 2).map(|offset| offset as u32 * 2)
+      }
+    })
+    .collect()
+}
+
+/// Adds the components of composite glyphs in `glyphs`, recursively.
+pub fn add_components(glyf: &[u8], offsets: &[u32], glyphs: &mut BTreeSet<u16>) {
+  let mut pending: Vec<u16> = glyphs.iter().copied().collect();
+
+  while let Some(glyph) = pending.pop() {
+    let Some(data) = glyph_data(glyf, offsets, glyph) else {
+      continue;
+    };
+    // a negative number of contours marks a composite glyph
+    if data.len() < 10 || (read_u16(data, 0).unwrap_or(0) as i16) >= 0 {
+      continue;
+    }
+
+    let mut offset = 10;
+    while let (Some(flags), Some(component)) = (read_u16(data, offset), read_u16(data, offset + 2))
+    {
+      if glyphs.insert(component) {
+        pending.push(component);
+      }
+
+      offset += 4 + if flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 };
+      if flags & WE_HAVE_A_SCALE != 0 {
+        offset += 2;
+      } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+        offset += 4;
+      } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+        offset += 8;
+      }
+
+      if flags & MORE_COMPONENTS == 0 {
+        break;
+      }
+    }
+  }
+}
+
+/// Rebuilds `glyf` and `loca` with only the outlines of `glyphs` left.
+///
+/// Returns both tables and whether `loca` uses the long format.
+pub fn subset(glyf: &[u8], offsets: &[u32], glyphs: &BTreeSet<u16>) -> (Vec<u8>, Vec<u8>, bool) {
+  let mut new_glyf = vec![];
+  let mut new_offsets = vec![0u32];
+
+  for glyph in 0..offsets.len().saturating_sub(1) {
+    if glyphs.contains(&(glyph as u16)) {
+      if let Some(data) = glyph_data(glyf, offsets, glyph as u16) {
+        new_glyf.extend_from_slice(data);
+        new_glyf.resize(new_glyf.len().next_multiple_of(4), 0);
+      }
+    }
+    new_offsets.push(new_glyf.len() as u32);
+  }
+
+  let long = new_glyf.len() > 0x1FFFE;
+  let mut loca = vec![];
+  for offset in new_offsets {
+    if long {
+      push_u32(&mut loca, offset);
+    } else {
+      push_u16(&mut loca, (offset / 2) as u16);
+    }
+  }
+
+  (new_glyf, loca, long)
+}
+
+/// Drops the variation data of glyphs that lost their outlines.
+pub fn subset_gvar(gvar: &[u8], glyphs: &BTreeSet<u16>) -> Option<Vec<u8>> {
+  let axis_count = read_u16(gvar, 4)? as usize;
+  let shared_count = read_u16(gvar, 6)? as usize;
+  let shared_offset = read_u32(gvar, 8)? as usize;
+  let glyph_count = read_u16(gvar, 12)?;
+  let long = read_u16(gvar, 14)? & 1 != 0;
+  let data_offset = read_u32(gvar, 16)? as usize;
+
+  let offset = |glyph: usize| {
+    let offset = if long {
+      read_u32(gvar, 20 + glyph * 4)? as usize
+    } else {
+      read_u16(gvar, 20 + glyph * 2)? as usize * 2
+    };
+    Some(data_offset + offset)
+  };
+
+  let shared = gvar.get(shared_offset..shared_offset + shared_count * axis_count * 2)?;
+
+  let mut data = vec![];
+  let mut offsets = vec![0u32];
+  for glyph in 0..glyph_count {
+    if glyphs.contains(&glyph) {
+      let start = offset(glyph as usize)?;
+      let end = offset(glyph as usize + 1)?;
+      data.extend_from_slice(gvar.get(start..end)?);
+    }
+    offsets.push(data.len() as u32);
+  }
+
+  // the header is kept, but offsets are always written in the long format
+  let new_shared_offset = 20 + offsets.len() * 4;
+  let mut out = gvar.get(..20)?.to_vec();
+  out[8..12].copy_from_slice(&(new_shared_offset as u32).to_be_bytes());
+  out[14..16].copy_from_slice(&(read_u16(gvar, 14)? | 1).to_be_bytes());
+  out[16..20].copy_from_slice(&((new_shared_offset + shared.len()) as u32).to_be_bytes());
+  for offset in offsets {
+    push_u32(&mut out, offset);
+  }
+  out.extend_from_slice(shared);
+  out.extend_from_slice(&data);
+
+  Some(out)
+}
+
+fn glyph_data<'a>(glyf: &'a [u8], offsets: &[u32], glyph: u16) -> Option<&'a [u8]> {
+  let start = *offsets.get(glyph as usize)? as usize;
+  let end = *offsets.get(glyph as usize + 1)? as usize;
+  glyf.get(start..end).filter(|data| !data.is_empty())
+}
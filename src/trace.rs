@@ -0,0 +1,164 @@
+//! Requests of Figma and the answers of ffh, written to a trace file to reproduce issues elsewhere.
+
+use actix_web::http::header::{HeaderMap, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+  collections::BTreeMap,
+  fs::{self, File, OpenOptions},
+  io::{self, BufRead, BufReader, Write},
+  path::{Path, PathBuf},
+  sync::Mutex,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TraceErr {
+  #[error("Unable to access the trace {0}: {1}")]
+  Io(PathBuf, #[source] io::Error),
+
+  #[error("Invalid entry on line {1} of the trace {0}: {2}")]
+  Parse(PathBuf, usize, #[source] serde_json::Error),
+}
+
+type Result<T, E = TraceErr> = std::result::Result<T, E>;
+
+/// Request headers that change the answer of ffh, the only ones recorded.
+pub const REQUEST_HEADERS: [&str; 5] = ["origin", "referer", "range", "if-none-match", "if-range"];
+/// Response headers worth comparing when replaying.
+pub const RESPONSE_HEADERS: [&str; 4] = ["content-type", "content-range", "etag", "vary"];
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TraceConfig {
+  /// Trace file requests are appended to, nothing is recorded when unset.
+  pub path: Option<PathBuf>,
+}
+
+/// A response body as recorded, fonts and other binary data only by their hash.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceBody {
+  Empty,
+  Json { value: Value },
+  Text { text: String },
+  Binary { sha256: String, length: usize },
+}
+
+impl TraceBody {
+  pub fn new(content_type: Option<&str>, body: &[u8]) -> Self {
+    if body.is_empty() {
+      return Self::Empty;
+    }
+
+    let content_type = content_type.unwrap_or_default();
+    if content_type.starts_with("application/json") {
+      if let Ok(value) = serde_json::from_slice(body) {
+        return Self::Json { value };
+      }
+    }
+    if content_type.starts_with("text/") {
+      if let Ok(text) = std::str::from_utf8(body) {
+        return Self::Text { text: text.to_owned() };
+      }
+    }
+    Self::Binary { sha256: format!("{:x}", Sha256::digest(body)), length: body.len() }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct TraceResponse {
+  pub status: u16,
+  pub headers: BTreeMap<String, String>,
+  pub body: TraceBody,
+}
+
+impl TraceResponse {
+  pub fn new(status: u16, headers: &HeaderMap, body: &[u8]) -> Self {
+    let content_type = headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok());
+    Self {
+      status,
+      headers: pick(headers, &RESPONSE_HEADERS),
+      body: TraceBody::new(content_type, body),
+    }
+  }
+}
+
+/// A request and the response it got.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TraceEntry {
+  /// RFC 3339 time of the request.
+  pub time: String,
+  pub method: String,
+  /// Path and query.
+  pub uri: String,
+  pub headers: BTreeMap<String, String>,
+  pub response: TraceResponse,
+}
+
+impl TraceEntry {
+  pub fn new(method: &str, uri: &str, headers: &HeaderMap, response: TraceResponse) -> Self {
+    Self {
+      time: chrono::Local::now().to_rfc3339(),
+      method: method.to_owned(),
+      uri: uri.to_owned(),
+      headers: pick(headers, &REQUEST_HEADERS),
+      response,
+    }
+  }
+}
+
+/// Appends entries to a trace file, one JSON object per line.
+pub struct Tracer {
+  path: PathBuf,
+  file: Mutex<File>,
+}
+
+impl Tracer {
+  pub fn new(path: &Path) -> Result<Self> {
+    let open = || {
+      if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+      }
+      OpenOptions::new().create(true).append(true).open(path)
+    };
+    let file = open().map_err(|e| TraceErr::Io(path.to_owned(), e))?;
+    Ok(Self { path: path.to_owned(), file: Mutex::new(file) })
+  }
+
+  pub fn record(&self, entry: &TraceEntry) {
+    let Ok(mut line) = serde_json::to_vec(entry) else {
+      return;
+    };
+    line.push(b'\n');
+    if let Err(e) = self.file.lock().unwrap().write_all(&line) {
+      log::warn!("Unable to write the trace {}: {e}", self.path.to_string_lossy());
+    }
+  }
+}
+
+/// Reads every entry of a trace file.
+pub fn load(path: &Path) -> Result<Vec<TraceEntry>> {
+  let file = File::open(path).map_err(|e| TraceErr::Io(path.to_owned(), e))?;
+  let mut entries = vec![];
+  for (number, line) in BufReader::new(file).lines().enumerate() {
+    let line = line.map_err(|e| TraceErr::Io(path.to_owned(), e))?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let entry =
+      serde_json::from_str(&line).map_err(|e| TraceErr::Parse(path.to_owned(), number + 1, e))?;
+    entries.push(entry);
+  }
+  Ok(entries)
+}
+
+fn pick(headers: &HeaderMap, names: &[&str]) -> BTreeMap<String, String> {
+  (names.iter())
+    .filter_map(|&name| {
+      let value = headers.get(name)?.to_str().ok()?;
+      Some((name.to_owned(), value.to_owned()))
+    })
+    .collect()
+}
@@ -0,0 +1,162 @@
+//! How often each font was requested by Figma, kept across restarts to tell used fonts from unused
+//! ones.
+
+use crate::provider::FontDescriptor;
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fs, io,
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+  },
+  time::{SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum UsageErr {
+  #[error("Unable to access the usage statistics {0}: {1}")]
+  Io(PathBuf, #[source] io::Error),
+
+  #[error("Invalid usage statistics {0}: {1}")]
+  Parse(PathBuf, #[source] serde_json::Error),
+}
+
+type Result<T, E = UsageErr> = std::result::Result<T, E>;
+
+#[derive(Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct UsageConfig {
+  pub enabled: bool,
+  /// Where the statistics are kept, `ffh/usage.json` in the user's data directory by default.
+  pub path: Option<PathBuf>,
+  /// Seconds between writes of the statistics by a running server.
+  pub flush_interval: u64,
+}
+
+impl Default for UsageConfig {
+  fn default() -> Self {
+    Self { enabled: true, path: None, flush_interval: 60 }
+  }
+}
+
+impl UsageConfig {
+  pub fn path(&self) -> Option<PathBuf> {
+    (self.path.clone()).or_else(|| dirs::data_dir().map(|dir| dir.join("ffh").join("usage.json")))
+  }
+}
+
+/// Requests of a single font, times in seconds since the Unix epoch.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FontUsage {
+  pub family: String,
+  pub requests: u64,
+  pub first_requested: u64,
+  pub last_requested: u64,
+}
+
+/// Usage of every font requested so far, by PostScript name.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct UsageReport {
+  pub fonts: BTreeMap<String, FontUsage>,
+}
+
+impl UsageReport {
+  /// Reads the statistics at `path`, empty ones when nothing was recorded yet.
+  pub fn load(path: &Path) -> Result<Self> {
+    match fs::read(path) {
+      Ok(data) => serde_json::from_slice(&data).map_err(|e| UsageErr::Parse(path.to_owned(), e)),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+      Err(e) => Err(UsageErr::Io(path.to_owned(), e)),
+    }
+  }
+
+  /// Fonts with the most requests first.
+  pub fn by_use(&self) -> Vec<(&String, &FontUsage)> {
+    let mut fonts: Vec<_> = self.fonts.iter().collect();
+    fonts.sort_by(|(a_name, a), (b_name, b)| {
+      (b.requests.cmp(&a.requests))
+        .then(b.last_requested.cmp(&a.last_requested))
+        .then(a_name.cmp(b_name))
+    });
+    fonts
+  }
+
+  /// PostScript and family names of the `fonts` never requested, sorted, each listed once.
+  pub fn unused<'a>(&self, fonts: &'a [FontDescriptor]) -> Vec<(&'a str, &'a str)> {
+    let unused: BTreeSet<_> = (fonts.iter())
+      .filter(|font| !self.fonts.contains_key(&font.postscript))
+      .map(|font| (font.postscript.as_str(), font.family.as_str()))
+      .collect();
+    unused.into_iter().collect()
+  }
+}
+
+/// Usage statistics of a running server, written back to disk by [`UsageStats::flush`].
+pub struct UsageStats {
+  path: Option<PathBuf>,
+  report: Mutex<UsageReport>,
+  dirty: AtomicBool,
+}
+
+impl UsageStats {
+  /// Continues the statistics on disk, or records nothing when disabled.
+  pub fn new(config: &UsageConfig) -> Self {
+    let path = config.path().filter(|_| config.enabled);
+    let report = match path.as_deref().map(UsageReport::load) {
+      Some(Ok(report)) => report,
+      Some(Err(e)) => {
+        log::warn!("{e}, starting over");
+        UsageReport::default()
+      }
+      None => UsageReport::default(),
+    };
+    Self { path, report: Mutex::new(report), dirty: AtomicBool::new(false) }
+  }
+
+  pub fn record(&self, postscript: &str, family: &str) {
+    if self.path.is_none() {
+      return;
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs());
+    let mut report = self.report.lock().unwrap();
+    let usage = report.fonts.entry(postscript.to_owned()).or_insert_with(|| FontUsage {
+      family: family.to_owned(),
+      requests: 0,
+      first_requested: now,
+      last_requested: now,
+    });
+    usage.requests += 1;
+    usage.last_requested = now;
+    self.dirty.store(true, Ordering::Relaxed);
+  }
+
+  /// Writes the statistics if anything was recorded since the last write.
+  pub fn flush(&self) -> Result<()> {
+    let Some(path) = &self.path else {
+      return Ok(());
+    };
+    if !self.dirty.swap(false, Ordering::Relaxed) {
+      return Ok(());
+    }
+
+    let data = serde_json::to_vec_pretty(&*self.report.lock().unwrap())
+      .map_err(|e| UsageErr::Parse(path.clone(), e))?;
+    let write = || {
+      if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+      }
+      // written next to the statistics and renamed, so that a crash never leaves half a file
+      let partial = path.with_extension("json.partial");
+      fs::write(&partial, data)?;
+      fs::rename(partial, path)
+    };
+    write().map_err(|e| {
+      self.dirty.store(true, Ordering::Relaxed);
+      UsageErr::Io(path.clone(), e)
+    })
+  }
+}
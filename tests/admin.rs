@@ -0,0 +1,120 @@
+//! Drives the routes of the loopback-only admin listener against the fonts in `tests/fonts`.
+
+use actix_web::{http::StatusCode, test, web, App};
+use chrono::DateTime;
+use common::fonts_dir;
+use ffh::{
+  config::Config,
+  middleware,
+  provider::{DirectoryFontProvider, SourceConfig, SourceKind},
+  route, Listeners, ServerState,
+};
+use serde_json::{json, Value};
+use std::time::{Duration, UNIX_EPOCH};
+
+mod common;
+
+macro_rules! app {
+  ($state:expr) => {
+    test::init_service(
+      App::new()
+        .app_data($state.clone())
+        .wrap(middleware::JsonErrors)
+        .configure(route::admin)
+        .default_service(web::to(route::notfound::handler)),
+    )
+    .await
+  };
+}
+
+fn state_with(config: &Config) -> web::Data<ServerState> {
+  let provider = Box::new(DirectoryFontProvider::new(fonts_dir()));
+  web::Data::new(ServerState::with_provider(provider, config).unwrap())
+}
+
+fn state() -> web::Data<ServerState> {
+  state_with(&Config::default())
+}
+
+fn get(uri: &str) -> test::TestRequest {
+  test::TestRequest::get().uri(uri)
+}
+
+#[actix_web::test]
+async fn font_metadata_describes_the_font() {
+  let state = state();
+  let app = app!(state);
+
+  let req = get("/fonts/DejaVuSans/metadata").to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["postscript"], "DejaVuSans");
+  assert_eq!(body["path"], fonts_dir().join("DejaVuSans.ttf").to_string_lossy().as_ref());
+  assert_eq!(body["vendor"], "PfEd");
+  assert_eq!(body["panose"], serde_json::json!([2, 11, 6, 3, 3, 8, 4, 2, 2, 4]));
+  assert_eq!(body["outlines"], "TrueType");
+  assert_eq!(body["metrics"]["units_per_em"], 2048);
+  let tables = body["tables"].as_array().unwrap();
+  assert!(tables.contains(&"glyf".into()) && tables.contains(&"OS/2".into()));
+
+  let family = body["names"].as_array().unwrap().iter().find(|name| name["id"] == 1).unwrap();
+  assert_eq!((&family["kind"], &family["language"]), (&"family".into(), &"en".into()));
+  assert_eq!(family["value"], "DejaVu Sans");
+
+  let req = get("/fonts/LibertinusSerif-Regular/metadata").to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["outlines"], "CFF");
+}
+
+#[actix_web::test]
+async fn font_metadata_of_unknown_fonts_is_not_found() {
+  let state = state();
+  let app = app!(state);
+
+  let res = test::call_service(&app, get("/fonts/Missing/metadata").to_request()).await;
+  assert_eq!(res.status(), StatusCode::NOT_FOUND);
+  let body: Value = test::read_body_json(res).await;
+  assert_eq!(body["code"], "font_not_found");
+}
+
+#[actix_web::test]
+async fn status_reports_build_sources_and_scan() {
+  let source = SourceConfig {
+    kind: SourceKind::Directory,
+    path: Some(fonts_dir()),
+    label: Some("fixtures".to_owned()),
+    priority: None,
+  };
+  let state = state_with(&Config { sources: vec![source], ..Default::default() });
+  let app = app!(state);
+
+  let body: Value = test::call_and_read_body_json(&app, get("/status").to_request()).await;
+  assert_eq!(body["build"]["version"], env!("CARGO_PKG_VERSION"));
+  // the commit checked out when building, read by build.rs
+  let git_hash = option_env!("FFH_GIT_HASH").unwrap_or("unknown");
+  assert_eq!(body["build"]["git_hash"], git_hash);
+  assert!(git_hash == "unknown" || git_hash.chars().all(|c| c.is_ascii_hexdigit()), "{git_hash}");
+
+  assert_eq!(body["provider"]["api_version"], 35);
+  assert_eq!(body["provider"]["sources"], json!([{ "label": "fixtures", "kind": "directory" }]));
+  assert_eq!(body["provider"]["libraries"], json!([]));
+  assert_eq!((&body["scan"]["fonts"], &body["scan"]["families"]), (&3.into(), &3.into()));
+  assert!(DateTime::parse_from_rfc3339(body["scan"]["scanned_at"].as_str().unwrap()).is_ok());
+  assert!(body["scan"]["duration"].as_f64().unwrap() >= 0.0);
+  assert!(body["protocol"].as_u64().unwrap() >= 1);
+  assert_eq!(body["profile"], Value::Null);
+  // nothing is bound in tests
+  let listeners = json!({ "figma": [], "admin": [], "certificate_expiry": null });
+  assert_eq!(body["listeners"], listeners);
+
+  let listeners = Listeners {
+    figma: vec!["https://127.0.0.1:7335".to_owned()],
+    admin: vec!["http://127.0.0.1:44951".to_owned()],
+    certificate_expiry: Some(UNIX_EPOCH + Duration::from_secs(2_000_000_000)),
+  };
+  assert!(state.listeners.set(listeners).is_ok());
+  let body: Value = test::call_and_read_body_json(&app, get("/status").to_request()).await;
+  assert_eq!(body["listeners"]["figma"], json!(["https://127.0.0.1:7335"]));
+  assert_eq!(body["listeners"]["admin"], json!(["http://127.0.0.1:44951"]));
+  let expiry = body["listeners"]["certificate_expiry"].as_str().unwrap();
+  assert_eq!(DateTime::parse_from_rfc3339(expiry).unwrap().timestamp(), 2_000_000_000);
+}
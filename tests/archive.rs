@@ -0,0 +1,184 @@
+//! Extracts the fonts in `tests/fonts` out of zip archives built on the fly.
+
+use common::font;
+use ffh::provider::{ArchiveErr, ArchiveFontProvider, FontProvider, FontProviderErr};
+use flate2::{write::DeflateEncoder, Compression};
+use std::{
+  env, fs,
+  io::Write,
+  path::{Path, PathBuf},
+  sync::atomic::{AtomicUsize, Ordering},
+  thread,
+  time::Duration,
+};
+
+mod common;
+
+/// A directory of its own for every call, tests running in parallel.
+fn temp_dir() -> PathBuf {
+  static DIRS: AtomicUsize = AtomicUsize::new(0);
+  let dir = DIRS.fetch_add(1, Ordering::Relaxed);
+  let dir = env::temp_dir().join(format!("ffh-archive-{}-{dir}", std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+/// Builds a zip archive of `(name, content, deflated)` entries, without checksums since they are
+/// not verified.
+fn zip(entries: &[(&str, &[u8], bool)], comment: &[u8]) -> Vec<u8> {
+  let mut data = vec![];
+  let mut directory = vec![];
+  for &(name, content, deflated) in entries {
+    let (method, compressed) = if deflated {
+      let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+      encoder.write_all(content).unwrap();
+      (8u16, encoder.finish().unwrap())
+    } else {
+      (0u16, content.to_vec())
+    };
+    let header = |signature: u32, central: bool| {
+      let mut header = signature.to_le_bytes().to_vec();
+      if central {
+        header.extend(20u16.to_le_bytes()); // version made by
+      }
+      header.extend(20u16.to_le_bytes()); // version needed
+      header.extend(0u16.to_le_bytes()); // flags
+      header.extend(method.to_le_bytes());
+      header.extend([0; 8]); // time, date and CRC-32
+      header.extend((compressed.len() as u32).to_le_bytes());
+      header.extend((content.len() as u32).to_le_bytes());
+      header.extend((name.len() as u16).to_le_bytes());
+      header.extend(0u16.to_le_bytes()); // extra field length
+      header
+    };
+
+    let mut record = header(0x02014b50, true);
+    record.extend([0; 10]); // comment length, disk and attributes
+    record.extend((data.len() as u32).to_le_bytes());
+    record.extend(name.as_bytes());
+    directory.push(record);
+
+    data.extend(header(0x04034b50, false));
+    data.extend(name.as_bytes());
+    data.extend(compressed);
+  }
+
+  let offset = data.len() as u32;
+  let size: usize = directory.iter().map(Vec::len).sum();
+  data.extend(directory.concat());
+  data.extend(0x06054b50u32.to_le_bytes());
+  data.extend([0; 4]); // disks
+  data.extend((entries.len() as u16).to_le_bytes());
+  data.extend((entries.len() as u16).to_le_bytes());
+  data.extend((size as u32).to_le_bytes());
+  data.extend(offset.to_le_bytes());
+  data.extend((comment.len() as u16).to_le_bytes());
+  data.extend(comment);
+  data
+}
+
+fn scan(archive: &Path, cache: &Path) -> Result<Vec<String>, FontProviderErr> {
+  let scan = ArchiveFontProvider::new(archive, cache).get_all_fonts()?;
+  let mut names: Vec<_> = scan.fonts.into_iter().map(|font| font.postscript).collect();
+  names.sort();
+  Ok(names)
+}
+
+fn archive_err(result: Result<Vec<String>, FontProviderErr>) -> ArchiveErr {
+  match result {
+    Err(FontProviderErr::Archive(_, e)) => e,
+    other => panic!("expected an archive error, got {:?}", other.err()),
+  }
+}
+
+#[test]
+fn stored_and_deflated_fonts_are_extracted() {
+  let dir = temp_dir();
+  let archive = dir.join("fonts.zip");
+  let (sans, serif) = (font("DejaVuSans.ttf"), font("DejaVuSerif-Italic.ttf"));
+  let entries: &[(&str, &[u8], bool)] = &[
+    ("fonts/DejaVuSans.ttf", &sans, true),
+    ("DejaVuSerif-Italic.TTF", &serif, false),
+    ("__MACOSX/fonts/._DejaVuSans.ttf", b"resource fork", false),
+    ("README.txt", b"not a font", true),
+  ];
+  // the end of central directory record is found behind a comment
+  fs::write(&archive, zip(entries, b"brand fonts, do not share")).unwrap();
+
+  let cache = dir.join("cache");
+  assert_eq!(scan(&archive, &cache).unwrap(), ["DejaVuSans", "DejaVuSerif-Italic"]);
+  // already extracted, so served from the same directory
+  assert_eq!(scan(&archive, &cache).unwrap(), ["DejaVuSans", "DejaVuSerif-Italic"]);
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn entries_escaping_the_extraction_directory_are_skipped() {
+  let dir = temp_dir();
+  let archive = dir.join("evil.zip");
+  let sans = font("DejaVuSans.ttf");
+  let entries: &[(&str, &[u8], bool)] = &[
+    ("../evil.ttf", &sans, false),
+    ("fonts/../../evil.ttf", &sans, false),
+    ("/tmp/evil.ttf", &sans, false),
+    ("fonts/DejaVuSans.ttf", &sans, false),
+  ];
+  fs::write(&archive, zip(entries, b"")).unwrap();
+
+  let cache = dir.join("cache");
+  assert_eq!(scan(&archive, &cache).unwrap(), ["DejaVuSans"]);
+  let outside = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name());
+  assert_eq!(outside.collect::<Vec<_>>().len(), 2, "only the archive and the cache");
+  for extracted in fs::read_dir(&cache).unwrap() {
+    assert!(!extracted.unwrap().path().join("evil.ttf").exists());
+  }
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unsupported_archives_are_refused() {
+  let dir = temp_dir();
+  let (archive, cache) = (dir.join("fonts.zip"), dir.join("cache"));
+  let sans = font("DejaVuSans.ttf");
+
+  fs::write(&archive, &sans).unwrap();
+  assert!(matches!(archive_err(scan(&archive, &cache)), ArchiveErr::NotZip));
+
+  // Zip64 archives mark the entry count as 0xFFFF
+  let mut data = zip(&[("DejaVuSans.ttf", &sans, false)], b"");
+  let end = data.len() - 22;
+  data[end + 8..end + 12].fill(0xFF);
+  fs::write(&archive, data).unwrap();
+  assert!(matches!(archive_err(scan(&archive, &cache)), ArchiveErr::Zip64));
+
+  let mut data = zip(&[("DejaVuSans.ttf", &sans, false)], b"");
+  let end = data.len() - 22;
+  let directory = u32::from_le_bytes(data[end + 16..end + 20].try_into().unwrap()) as usize;
+  data[directory + 10] = 14; // LZMA
+  fs::write(&archive, data).unwrap();
+  assert!(matches!(archive_err(scan(&archive, &cache)), ArchiveErr::UnsupportedCompression(..)));
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn only_versions_before_the_replaced_one_are_removed() {
+  let dir = temp_dir();
+  let (archive, cache) = (dir.join("fonts.zip"), dir.join("cache"));
+  let sans = font("DejaVuSans.ttf");
+  let versions = |cache: &Path| {
+    let dirs = fs::read_dir(cache).unwrap().next().unwrap().unwrap().path();
+    fs::read_dir(dirs).unwrap().count()
+  };
+
+  for version in 0..3 {
+    let comment = format!("version {version}");
+    fs::write(&archive, zip(&[("DejaVuSans.ttf", &sans, false)], comment.as_bytes())).unwrap();
+    assert_eq!(scan(&archive, &cache).unwrap(), ["DejaVuSans"]);
+    // the directories are told apart by their modification time
+    thread::sleep(Duration::from_millis(20));
+  }
+  // the current version and the one it replaced
+  assert_eq!(versions(&cache), 2);
+  fs::remove_dir_all(dir).unwrap();
+}
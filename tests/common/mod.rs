@@ -0,0 +1,18 @@
+//! Fixtures shared by the integration tests, each of which uses only some of them.
+#![allow(dead_code)]
+
+use std::{fs, path::PathBuf};
+
+/// Directory of the fonts every test runs against.
+pub fn fonts_dir() -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fonts")
+}
+
+pub fn fixture(name: &str) -> PathBuf {
+  fonts_dir().join(name)
+}
+
+/// Content of the font `name` in `tests/fonts`.
+pub fn font(name: &str) -> Vec<u8> {
+  fs::read(fixture(name)).unwrap()
+}
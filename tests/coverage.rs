@@ -0,0 +1,123 @@
+//! Parses, merges and compares sets of code points, and finds the fonts in `tests/fonts` that
+//! cover them.
+
+use common::{font, fonts_dir};
+use ffh::{
+  config::Config,
+  fontcache::Lru,
+  provider::{coverage::Coverage, DirectoryFontProvider, FontDatabase},
+};
+use ttf_parser::Face;
+
+mod common;
+
+fn ranges(coverage: &Coverage) -> Vec<(u32, u32)> {
+  coverage.ranges().iter().map(|range| (*range.start(), *range.end())).collect()
+}
+
+#[test]
+fn ranges_are_parsed_like_css_unicode_ranges() {
+  let parse = |ranges: &str| self::ranges(&Coverage::parse("", ranges).unwrap());
+  assert_eq!(parse("U+41"), [(0x41, 0x41)]);
+  assert_eq!(parse("u+4e00-9FFF"), [(0x4E00, 0x9FFF)]);
+  assert_eq!(parse("U+30??"), [(0x3000, 0x30FF)]);
+  assert_eq!(parse("U+10????"), [(0x100000, 0x10FFFF)]);
+  assert_eq!(parse(" U+41 , ,U+10FFFF"), [(0x41, 0x41), (0x10FFFF, 0x10FFFF)]);
+  assert_eq!(parse(""), []);
+}
+
+#[test]
+fn invalid_ranges_are_refused() {
+  for range in
+    ["41", "U+", "U+ZZ", "U+42-41", "U+110000", "U+1234567", "U+3?4", "U+-41", "U+41-", "U+??????"]
+  {
+    assert!(Coverage::parse("", range).is_err(), "{range}");
+  }
+}
+
+#[test]
+fn overlapping_and_adjacent_ranges_are_merged() {
+  let coverage = Coverage::parse("ABCZ", "U+44-50,U+48,U+5B-5F,U+0-1").unwrap();
+  assert_eq!(ranges(&coverage), [(0, 1), (0x41, 0x50), (0x5A, 0x5F)]);
+  assert_eq!(coverage.len(), 2 + 16 + 6);
+
+  let unordered = Coverage::from_codepoints([5, 3, 4, 1, 3, u32::MAX]);
+  assert_eq!(ranges(&unordered), [(1, 1), (3, 5), (u32::MAX, u32::MAX)]);
+}
+
+#[test]
+fn sets_contain_and_cover_code_points() {
+  let coverage = Coverage::parse("", "U+20-7E,U+3000-30FF").unwrap();
+  assert!(coverage.contains(0x20) && coverage.contains(0x7E) && coverage.contains(0x3042));
+  assert!(!coverage.contains(0x1F) && !coverage.contains(0x7F) && !coverage.contains(0x3100));
+
+  assert!(coverage.covers(&Coverage::parse("Figma あ", "").unwrap()));
+  assert!(coverage.covers(&Coverage::default()));
+  assert!(!coverage.covers(&Coverage::parse("Figmä", "").unwrap()));
+  // a range spanning a gap is not covered even though both of its ends are
+  assert!(!coverage.covers(&Coverage::parse("", "U+70-3010").unwrap()));
+}
+
+#[test]
+fn sets_format_as_the_ranges_they_parse_from() {
+  let coverage = Coverage::parse("A", "U+4E00-9FFF,U+10FFFF").unwrap();
+  assert_eq!(coverage.to_string(), "U+0041,U+4E00-9FFF,U+10FFFF");
+  assert!(Coverage::parse("", &coverage.to_string()).unwrap() == coverage);
+  assert_eq!(Coverage::default().to_string(), "");
+}
+
+#[test]
+fn charset_pages_are_read_bit_by_bit() {
+  let mut ascii = [0; 8];
+  ascii[1] = 1 << 1; // U+0021
+  ascii[2] = u32::MAX; // U+0040-005F
+  let mut last = [0; 8];
+  last[7] = 1 << 31; // U+03FF
+  let coverage = Coverage::from_charset_pages([(0, ascii), (0x300, last), (0x100, [0; 8])]);
+  assert_eq!(ranges(&coverage), [(0x21, 0x21), (0x40, 0x5F), (0x3FF, 0x3FF)]);
+
+  assert!(Coverage::from_charset_pages([]).is_empty());
+}
+
+#[test]
+fn cmap_coverage_matches_the_fixture() {
+  let data = font("DejaVuSans.ttf");
+  let coverage = Coverage::from_cmap(&Face::parse(&data, 0).unwrap());
+  assert_eq!(ranges(&coverage), [(0x20, 0x7E)]);
+}
+
+#[test]
+fn fonts_covering_characters_are_found() {
+  let provider = Box::new(DirectoryFontProvider::new(fonts_dir()));
+  let database = FontDatabase::new(provider, &Config::default()).unwrap();
+  let covering = |text: &str| {
+    let mut names: Vec<_> = (database.covering(&Coverage::parse(text, "").unwrap()).into_iter())
+      .map(|font| font.postscript.clone())
+      .collect();
+    names.sort();
+    names
+  };
+
+  let all = ["DejaVuSans", "DejaVuSerif-Italic", "LibertinusSerif-Regular"];
+  assert_eq!(covering("Figma"), all);
+  // every character has to be covered
+  assert!(covering("Figma\u{4E00}").is_empty());
+  assert!(covering("\u{203C}").is_empty());
+  // answered from the cache the second time
+  assert_eq!(covering("Figma"), all);
+}
+
+#[test]
+fn least_recently_used_queries_are_forgotten_first() {
+  let queries = Lru::new(2);
+  let query = |text: &str| Coverage::parse(text, "").unwrap();
+  queries.insert(query("a"), 1, 1);
+  queries.insert(query("b"), 2, 1);
+  assert_eq!(queries.get(&query("a")), Some(1));
+
+  queries.insert(query("c"), 3, 1);
+  assert_eq!(queries.get(&query("b")), None);
+  assert_eq!(queries.get(&query("a")), Some(1));
+  assert_eq!(queries.get(&query("c")), Some(3));
+  assert_eq!(queries.usage(), (2, 2));
+}
@@ -0,0 +1,72 @@
+//! Lists the fonts of a directory tree with the directory provider.
+
+use common::fonts_dir;
+use ffh::provider::{DirectoryFontProvider, FontProvider, SkipReason};
+use std::{env, fs, path::PathBuf};
+
+mod common;
+
+/// A directory of its own holding a copy of DejaVuSans.
+fn fonts_copy(topic: &str) -> PathBuf {
+  let dir = env::temp_dir().join(format!("ffh-directory-{topic}-{}", std::process::id()));
+  fs::create_dir_all(dir.join("sub")).unwrap();
+  fs::copy(fonts_dir().join("DejaVuSans.ttf"), dir.join("sub").join("DejaVuSans.ttf")).unwrap();
+  dir
+}
+
+#[test]
+fn subdirectories_are_scanned() {
+  let scan = DirectoryFontProvider::new(fonts_dir()).get_all_fonts().unwrap();
+  let mut postscript: Vec<_> = scan.fonts.iter().map(|font| &*font.postscript).collect();
+  postscript.sort();
+  assert_eq!(postscript, ["DejaVuSans", "DejaVuSerif-Italic", "LibertinusSerif-Regular"]);
+  assert!(scan.skipped.is_empty());
+
+  let dir = fonts_copy("nested");
+  let scan = DirectoryFontProvider::new(&dir).get_all_fonts().unwrap();
+  assert_eq!(scan.fonts.len(), 1);
+  assert_eq!(scan.fonts[0].path, dir.join("sub").join("DejaVuSans.ttf"));
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn links_to_directories_are_not_followed() {
+  let dir = fonts_copy("loop");
+  // a link back to the top of the tree
+  std::os::unix::fs::symlink(&dir, dir.join("sub").join("loop")).unwrap();
+
+  let scan = DirectoryFontProvider::new(&dir).get_all_fonts().unwrap();
+  assert_eq!(scan.fonts.len(), 1);
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn unreadable_files_are_skipped() {
+  let dir = fonts_copy("unreadable");
+  let (dangling, truncated) = (dir.join("Dangling.ttf"), dir.join("Truncated.otf"));
+  std::os::unix::fs::symlink(dir.join("missing"), &dangling).unwrap();
+  fs::write(&truncated, b"OTTO").unwrap();
+
+  // the rest of the directory is still listed
+  let scan = DirectoryFontProvider::new(&dir).get_all_fonts().unwrap();
+  assert_eq!(scan.fonts.len(), 1);
+  let mut skipped: Vec<_> =
+    scan.skipped.iter().map(|font| (font.path.clone(), font.reason)).collect();
+  skipped.sort_by(|a, b| a.0.cmp(&b.0));
+  assert!(
+    skipped
+      == [
+        (Some(dangling), SkipReason::Unreadable),
+        (Some(truncated), SkipReason::UnsupportedFormat)
+      ]
+  );
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn missing_directories_fail_the_scan() {
+  let dir = env::temp_dir().join(format!("ffh-directory-missing-{}", std::process::id()));
+  assert!(DirectoryFontProvider::new(dir).get_all_fonts().is_err());
+}
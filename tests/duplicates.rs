@@ -0,0 +1,107 @@
+//! Picks the copy of a font handed to Figma under every duplicate policy.
+
+use ffh::provider::{
+  duplicates::{self, DuplicateConfig, DuplicatePolicy},
+  FontDescriptor,
+};
+use std::path::{Path, PathBuf};
+
+fn font(path: &str, index: u32) -> FontDescriptor {
+  FontDescriptor {
+    path: PathBuf::from(path),
+    index,
+    postscript: "Inter-Regular".to_owned(),
+    family: "Inter".to_owned(),
+    style: "Regular".to_owned(),
+    source: "fontconfig".to_owned(),
+    ..Default::default()
+  }
+}
+
+/// A system copy at revision 2, a user copy at revision 1 and a project copy at revision 1.
+fn copies() -> Vec<FontDescriptor> {
+  let system = FontDescriptor { revision: Some(2.0), ..font("/usr/share/fonts/Inter.ttf", 0) };
+  let user = FontDescriptor {
+    revision: Some(1.0),
+    user_installed: true,
+    ..font("/home/me/.local/share/fonts/Inter.ttf", 0)
+  };
+  let project = FontDescriptor { revision: Some(1.0), ..font("/work/fonts/Inter.ttf", 0) };
+  vec![system, user, project]
+}
+
+fn winner(config: &DuplicateConfig) -> PathBuf {
+  let (kept, conflicts) = duplicates::resolve(copies(), config);
+  assert_eq!(kept.len(), 1);
+  assert_eq!(conflicts.len(), 1);
+  assert_eq!(conflicts[0].winner, kept[0].path);
+  assert_eq!(conflicts[0].losers.len(), 2);
+  kept[0].path.clone()
+}
+
+#[test]
+fn newest_keeps_the_highest_revision() {
+  let config = DuplicateConfig { policy: DuplicatePolicy::Newest, priority: vec![] };
+  assert_eq!(winner(&config), PathBuf::from("/usr/share/fonts/Inter.ttf"));
+}
+
+#[test]
+fn user_first_keeps_the_user_installed_copy() {
+  let config = DuplicateConfig { policy: DuplicatePolicy::UserFirst, priority: vec![] };
+  assert_eq!(winner(&config), PathBuf::from("/home/me/.local/share/fonts/Inter.ttf"));
+}
+
+#[test]
+fn priority_keeps_the_copy_in_the_first_directory() {
+  let config = DuplicateConfig {
+    policy: DuplicatePolicy::Priority,
+    priority: vec![PathBuf::from("/work"), PathBuf::from("/usr")],
+  };
+  assert_eq!(winner(&config), PathBuf::from("/work/fonts/Inter.ttf"));
+}
+
+#[test]
+fn higher_priority_sources_win_over_the_policy() {
+  let mut fonts = copies();
+  fonts[2].priority = 10;
+  let (kept, _) = duplicates::resolve(fonts, &DuplicateConfig::default());
+  assert_eq!(kept[0].path, PathBuf::from("/work/fonts/Inter.ttf"));
+}
+
+#[test]
+fn faces_of_the_same_file_are_not_duplicates() {
+  // the default instance of a variable font and a named instance with the same names
+  let fonts =
+    vec![font("/usr/share/fonts/Inter.ttf", 0), font("/usr/share/fonts/Inter.ttf", 0x10000)];
+  let (kept, conflicts) = duplicates::resolve(fonts, &DuplicateConfig::default());
+  assert_eq!(kept.len(), 2);
+  assert!(conflicts.is_empty());
+
+  // another copy of the file still loses, once per file
+  let mut fonts = vec![
+    font("/usr/share/fonts/Inter.ttf", 0),
+    font("/usr/share/fonts/Inter.ttf", 0x10000),
+    font("/opt/fonts/Inter.ttf", 0),
+    font("/opt/fonts/Inter.ttf", 0x10000),
+  ];
+  fonts[0].revision = Some(2.0);
+  fonts[1].revision = Some(2.0);
+  let (kept, conflicts) = duplicates::resolve(fonts, &DuplicateConfig::default());
+  assert_eq!(kept.len(), 2);
+  assert!(kept.iter().all(|font| font.path.as_path() == Path::new("/usr/share/fonts/Inter.ttf")));
+  assert_eq!(conflicts[0].losers, vec![PathBuf::from("/opt/fonts/Inter.ttf")]);
+
+  // even when another losing copy is preferred over some of its faces
+  let mut fonts = vec![
+    font("/usr/share/fonts/Inter.ttf", 0),
+    font("/opt/fonts/Inter.ttf", 0),
+    font("/srv/fonts/Inter.ttf", 0),
+    font("/opt/fonts/Inter.ttf", 0x10000),
+  ];
+  for (font, revision) in fonts.iter_mut().zip([2.0, 1.5, 1.2, 1.0]) {
+    font.revision = Some(revision);
+  }
+  let (_, conflicts) = duplicates::resolve(fonts, &DuplicateConfig::default());
+  let losers = [PathBuf::from("/opt/fonts/Inter.ttf"), PathBuf::from("/srv/fonts/Inter.ttf")];
+  assert_eq!(conflicts[0].losers, losers);
+}
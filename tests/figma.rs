@@ -0,0 +1,528 @@
+//! Drives the `/figma/*` routes and the origin guard against the fonts in `tests/fonts`.
+
+use actix_web::{
+  http::{header, StatusCode},
+  test, web, App,
+};
+use common::{font, fonts_dir};
+use ffh::{
+  config::Config,
+  middleware,
+  provider::{
+    embedding::{EmbeddingAction, EmbeddingConfig},
+    is_user_installed,
+    sfnt::{read_u16, read_u32},
+    DirectoryFontProvider,
+  },
+  ratelimit::RouteLimit,
+  route,
+  sanitize::SanitizeConfig,
+  trace, ServerState,
+};
+use serde_json::Value;
+use std::{
+  env,
+  fs::{self, File},
+  path::PathBuf,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+mod common;
+
+const FIGMA: &str = "https://www.figma.com";
+
+fn font_path(name: &str) -> String {
+  fonts_dir().join(name).to_string_lossy().into_owned()
+}
+
+fn state() -> web::Data<ServerState> {
+  state_with(&Config::default())
+}
+
+/// The Figma listener as set up by `ffh serve`, minus logging and compression.
+macro_rules! app {
+  ($state:expr) => {
+    test::init_service(
+      App::new()
+        .app_data($state.clone())
+        .wrap(middleware::LimitRequests)
+        .wrap(middleware::AllowFigmaOnly)
+        .wrap(middleware::JsonErrors)
+        .wrap(middleware::RecordMetrics)
+        .wrap(middleware::RecordTrace)
+        .wrap(middleware::CompressExcept::new(route::UNCOMPRESSED))
+        .configure(route::figma)
+        .default_service(web::to(route::notfound::handler)),
+    )
+    .await
+  };
+}
+
+fn state_with(config: &Config) -> web::Data<ServerState> {
+  let provider = Box::new(DirectoryFontProvider::new(fonts_dir()));
+  web::Data::new(ServerState::with_provider(provider, config).unwrap())
+}
+
+fn get(uri: &str) -> test::TestRequest {
+  test::TestRequest::get().uri(uri).insert_header((header::ORIGIN, FIGMA))
+}
+
+fn seconds(time: SystemTime) -> u64 {
+  time.duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+fn font_file_uri(name: &str) -> String {
+  format!("/figma/font-file?freetype_minimum_api_version=0&file={}", font_path(name))
+}
+
+#[actix_web::test]
+async fn version_reports_the_figma_api_version() {
+  let state = state();
+  let app = app!(state);
+
+  let body: Value = test::call_and_read_body_json(&app, get("/figma/version").to_request()).await;
+  assert_eq!(body["version"], 4);
+}
+
+#[actix_web::test]
+async fn update_negotiates_the_protocol_version() {
+  let state = state();
+  let app = app!(state);
+
+  let req = get("/figma/update?version=99").to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["version"], 5);
+
+  let body: Value = test::call_and_read_body_json(&app, get("/figma/version").to_request()).await;
+  assert_eq!(body["version"], 5);
+}
+
+#[actix_web::test]
+async fn pinned_protocol_version_wins() {
+  let mut config = Config::default();
+  config.protocol.version = Some(4);
+  let state = state_with(&config);
+  let app = app!(state);
+
+  let req = get("/figma/update?version=5").to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["version"], 4);
+
+  let req = get("/figma/font-files?freetype_minimum_api_version=0&isolate=false&version=5");
+  let body: Value = test::call_and_read_body_json(&app, req.to_request()).await;
+  assert_eq!(body["version"], 4);
+}
+
+#[actix_web::test]
+async fn requests_without_origin_are_forbidden() {
+  let state = state();
+  let app = app!(state);
+
+  let req = test::TestRequest::get().uri("/figma/version").to_request();
+  let res = test::call_service(&app, req).await;
+  assert_eq!(res.status(), StatusCode::FORBIDDEN);
+  let body: Value = test::read_body_json(res).await;
+  assert_eq!(body["code"], "missing_origin");
+}
+
+#[actix_web::test]
+async fn requests_from_other_origins_are_forbidden() {
+  let state = state();
+  let app = app!(state);
+
+  for (name, value) in [(header::ORIGIN, "https://example.com"), (header::REFERER, "null")] {
+    let req = test::TestRequest::get().uri("/figma/version").insert_header((name, value));
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+  }
+
+  let metrics = state.metrics.render(&state.database, &state.font_cache);
+  assert!(metrics.contains("ffh_forbidden_requests_total{reason=\"foreign_origin\"} 2"));
+}
+
+#[actix_web::test]
+async fn figma_referer_is_allowed() {
+  let state = state();
+  let app = app!(state);
+
+  let req = test::TestRequest::get().uri("/figma/version").insert_header((header::REFERER, FIGMA));
+  let res = test::call_service(&app, req.to_request()).await;
+  assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn font_files_lists_every_fixture() {
+  let state = state();
+  let app = app!(state);
+
+  let req = get("/figma/font-files?freetype_minimum_api_version=0&isolate=false").to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["version"], 4);
+
+  let sans = &body["fontFiles"][font_path("DejaVuSans.ttf")][0];
+  assert_eq!(sans["postscript"], "DejaVuSans");
+  assert_eq!(sans["family"], "DejaVu Sans");
+  assert_eq!(sans["weight"], 400);
+  assert_eq!(sans["italic"], false);
+  assert_eq!(sans["source"], "directory");
+
+  let serif = &body["fontFiles"][font_path("DejaVuSerif-Italic.ttf")][0];
+  assert_eq!(serif["postscript"], "DejaVuSerif-Italic");
+  assert_eq!(serif["italic"], true);
+}
+
+#[actix_web::test]
+async fn font_files_answers_with_the_requested_version() {
+  let state = state();
+  let app = app!(state);
+
+  let req = get("/figma/font-files?freetype_minimum_api_version=0&isolate=false&version=5");
+  let body: Value = test::call_and_read_body_json(&app, req.to_request()).await;
+  assert_eq!(body["version"], 5);
+
+  let sans = &body["fontFiles"][font_path("DejaVuSans.ttf")][0];
+  assert_eq!(sans["family"], "DejaVu Sans");
+  assert_eq!(sans["localizedFamily"], "DejaVu Sans");
+  assert_eq!(sans["weight"], 400);
+  let path = PathBuf::from(font_path("DejaVuSans.ttf"));
+  assert_eq!(sans["userInstalled"], is_user_installed(&path));
+  assert_eq!(sans["modifiedAt"], seconds(fs::metadata(&path).unwrap().modified().unwrap()));
+}
+
+#[actix_web::test]
+async fn font_files_report_fonts_updated_in_place() {
+  let dir = env::temp_dir().join(format!("ffh-figma-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("DejaVuSans.ttf");
+  fs::copy(font_path("DejaVuSans.ttf"), &path).unwrap();
+  let file = File::options().write(true).open(&path).unwrap();
+  file.set_modified(UNIX_EPOCH + Duration::from_secs(1_000_000)).unwrap();
+
+  let provider = Box::new(DirectoryFontProvider::new(&dir));
+  let state = web::Data::new(ServerState::with_provider(provider, &Config::default()).unwrap());
+  let app = app!(state);
+  let uri = "/figma/font-files?freetype_minimum_api_version=0&isolate=false&version=5";
+  let key = path.to_string_lossy().into_owned();
+
+  let body: Value = test::call_and_read_body_json(&app, get(uri).to_request()).await;
+  assert_eq!(body["fontFiles"][&key][0]["modifiedAt"], 1_000_000);
+
+  file.set_modified(UNIX_EPOCH + Duration::from_secs(2_000_000)).unwrap();
+  let body: Value = test::call_and_read_body_json(&app, get(uri).to_request()).await;
+  assert_eq!(body["fontFiles"][&key][0]["modifiedAt"], 2_000_000);
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+/// A directory of its own holding DejaVuSans, with `patch` applied to its table `tag`.
+fn patched_fonts_dir(topic: &str, tag: &[u8; 4], patch: impl FnOnce(&mut [u8])) -> PathBuf {
+  let dir = env::temp_dir().join(format!("ffh-figma-{topic}-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let mut data = font("DejaVuSans.ttf");
+  let table = (0..read_u16(&data, 4).unwrap() as usize)
+    .map(|i| 12 + i * 16)
+    .find(|&record| &data[record..record + 4] == tag)
+    .map(|record| read_u32(&data, record + 8).unwrap() as usize)
+    .unwrap();
+  patch(&mut data[table..]);
+  fs::write(dir.join("DejaVuSans.ttf"), data).unwrap();
+  dir
+}
+
+/// A directory holding DejaVuSans with `OS/2.fsType` set to restricted license embedding.
+fn restricted_fonts_dir() -> PathBuf {
+  patched_fonts_dir("restricted", b"OS/2", |os2| {
+    os2[8..10].copy_from_slice(&0x0002u16.to_be_bytes());
+  })
+}
+
+#[actix_web::test]
+async fn restricted_fonts_are_listed_but_not_served() {
+  let dir = restricted_fonts_dir();
+  let path = dir.join("DejaVuSans.ttf").to_string_lossy().into_owned();
+  let state_with = |restricted| {
+    let config = Config {
+      embedding: EmbeddingConfig { restricted, ..Default::default() },
+      ..Default::default()
+    };
+    let provider = Box::new(DirectoryFontProvider::new(&dir));
+    web::Data::new(ServerState::with_provider(provider, &config).unwrap())
+  };
+  let files_uri = "/figma/font-files?freetype_minimum_api_version=0&isolate=false";
+  let file_uri = format!("/figma/font-file?freetype_minimum_api_version=0&file={path}");
+
+  // the default
+  let state = state_with(EmbeddingConfig::default().restricted);
+  let app = app!(state);
+  let body: Value = test::call_and_read_body_json(&app, get(files_uri).to_request()).await;
+  assert_eq!(body["fontFiles"][&path][0]["postscript"], "DejaVuSans");
+
+  for uri in [&*file_uri, "/figma/font-subset?postscript=DejaVuSans&text=Figma"] {
+    let res = test::call_service(&app, get(uri).to_request()).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN, "{uri}");
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body["code"], "embedding_restricted");
+  }
+
+  let state = state_with(EmbeddingAction::Warn);
+  let app = app!(state);
+  let res = test::call_service(&app, get(&file_uri).to_request()).await;
+  assert_eq!(res.status(), StatusCode::OK);
+
+  let state = state_with(EmbeddingAction::Hide);
+  let app = app!(state);
+  let body: Value = test::call_and_read_body_json(&app, get(files_uri).to_request()).await;
+  assert!(body["fontFiles"].as_object().unwrap().is_empty());
+  let res = test::call_service(&app, get(&file_uri).to_request()).await;
+  assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_web::test]
+async fn malformed_fonts_are_refused_or_quarantined() {
+  // without the magic number of `head`
+  let dir = patched_fonts_dir("malformed", b"head", |head| head[12..16].fill(0));
+  let path = dir.join("DejaVuSans.ttf").to_string_lossy().into_owned();
+  let state_with = |sanitize| {
+    let config = Config { sanitize, ..Default::default() };
+    let provider = Box::new(DirectoryFontProvider::new(&dir));
+    web::Data::new(ServerState::with_provider(provider, &config).unwrap())
+  };
+  let files_uri = "/figma/font-files?freetype_minimum_api_version=0&isolate=false";
+  let file_uri = format!("/figma/font-file?freetype_minimum_api_version=0&file={path}");
+  let subset_uri = "/figma/font-subset?postscript=DejaVuSans&text=Figma";
+
+  // the default lists the font and checks it once it is requested, remembering the outcome
+  let state = state_with(SanitizeConfig::default());
+  let app = app!(state);
+  let body: Value = test::call_and_read_body_json(&app, get(files_uri).to_request()).await;
+  assert_eq!(body["fontFiles"][&path][0]["postscript"], "DejaVuSans");
+  for uri in [&*file_uri, &file_uri, subset_uri] {
+    let res = test::call_service(&app, get(uri).to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY, "{uri}");
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body["code"], "unprocessable_font");
+  }
+
+  let state = state_with(SanitizeConfig { enabled: false, ..Default::default() });
+  let app = app!(state);
+  let res = test::call_service(&app, get(&file_uri).to_request()).await;
+  assert_eq!(res.status(), StatusCode::OK);
+
+  let state = state_with(SanitizeConfig { at_startup: true, ..Default::default() });
+  let quarantined = state.database.quarantined();
+  assert_eq!(quarantined.len(), 1);
+  assert_eq!(
+    (&*quarantined[0].postscript, &*quarantined[0].reason),
+    ("DejaVuSans", "Invalid `head` table: bad magic number")
+  );
+  let app = app!(state);
+  let body: Value = test::call_and_read_body_json(&app, get(files_uri).to_request()).await;
+  assert!(body["fontFiles"].as_object().unwrap().is_empty());
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_web::test]
+async fn font_files_rejects_newer_freetype() {
+  let state = state();
+  let app = app!(state);
+
+  let req = get("/figma/font-files?freetype_minimum_api_version=99&isolate=false").to_request();
+  let res = test::call_service(&app, req).await;
+  assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn font_file_serves_the_file() {
+  let state = state();
+  let app = app!(state);
+
+  // without a gzip copy in the cache the file is sent as is, the same representation as its ranges
+  let req = get(&font_file_uri("DejaVuSans.ttf")).insert_header((header::ACCEPT_ENCODING, "gzip"));
+  let res = test::call_service(&app, req.to_request()).await;
+  assert_eq!(res.status(), StatusCode::OK);
+  assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), "font/ttf");
+  assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+  assert!(res.headers().contains_key(header::ETAG));
+
+  let body = test::read_body(res).await;
+  assert_eq!(body, font("DejaVuSans.ttf"));
+}
+
+#[actix_web::test]
+async fn font_file_answers_ranges_and_revalidation() {
+  let state = state();
+  let app = app!(state);
+  let data = font("DejaVuSans.ttf");
+
+  let req = get(&font_file_uri("DejaVuSans.ttf")).insert_header((header::RANGE, "bytes=4-11"));
+  let res = test::call_service(&app, req.to_request()).await;
+  assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+  let etag = res.headers().get(header::ETAG).unwrap().clone();
+  assert_eq!(test::read_body(res).await, data[4..12]);
+
+  let req = get(&font_file_uri("DejaVuSans.ttf")).insert_header((header::RANGE, "bytes=0-3,8-11"));
+  let res = test::call_service(&app, req.to_request()).await;
+  assert_eq!(res.status(), StatusCode::OK);
+  assert!(res.headers().get(header::CONTENT_RANGE).is_none());
+  assert_eq!(test::read_body(res).await, data);
+
+  let req = get(&font_file_uri("DejaVuSans.ttf")).insert_header((header::IF_NONE_MATCH, etag));
+  let res = test::call_service(&app, req.to_request()).await;
+  assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+}
+
+#[actix_web::test]
+async fn font_file_refuses_unknown_files() {
+  let state = state();
+  let app = app!(state);
+
+  let uri = "/figma/font-file?freetype_minimum_api_version=0&file=/etc/passwd";
+  let res = test::call_service(&app, get(uri).to_request()).await;
+  assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn font_subset_builds_a_smaller_font() {
+  let state = state();
+  let app = app!(state);
+
+  let req = get("/figma/font-subset?postscript=DejaVuSans&text=Figma").to_request();
+  let res = test::call_service(&app, req).await;
+  assert_eq!(res.status(), StatusCode::OK);
+  assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), "font/ttf");
+  let etag = res.headers().get(header::ETAG).unwrap().clone();
+
+  let subset = test::read_body(res).await;
+  let face = ttf_parser::Face::parse(&subset, 0).unwrap();
+  assert!(face.glyph_index('F').is_some());
+  assert!(face.glyph_index('z').is_none());
+  assert!(subset.len() < font("DejaVuSans.ttf").len());
+
+  // other routes than font files are compressed
+  let req = get("/figma/font-subset?postscript=DejaVuSans&text=Figma")
+    .insert_header((header::ACCEPT_ENCODING, "gzip"));
+  let res = test::call_service(&app, req.to_request()).await;
+  assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+
+  // the same characters given as a range are the same subset
+  let req =
+    get("/figma/font-subset?postscript=DejaVuSans&unicodes=U%2B46,U%2B61,U%2B67,U%2B69,U%2B6D")
+      .insert_header((header::IF_NONE_MATCH, etag));
+  let res = test::call_service(&app, req.to_request()).await;
+  assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+  // counted apart from whole font files, before compression
+  let metrics = state.metrics.render(&state.database, &state.font_cache);
+  assert!(metrics.contains(&format!("ffh_font_subset_bytes_total {}\n", 2 * subset.len())));
+  assert!(metrics.contains("ffh_font_file_bytes_total 0\n"), "{metrics}");
+}
+
+#[actix_web::test]
+async fn font_subset_rejects_bad_requests() {
+  let state = state();
+  let app = app!(state);
+
+  for (uri, status) in [
+    ("/figma/font-subset?postscript=DejaVuSans", StatusCode::BAD_REQUEST),
+    ("/figma/font-subset?postscript=DejaVuSans&unicodes=U%2BZZ", StatusCode::BAD_REQUEST),
+    ("/figma/font-subset?postscript=Missing&text=a", StatusCode::NOT_FOUND),
+  ] {
+    let res = test::call_service(&app, get(uri).to_request()).await;
+    assert_eq!(res.status(), status, "{uri}");
+  }
+}
+
+#[actix_web::test]
+async fn errors_answer_with_a_code_in_the_negotiated_version() {
+  let state = state();
+  let app = app!(state);
+  test::call_service(&app, get("/figma/update?version=5").to_request()).await;
+
+  for (uri, status, code) in [
+    ("/figma/font-files?isolate=false", StatusCode::BAD_REQUEST, "invalid_query"),
+    (
+      "/figma/font-files?freetype_minimum_api_version=99&isolate=false",
+      StatusCode::BAD_REQUEST,
+      "unsupported_freetype",
+    ),
+    ("/figma/font-subset?postscript=Missing&text=a", StatusCode::NOT_FOUND, "font_not_found"),
+    ("/figma/nothing-here", StatusCode::NOT_FOUND, "not_found"),
+  ] {
+    let res = test::call_service(&app, get(uri).to_request()).await;
+    assert_eq!(res.status(), status, "{uri}");
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body["version"], 5, "{uri}");
+    assert_eq!(body["code"], code, "{uri}");
+    assert!(body["message"].as_str().is_some_and(|message| !message.is_empty()), "{uri}");
+  }
+}
+
+#[actix_web::test]
+async fn requests_over_the_rate_limit_are_refused() {
+  let mut config = Config::default();
+  let limit = RouteLimit { rate: 0.001, burst: 2 };
+  config.limits.routes.insert("/figma/font-files".to_owned(), limit);
+  let state = state_with(&config);
+  let app = app!(state);
+  let uri = "/figma/font-files?freetype_minimum_api_version=0&isolate=false";
+
+  for _ in 0..2 {
+    assert_eq!(test::call_service(&app, get(uri).to_request()).await.status(), StatusCode::OK);
+  }
+  let res = test::call_service(&app, get(uri).to_request()).await;
+  assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+  assert!(res.headers().contains_key(header::RETRY_AFTER));
+  let body: Value = test::read_body_json(res).await;
+  assert_eq!(body["code"], "rate_limited");
+  assert!(body["retryAfter"].as_u64().unwrap() > 0);
+
+  let uri = "/figma/version";
+  assert_eq!(test::call_service(&app, get(uri).to_request()).await.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn concurrent_downloads_are_capped() {
+  let mut config = Config::default();
+  config.limits.max_downloads = 1;
+  let state = state_with(&config);
+  let app = app!(state);
+  let uri = font_file_uri("DejaVuSans.ttf");
+
+  let downloading = test::call_service(&app, get(&uri).to_request()).await;
+  let res = test::call_service(&app, get(&uri).to_request()).await;
+  assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+  let body: Value = test::read_body_json(res).await;
+  assert_eq!(body["code"], "too_many_downloads");
+
+  test::read_body(downloading).await;
+  let res = test::call_service(&app, get(&uri).to_request()).await;
+  assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn trace_records_requests_and_fonts_by_hash() {
+  let path = env::temp_dir().join(format!("ffh-trace-{}.jsonl", std::process::id()));
+  let mut config = Config::default();
+  config.trace.path = Some(path.clone());
+  let state = state_with(&config);
+  let app = app!(state);
+
+  let res = test::call_service(&app, get(&font_file_uri("DejaVuSans.ttf")).to_request()).await;
+  assert_eq!(test::read_body(res).await, font("DejaVuSans.ttf"));
+  let req = test::TestRequest::get().uri("/figma/version").to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+  let entries = trace::load(&path).unwrap();
+  fs::remove_file(path).unwrap();
+  assert_eq!(entries.len(), 2);
+  assert_eq!(entries[0].headers["origin"], FIGMA);
+  assert_eq!(entries[0].response.status, 200);
+  let trace::TraceBody::Binary { length, .. } = entries[0].response.body else {
+    panic!("font recorded as text");
+  };
+  assert_eq!(length, fs::metadata(font_path("DejaVuSans.ttf")).unwrap().len() as usize);
+  assert_eq!(entries[1].response.status, 403);
+}
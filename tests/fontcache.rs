@@ -0,0 +1,125 @@
+//! Keeps font bytes in the least recently used cache and re-reads them once their file changes.
+
+use common::font;
+use ffh::fontcache::{CacheConfig, FontCache, Lru};
+use std::{
+  env,
+  fs::{self, File},
+  io,
+  path::PathBuf,
+  time::{Duration, SystemTime},
+};
+
+mod common;
+
+/// A copy of DejaVuSans that the test is free to change.
+fn font_file(topic: &str) -> PathBuf {
+  let path = env::temp_dir().join(format!("ffh-fontcache-{topic}-{}.ttf", std::process::id()));
+  fs::write(&path, font("DejaVuSans.ttf")).unwrap();
+  path
+}
+
+#[test]
+fn least_recently_used_values_are_evicted_first() {
+  let lru = Lru::new(3);
+  lru.insert("a", 1, 1);
+  lru.insert("b", 2, 1);
+  lru.insert("c", 3, 1);
+  // reading `a` makes `b` the least recently used
+  assert_eq!(lru.get("a"), Some(1));
+
+  lru.insert("d", 4, 1);
+  assert_eq!(lru.get("b"), None);
+  assert_eq!((lru.get("a"), lru.get("c"), lru.get("d")), (Some(1), Some(3), Some(4)));
+  assert_eq!(lru.usage(), (3, 3));
+}
+
+#[test]
+fn values_are_kept_within_the_budget() {
+  let lru = Lru::new(10);
+  lru.insert("a", 1, 4);
+  lru.insert("b", 2, 4);
+  // as many values as needed are evicted to make room
+  lru.insert("c", 3, 8);
+  assert_eq!(lru.usage(), (8, 1));
+  assert_eq!(lru.get("c"), Some(3));
+
+  // values larger than the whole cache are not kept, nor do they evict anything
+  lru.insert("d", 4, 11);
+  assert_eq!(lru.get("d"), None);
+  assert_eq!(lru.usage(), (8, 1));
+
+  // replacing a value gives its size back
+  lru.insert("c", 5, 2);
+  assert_eq!(lru.usage(), (2, 1));
+  assert_eq!(lru.get("c"), Some(5));
+}
+
+#[test]
+fn cached_fonts_are_read_once() {
+  let path = font_file("hits");
+  let cache = FontCache::new(&CacheConfig::default());
+  let metadata = path.metadata().unwrap();
+  let read = || fs::read(&path);
+
+  let first = cache.get_or_insert_with((path.clone(), false), &metadata, read).unwrap();
+  let second = cache
+    .get_or_insert_with((path.clone(), false), &metadata, || -> io::Result<_> { unreachable!() })
+    .unwrap();
+  assert_eq!(first.data, second.data);
+  assert_eq!((cache.hits(), cache.misses()), (1, 1));
+  assert_eq!(cache.usage(), (metadata.len(), 1));
+
+  // the clean copy of the same file is cached apart
+  cache.get_or_insert_with((path.clone(), true), &metadata, read).unwrap();
+  assert_eq!(cache.usage(), (2 * metadata.len(), 2));
+
+  fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn fonts_are_read_again_once_their_mtime_changes() {
+  let path = font_file("mtime");
+  let cache = FontCache::new(&CacheConfig::default());
+  let key = (path.clone(), false);
+  cache.get_or_insert_with(key.clone(), &path.metadata().unwrap(), || fs::read(&path)).unwrap();
+
+  // the same size, only touched
+  let modified = SystemTime::now() + Duration::from_secs(60);
+  File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+  let font = cache
+    .get_or_insert_with(key, &path.metadata().unwrap(), || Ok::<_, io::Error>(b"changed".to_vec()))
+    .unwrap();
+  assert_eq!(&font.data[..], b"changed");
+  assert_eq!((cache.hits(), cache.misses()), (0, 2));
+  // the stale bytes were replaced, not kept next to the new ones
+  assert_eq!(cache.usage(), (7, 1));
+
+  fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn gzip_copies_count_against_the_budget() {
+  let path = font_file("gzip");
+  let metadata = path.metadata().unwrap();
+  let config = CacheConfig { precompress: true, ..Default::default() };
+  let cache = FontCache::new(&config);
+
+  let font =
+    cache.get_or_insert_with((path.clone(), false), &metadata, || fs::read(&path)).unwrap();
+  let gzip = font.gzip.as_ref().unwrap();
+  assert!(gzip.len() < font.data.len());
+  assert_eq!(cache.usage(), ((font.data.len() + gzip.len()) as u64, 1));
+
+  // a cache too small for the font still serves it, without keeping it
+  let config = CacheConfig { max_bytes: metadata.len() / 2, ..Default::default() };
+  let cache = FontCache::new(&config);
+  assert!(!cache.accepts(metadata.len()));
+  cache.get_or_insert_with((path.clone(), false), &metadata, || fs::read(&path)).unwrap();
+  assert_eq!(cache.usage(), (0, 0));
+
+  let disabled = FontCache::new(&CacheConfig { enabled: false, ..Default::default() });
+  assert!(!disabled.accepts(1));
+
+  fs::remove_file(path).unwrap();
+}
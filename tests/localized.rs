@@ -0,0 +1,121 @@
+//! Reads family names in every language out of the `name` table of `tests/fonts/DejaVuSans.ttf`
+//! and picks one by language preference.
+
+use common::font;
+use ffh::provider::{
+  localized::{self, LocalizedName},
+  sfnt,
+};
+use ttf_parser::{Face, Tag};
+
+mod common;
+
+fn names(names: &[(&str, &str)]) -> Vec<LocalizedName> {
+  let name = |&(language, name): &(&str, &str)| LocalizedName {
+    language: language.to_owned(),
+    name: name.to_owned(),
+  };
+  names.iter().map(name).collect()
+}
+
+fn select(names: &[LocalizedName], languages: &[&str]) -> Option<String> {
+  let languages: Vec<_> = languages.iter().map(|language| language.to_string()).collect();
+  localized::select(names, &languages).map(str::to_owned)
+}
+
+/// DejaVuSans with its `name` table replaced by `(platform, language, name ID, name)` records,
+/// written as UTF-16 on every platform.
+fn with_names(records: &[(u16, u16, u16, &str)]) -> Vec<u8> {
+  let data = font("DejaVuSans.ttf");
+  let face = Face::parse(&data, 0).unwrap();
+  let raw = face.raw_face();
+
+  let mut strings = vec![];
+  let mut name = vec![];
+  name.extend(0u16.to_be_bytes()); // format
+  name.extend((records.len() as u16).to_be_bytes());
+  name.extend((6 + 12 * records.len() as u16).to_be_bytes());
+  for &(platform, language, id, string) in records {
+    let string: Vec<u8> = string.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    for field in [platform, 1, language, id, string.len() as u16, strings.len() as u16] {
+      name.extend(field.to_be_bytes());
+    }
+    strings.extend(string);
+  }
+  name.extend(strings);
+
+  let name_tag = Tag::from_bytes(b"name");
+  let mut tables: Vec<_> = (raw.table_records.into_iter())
+    .filter(|record| record.tag != name_tag)
+    .map(|record| (record.tag, raw.table(record.tag).unwrap().to_vec()))
+    .collect();
+  tables.push((name_tag, name));
+  sfnt::write_sfnt(0x00010000, tables)
+}
+
+#[test]
+fn the_exact_language_is_preferred() {
+  let family = names(&[("en", "Gothic"), ("zh-cn", "黑体"), ("zh-tw", "黑體")]);
+  assert_eq!(select(&family, &["zh-tw"]).unwrap(), "黑體");
+  assert_eq!(select(&family, &["ZH-CN"]).unwrap(), "黑体");
+  // the first preference found wins, whatever the order of the names
+  assert_eq!(select(&family, &["ko", "zh-tw", "zh-cn"]).unwrap(), "黑體");
+}
+
+#[test]
+fn another_region_of_the_language_comes_next() {
+  let family = names(&[("en", "Gothic"), ("zh-cn", "黑体"), ("zh-tw", "黑體")]);
+  assert_eq!(select(&family, &["zh"]).unwrap(), "黑体");
+  assert_eq!(select(&family, &["zh-hk"]).unwrap(), "黑体");
+  // before a less preferred language is matched exactly
+  assert_eq!(select(&family, &["zh-hk", "en"]).unwrap(), "黑体");
+  assert_eq!(select(&names(&[("en", "Gothic"), ("zh", "黑体")]), &["zh-tw"]).unwrap(), "黑体");
+}
+
+#[test]
+fn english_and_then_the_first_name_are_the_fallbacks() {
+  let family = names(&[("ja", "ゴシック"), ("en", "Gothic")]);
+  assert_eq!(select(&family, &["ko"]).unwrap(), "Gothic");
+  assert_eq!(select(&family, &[]).unwrap(), "Gothic");
+  assert_eq!(
+    select(&names(&[("ja", "ゴシック"), ("en-gb", "Gothic")]), &["ko"]).unwrap(),
+    "Gothic"
+  );
+
+  assert_eq!(
+    select(&names(&[("ja", "ゴシック"), ("de", "Gotisch")]), &["ko"]).unwrap(),
+    "ゴシック"
+  );
+  assert!(select(&[], &["en"]).is_none());
+}
+
+#[test]
+fn names_are_read_in_every_known_language() {
+  let data = with_names(&[
+    (3, 0x0409, 1, "Gothic"),
+    (3, 0x0411, 1, "ゴシック"),
+    (3, 0x0C04, 1, "黑體"),
+    (3, 0x0809, 1, "Gothic"),    // British English, the same name once more
+    (3, 0x0456, 1, "Gótica"),    // Galician, not a known language
+    (0, 0, 1, "Gothic Unicode"), // no language on the Unicode platform
+    (1, 11, 1, "ゴシック Mac"),  // Japanese, but not in a Unicode encoding
+    (3, 0x0409, 2, "Book"),
+  ]);
+  let face = Face::parse(&data, 0).unwrap();
+
+  let family = localized::from_name_table(&face, 1);
+  let family: Vec<_> = family.iter().map(|name| (&*name.language, &*name.name)).collect();
+  assert_eq!(family, [("en", "Gothic"), ("ja", "ゴシック"), ("zh-tw", "黑體")]);
+  assert_eq!(localized::from_name_table(&face, 2).len(), 1);
+  assert!(localized::from_name_table(&face, 16).is_empty());
+}
+
+#[test]
+fn fixture_names_are_english() {
+  let data = font("DejaVuSans.ttf");
+  let face = Face::parse(&data, 0).unwrap();
+  // the Macintosh records are in Mac Roman and left out
+  let family = localized::from_name_table(&face, 1);
+  assert!(family == names(&[("en", "DejaVu Sans")]));
+  assert_eq!(select(&localized::from_name_table(&face, 2), &["ja"]).unwrap(), "Book");
+}
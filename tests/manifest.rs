@@ -0,0 +1,142 @@
+//! Loads project manifests and checks them against the fonts in `tests/fonts`.
+
+use ffh::{
+  config::Config,
+  manifest::{Manifest, ManifestConfig, ManifestErr, VerifyStatus},
+  provider::{DirectoryFontProvider, FontDatabase, FontDescriptor, FontProvider},
+};
+use std::{
+  env, fs,
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+mod common;
+
+const DEJAVU_SHA256: &str = "f8b878331dedc4712762f30dbdf8e73b65c5912ea17f7a3b8e38eed0db3ca11d";
+
+fn fonts() -> Vec<FontDescriptor> {
+  DirectoryFontProvider::new(common::fonts_dir()).get_all_fonts().unwrap().fonts
+}
+
+/// Loads `content` through a manifest file of its own, tests running in parallel.
+fn load(content: &str) -> Result<Manifest, ManifestErr> {
+  static FILES: AtomicUsize = AtomicUsize::new(0);
+  let file = FILES.fetch_add(1, Ordering::Relaxed);
+  let path = env::temp_dir().join(format!("ffh-manifest-{}-{file}.toml", std::process::id()));
+  fs::write(&path, content).unwrap();
+  let manifest = Manifest::load(&path);
+  fs::remove_file(&path).unwrap();
+  manifest
+}
+
+/// Status and detail of verifying a manifest of a single font.
+fn verify(font: &str) -> (VerifyStatus, Option<String>) {
+  let manifest = load(&format!("[[fonts]]\n{font}")).unwrap();
+  let [verification] = &manifest.verify(&fonts())[..] else {
+    panic!("expected one verification");
+  };
+  (verification.status, verification.detail.clone())
+}
+
+#[test]
+fn matching_fonts_are_ok() {
+  let font = format!(
+    "postscript = \"DejaVuSans\"\nfamily = \"DejaVu Sans\"\nstyle = \"Book\"\n\
+     version = \"Version 2.000\"\nsha256 = \"{}\"",
+    DEJAVU_SHA256.to_uppercase()
+  );
+  let (status, detail) = verify(&font);
+  assert!(status == VerifyStatus::Ok, "{detail:?}");
+  assert!(verify("postscript = \"LibertinusSerif-Regular\"").0 == VerifyStatus::Ok);
+}
+
+#[test]
+fn missing_fonts_are_reported() {
+  assert!(verify("postscript = \"Inter-Regular\"").0 == VerifyStatus::Missing);
+}
+
+#[test]
+fn other_names_are_reported() {
+  let (status, detail) = verify("postscript = \"DejaVuSans\"\nfamily = \"DejaVu Serif\"");
+  assert!(status == VerifyStatus::NameMismatch);
+  assert_eq!(detail.unwrap(), "expected DejaVu Serif, found DejaVu Sans");
+
+  let (status, _) = verify("postscript = \"DejaVuSans\"\nstyle = \"Bold\"");
+  assert!(status == VerifyStatus::NameMismatch);
+}
+
+#[test]
+fn older_fonts_are_reported() {
+  let (status, detail) = verify("postscript = \"DejaVuSans\"\nversion = \"99.5\"");
+  assert!(status == VerifyStatus::Outdated);
+  assert!(detail.unwrap().starts_with("expected 99.5, found "));
+}
+
+#[test]
+fn other_files_are_reported() {
+  let (status, detail) =
+    verify(&format!("postscript = \"DejaVuSans\"\nsha256 = \"{}\"", "0".repeat(64)));
+  assert!(status == VerifyStatus::HashMismatch);
+  assert!(detail.unwrap().ends_with(DEJAVU_SHA256));
+}
+
+#[test]
+fn unreadable_files_are_reported() {
+  let manifest =
+    load(&format!("[[fonts]]\npostscript = \"DejaVuSans\"\nsha256 = \"{DEJAVU_SHA256}\"")).unwrap();
+  let mut fonts = fonts();
+  for font in &mut fonts {
+    font.path = font.path.with_extension("gone");
+  }
+  assert!(manifest.verify(&fonts)[0].status == VerifyStatus::Unreadable);
+}
+
+#[test]
+fn versions_without_a_number_are_refused() {
+  for version in ["v", "", "Version ."] {
+    let manifest = load(&format!("[[fonts]]\npostscript = \"A\"\nversion = \"{version}\""));
+    assert!(matches!(manifest, Err(ManifestErr::InvalidVersion(_, _, found)) if found == version));
+  }
+  assert!(load("[[fonts]]\npostscript = \"A\"\nversion = \"Version 2.037;hotconv\"").is_ok());
+  assert!(matches!(
+    load("[[fonts]]\npostscript = \"A\"\nrevision = \"2\""),
+    Err(ManifestErr::Parse(..))
+  ));
+}
+
+#[test]
+fn only_matching_copies_are_admitted() {
+  let manifest = load(&format!(
+    "[[fonts]]\npostscript = \"DejaVuSans\"\nsha256 = \"{DEJAVU_SHA256}\"\n\
+     [[fonts]]\npostscript = \"LibertinusSerif-Regular\"\nversion = \"99\""
+  ))
+  .unwrap();
+  let fonts = fonts();
+  let admitted: Vec<_> =
+    (fonts.iter()).filter(|font| manifest.admits(font)).map(|font| &*font.postscript).collect();
+  assert_eq!(admitted, ["DejaVuSans"]);
+
+  // another copy of the same font, but not the file of the manifest
+  let mut copy = fonts.iter().find(|font| font.postscript == "DejaVuSans").unwrap().clone();
+  copy.path = common::fixture("LibertinusSerif-Regular.otf");
+  assert!(!manifest.admits(&copy));
+}
+
+#[test]
+fn strict_mode_lists_only_verified_fonts() {
+  let path = env::temp_dir().join(format!("ffh-manifest-strict-{}.toml", std::process::id()));
+  let manifest = "[[fonts]]\npostscript = \"DejaVuSans\"\n\
+                  [[fonts]]\npostscript = \"DejaVuSerif-Italic\"\nstyle = \"Bold\"";
+  fs::write(&path, manifest).unwrap();
+  let config = Config {
+    manifest: ManifestConfig { path: Some(path.clone()), strict: true },
+    ..Default::default()
+  };
+  let provider = Box::new(DirectoryFontProvider::new(common::fonts_dir()));
+  let database = FontDatabase::new(provider, &config).unwrap();
+  fs::remove_file(&path).unwrap();
+
+  // DejaVuSerif-Italic is in the manifest, but with another style
+  let listed: Vec<_> = database.iter().map(|font| &*font.postscript).collect();
+  assert_eq!(listed, ["DejaVuSans"]);
+}
@@ -0,0 +1,71 @@
+//! Reads names, classification, metrics and tables out of the fonts in `tests/fonts`.
+
+use common::font;
+use ffh::provider::metadata::{self, FontMetadata, MetadataErr};
+
+mod common;
+
+fn inspect(name: &str) -> FontMetadata {
+  metadata::inspect(&font(name), 0).unwrap()
+}
+
+fn name(metadata: &FontMetadata, kind: &str) -> String {
+  let record = metadata.names.iter().find(|name| name.kind() == Some(kind)).unwrap();
+  record.value.clone()
+}
+
+#[test]
+fn truetype_fixture_is_described() {
+  let sans = inspect("DejaVuSans.ttf");
+  assert_eq!(sans.vendor.as_deref(), Some("PfEd"));
+  assert_eq!(sans.panose, Some([2, 11, 6, 3, 3, 8, 4, 2, 2, 4]));
+  assert_eq!(sans.outlines.map(<&str>::from), Some("TrueType"));
+  assert_eq!(
+    sans.tables,
+    [
+      "FFTM", "GDEF", "GPOS", "GSUB", "MATH", "OS/2", "cmap", "cvt ", "fpgm", "gasp", "glyf",
+      "head", "hhea", "hmtx", "kern", "loca", "maxp", "name", "post", "prep"
+    ]
+  );
+
+  let metrics = &sans.metrics;
+  assert_eq!((metrics.units_per_em, metrics.ascender, metrics.descender), (2048, 1901, -483));
+  // the OS/2 table predates sxHeight and sCapHeight
+  assert_eq!((metrics.x_height, metrics.cap_height), (None, None));
+}
+
+#[test]
+fn cff_fixture_is_described() {
+  let serif = inspect("LibertinusSerif-Regular.otf");
+  // the vendor ID is padded with a space
+  assert_eq!(serif.vendor.as_deref(), Some("QUE"));
+  assert_eq!(serif.outlines.map(<&str>::from), Some("CFF"));
+  assert!(
+    serif.tables.iter().any(|table| table == "CFF ") && !serif.tables.contains(&"glyf".into())
+  );
+  assert_eq!(serif.metrics.units_per_em, 1000);
+  assert_eq!((serif.metrics.x_height, serif.metrics.cap_height), (Some(429), Some(658)));
+}
+
+#[test]
+fn names_are_listed_once_by_id() {
+  let sans = inspect("DejaVuSans.ttf");
+  let ids: Vec<_> = sans.names.iter().map(|name| name.id).collect();
+  assert_eq!(ids, [0, 1, 2, 3, 4, 5, 6, 8, 11, 13, 14, 16, 17]);
+  assert!(sans.names.iter().all(|name| name.language == Some("en")));
+  assert_eq!(name(&sans, "family"), "DejaVu Sans");
+  assert_eq!(name(&sans, "subfamily"), "Book");
+  assert_eq!(name(&sans, "postscript"), "DejaVuSans");
+
+  // feature names have no predefined meaning
+  let serif = inspect("LibertinusSerif-Regular.otf");
+  let feature = serif.names.iter().find(|name| name.id == 256).unwrap();
+  assert!(feature.kind().is_none());
+}
+
+#[test]
+fn malformed_fonts_are_refused() {
+  let data = font("DejaVuSans.ttf");
+  assert!(matches!(metadata::inspect(&data[..100], 0), Err(MetadataErr::Malformed(_))));
+  assert!(matches!(metadata::inspect(&data, 1), Err(MetadataErr::Malformed(_))));
+}
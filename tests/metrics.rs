@@ -0,0 +1,26 @@
+//! Checks what `/metrics` reports about the font scan.
+
+use ffh::{
+  config::Config,
+  provider::{SourceConfig, SourceKind},
+  ServerState,
+};
+use std::path::PathBuf;
+
+fn directory(path: PathBuf) -> SourceConfig {
+  SourceConfig { kind: SourceKind::Directory, path: Some(path), label: None, priority: None }
+}
+
+#[test]
+fn sources_that_fail_to_scan_are_counted() {
+  let tests = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+  let config = Config {
+    sources: vec![directory(tests.join("fonts")), directory(tests.join("missing"))],
+    ..Config::default()
+  };
+  let state = ServerState::new(&config).unwrap();
+
+  let metrics = state.metrics.render(&state.database, &state.font_cache);
+  assert!(metrics.contains("ffh_font_source_errors_total 1\n"), "{metrics}");
+  assert!(metrics.contains("ffh_fonts_skipped_total{reason=\"source_unavailable\"} 1\n"));
+}
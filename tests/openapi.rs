@@ -0,0 +1,63 @@
+//! Keeps `openapi.json` in line with the routes, query structs and DTOs.
+
+use actix_web::{http::Method, test, web, App};
+use ffh::{
+  config::Config, middleware, openapi, provider::DirectoryFontProvider, route, ServerState,
+};
+use serde_json::Value;
+use std::{fs, path::PathBuf};
+
+fn committed() -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("openapi.json")
+}
+
+#[actix_web::test]
+async fn committed_document_is_up_to_date() {
+  let generated = openapi::document().to_pretty_json().unwrap();
+  let committed = fs::read_to_string(committed()).unwrap();
+  assert!(
+    generated.trim_end() == committed.trim_end(),
+    "openapi.json is outdated, regenerate it with `cargo run -- openapi > openapi.json`"
+  );
+}
+
+#[actix_web::test]
+async fn every_documented_operation_is_served() {
+  let fonts = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fonts");
+  let provider = Box::new(DirectoryFontProvider::new(fonts));
+  let state = web::Data::new(ServerState::with_provider(provider, &Config::default()).unwrap());
+  let app = test::init_service(
+    App::new()
+      .app_data(state.clone())
+      .wrap(middleware::JsonErrors)
+      .configure(route::figma)
+      .configure(route::admin)
+      .default_service(web::to(route::notfound::handler)),
+  )
+  .await;
+
+  let document: Value = serde_json::to_value(openapi::document()).unwrap();
+  for (path, operations) in document["paths"].as_object().unwrap() {
+    // any value for path parameters, answered with a 404 of another code when unknown
+    let uri = path.replace("{postscript}", "DejaVuSans").replace("{name}", "missing");
+    for method in operations.as_object().unwrap().keys() {
+      let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+      let req = test::TestRequest::default()
+        .method(method.clone())
+        .uri(&uri)
+        .insert_header(("Origin", "https://www.figma.com"));
+      let res = test::call_service(&app, req.to_request()).await;
+      let status = res.status();
+      let body = test::read_body(res).await;
+      let error: Option<Value> = serde_json::from_slice(&body).ok();
+      let code = error.as_ref().and_then(|error| error["code"].as_str());
+      assert_ne!(code, Some("not_found"), "{method} {path} is documented but not served");
+
+      let documented = operations[method.as_str().to_lowercase()]["responses"].as_object().unwrap();
+      assert!(
+        documented.contains_key(status.as_str()),
+        "{method} {path} answered {status}, which is not documented"
+      );
+    }
+  }
+}
@@ -0,0 +1,135 @@
+//! Builds, shortens and disambiguates PostScript names, and reads them out of `tests/fonts`.
+
+use common::fixture;
+use ffh::provider::{
+  embedding::Embedding,
+  postscript::{self, PostscriptCollision},
+  sfnt, FontDescriptor,
+};
+use std::{fs, path::PathBuf};
+use ttf_parser::Tag;
+
+mod common;
+
+fn font(path: &str, index: u32, postscript: &str) -> FontDescriptor {
+  FontDescriptor {
+    path: PathBuf::from(path),
+    index,
+    postscript: postscript.to_owned(),
+    family: "Inter".to_owned(),
+    style: "Regular".to_owned(),
+    source: "fontconfig".to_owned(),
+    ..Default::default()
+  }
+}
+
+fn names(fonts: &[FontDescriptor]) -> Vec<&str> {
+  fonts.iter().map(|font| font.postscript.as_str()).collect()
+}
+
+#[test]
+fn synthesized_names_keep_only_alphanumerics() {
+  assert_eq!(postscript::synthesize("Source Sans 3", "Semi Bold").unwrap(), "SourceSans3-SemiBold");
+  assert_eq!(postscript::synthesize("Noto Sans", "").unwrap(), "NotoSans");
+  assert_eq!(postscript::synthesize("Noto Sans", "  ").unwrap(), "NotoSans");
+  assert_eq!(postscript::synthesize("Ünïcödé", "Bold").unwrap(), "ncd-Bold");
+  assert!(postscript::synthesize("", "Bold").is_none());
+  assert!(postscript::synthesize("-- ", "Bold").is_none());
+}
+
+#[test]
+fn long_names_are_shortened_with_a_hash() {
+  let fits = "A".repeat(63);
+  assert_eq!(postscript::shorten(fits.clone()), fits);
+
+  let long = format!("{}-Regular", "VeryLongFamily".repeat(8));
+  let short = postscript::shorten(long.clone());
+  assert_eq!(short.len(), 63);
+  assert!(long.starts_with(&short[..short.len() - 12]));
+  let suffix = &short[short.len() - 12..];
+  assert!(suffix.starts_with('-') && suffix.ends_with("..."), "{short}");
+  assert!(suffix[1..9].chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_lowercase()));
+
+  // the same name always shortens the same way, and different names differently
+  assert_eq!(postscript::shorten(long.clone()), short);
+  assert_ne!(postscript::shorten(format!("{long}2")), short);
+  assert_eq!(postscript::synthesize(&"VeryLongFamily".repeat(8), "Regular").unwrap(), short);
+}
+
+#[test]
+fn unique_names_are_left_alone() {
+  let mut fonts = vec![font("/a.ttf", 0, "Inter-Regular"), font("/b.ttf", 0, "Inter-Bold")];
+  assert!(postscript::disambiguate(&mut fonts).is_empty());
+  assert_eq!(names(&fonts), ["Inter-Regular", "Inter-Bold"]);
+}
+
+#[test]
+fn colliding_names_get_a_suffix_except_for_the_lowest_path() {
+  let mut fonts = vec![
+    font("/usr/share/fonts/Inter.ttc", 1, "Inter-Regular"),
+    font("/home/me/Inter.ttf", 0, "Inter-Regular"),
+    font("/usr/share/fonts/Inter.ttc", 0, "Inter-Regular"),
+  ];
+  let collisions = postscript::disambiguate(&mut fonts);
+
+  assert_eq!(fonts[1].postscript, "Inter-Regular");
+  for font in [&fonts[0], &fonts[2]] {
+    let (name, suffix) = font.postscript.split_at("Inter-Regular".len());
+    assert_eq!(name, "Inter-Regular");
+    assert!(suffix.len() == 9 && suffix.starts_with('_'), "{}", font.postscript);
+  }
+  // faces of the same file are told apart by their index
+  assert_ne!(fonts[0].postscript, fonts[2].postscript);
+
+  let [PostscriptCollision { postscript, fonts: renamed }] = &collisions[..] else {
+    panic!("expected one collision");
+  };
+  assert_eq!(postscript, "Inter-Regular");
+  assert_eq!(renamed.len(), 3);
+  assert_eq!(renamed[0], (PathBuf::from("/home/me/Inter.ttf"), "Inter-Regular".to_owned()));
+}
+
+#[test]
+fn disambiguated_names_do_not_depend_on_the_scan_order() {
+  let scanned = || {
+    vec![
+      font("/b.ttf", 0, "Inter-Regular"),
+      font("/a.ttf", 0, "Inter-Regular"),
+      font("/c.ttf", 0, "Inter-Regular"),
+    ]
+  };
+  let mut forward = scanned();
+  let mut backward = scanned();
+  backward.reverse();
+  postscript::disambiguate(&mut forward);
+  postscript::disambiguate(&mut backward);
+  backward.reverse();
+
+  assert_eq!(names(&forward), names(&backward));
+  assert_eq!(forward[1].postscript, "Inter-Regular");
+}
+
+#[test]
+fn disambiguated_names_stay_within_63_characters() {
+  let long = "A".repeat(63);
+  let mut fonts = vec![font("/a.ttf", 0, &long), font("/b.ttf", 0, &long)];
+  postscript::disambiguate(&mut fonts);
+
+  assert_eq!(fonts[0].postscript, long);
+  assert_eq!(fonts[1].postscript.len(), 63);
+  assert!(fonts[1].postscript.starts_with(&long[..54]));
+}
+
+#[test]
+fn names_and_tables_are_read_from_the_fixtures() {
+  let path = fixture("DejaVuSans.ttf");
+  let data = fs::read(&path).unwrap();
+  assert_eq!(postscript::read(&data, 0).unwrap(), "DejaVuSans");
+
+  // a single table is read without loading the whole file
+  let os2 = sfnt::read_table(&path, 0, Tag::from_bytes(b"OS/2")).unwrap().unwrap();
+  let face = ttf_parser::Face::parse(&data, 0).unwrap();
+  assert_eq!(os2, face.raw_face().table(Tag::from_bytes(b"OS/2")).unwrap());
+  assert!(Embedding::from_os2(&os2) == Embedding::from_face(&face));
+  assert!(sfnt::read_table(&path, 0, Tag::from_bytes(b"fvar")).unwrap().is_none());
+}
@@ -0,0 +1,113 @@
+//! Renders previews of the fonts in `tests/fonts` and parses the preview parameters.
+
+use common::font;
+use ffh::preview::{self, Color, Preview, PreviewErr, PreviewFormat};
+use tiny_skia::Pixmap;
+
+mod common;
+
+fn preview(text: &str, sizes: &[f32], format: PreviewFormat) -> Preview {
+  Preview {
+    text: text.to_owned(),
+    sizes: sizes.to_vec(),
+    color: Color::parse("336699").unwrap(),
+    axes: vec![],
+    format,
+  }
+}
+
+fn png(text: &str, sizes: &[f32]) -> Pixmap {
+  let data = font("DejaVuSans.ttf");
+  let png = preview::render(&data, 0, &preview(text, sizes, PreviewFormat::Png)).unwrap();
+  Pixmap::decode_png(&png).unwrap()
+}
+
+#[test]
+fn text_is_drawn_in_the_colour() {
+  let pixmap = png("Figma", &[32.0]);
+  assert!(pixmap.width() > 32 && pixmap.height() > 32);
+
+  let opaque: Vec<_> = pixmap.pixels().iter().filter(|pixel| pixel.alpha() == 0xFF).collect();
+  assert!(!opaque.is_empty());
+  for pixel in opaque {
+    assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (0x33, 0x66, 0x99));
+  }
+  // the background is left transparent
+  assert_eq!(pixmap.pixel(0, 0).unwrap().alpha(), 0);
+}
+
+#[test]
+fn every_line_is_drawn_once_per_size() {
+  let one = png("Figma", &[32.0]);
+  let two_lines = png("Figma\nFigma", &[32.0]);
+  let two_sizes = png("Figma", &[32.0, 16.0]);
+  assert_eq!(two_lines.width(), one.width());
+  assert!(two_lines.height() > one.height() * 3 / 2);
+  assert!(two_sizes.height() > one.height() && two_sizes.height() < two_lines.height());
+
+  // an empty preview is still an image
+  let empty = png("", &[32.0]);
+  assert!(empty.pixels().iter().all(|pixel| pixel.alpha() == 0));
+}
+
+#[test]
+fn svg_previews_carry_the_outlines() {
+  let data = font("LibertinusSerif-Regular.otf");
+  let svg = preview::render(&data, 0, &preview("Figma", &[32.0], PreviewFormat::Svg)).unwrap();
+  let svg = String::from_utf8(svg).unwrap();
+  assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>"), "{svg}");
+  assert!(svg.contains(r##"fill="#336699" fill-opacity="1""##));
+  // CFF outlines are cubic
+  assert!(svg.contains(" d=\"M") && svg.contains('C'));
+}
+
+#[test]
+fn unrenderable_previews_are_refused() {
+  let data = font("DejaVuSans.ttf");
+  let render = |preview: &Preview| preview::render(&data, 0, preview);
+
+  let huge = preview(&"Figma".repeat(100), &[512.0], PreviewFormat::Png);
+  assert!(matches!(render(&huge), Err(PreviewErr::TooLarge)));
+
+  let mut weight = preview("Figma", &[32.0], PreviewFormat::Png);
+  weight.axes = vec![(ttf_parser::Tag::from_bytes(b"wght"), 700.0)];
+  assert!(matches!(render(&weight), Err(PreviewErr::UnknownAxis(axis)) if axis == "wght"));
+
+  let malformed = preview::render(&data[..100], 0, &preview("Figma", &[32.0], PreviewFormat::Png));
+  assert!(matches!(malformed, Err(PreviewErr::Malformed)));
+}
+
+#[test]
+fn sizes_are_parsed_within_bounds() {
+  assert_eq!(Preview::parse_sizes("12, 24.5,512").unwrap(), [12.0, 24.5, 512.0]);
+
+  for sizes in ["", "0", "-12", "512.5", "twelve", "12,,24", "NaN", "inf", "12px"] {
+    assert!(matches!(Preview::parse_sizes(sizes), Err(PreviewErr::InvalidSize(_))), "{sizes}");
+  }
+  let many = vec!["12"; 17].join(",");
+  assert!(matches!(Preview::parse_sizes(&many), Err(PreviewErr::TooLarge)));
+}
+
+#[test]
+fn axes_are_parsed_as_tag_and_value() {
+  let axes = Preview::parse_axes("wght:700, wdth:87.5,").unwrap();
+  assert_eq!(axes.len(), 2);
+  assert_eq!((axes[1].0.to_bytes(), axes[1].1), (*b"wdth", 87.5));
+  assert!(Preview::parse_axes("").unwrap().is_empty());
+
+  for axes in ["wght", "wgh:700", "weight:700", "wght:bold", "wght:NaN", "wght:inf", "wgé:1", ":1"]
+  {
+    assert!(matches!(Preview::parse_axes(axes), Err(PreviewErr::InvalidAxis(_))), "{axes}");
+  }
+}
+
+#[test]
+fn colours_are_parsed_as_hex() {
+  let color = Color::parse("#33669980").unwrap();
+  assert_eq!((color.red, color.green, color.blue, color.alpha), (0x33, 0x66, 0x99, 0x80));
+  assert_eq!(Color::parse("FFFFFF").unwrap().alpha, 0xFF);
+
+  for color in ["", "#", "#12345", "1234567", "GGGGGG", "+F+F+F", "-1-1-1", "ÿÿÿ", "##123456"] {
+    assert!(matches!(Color::parse(color), Err(PreviewErr::InvalidColor(_))), "{color}");
+  }
+}
@@ -0,0 +1,178 @@
+//! Switches between the profiles of client projects through the admin routes and checks what the
+//! `/figma/*` routes serve meanwhile.
+
+use actix_web::{http::StatusCode, test, web, App};
+use common::fonts_dir;
+use ffh::{
+  config::Config,
+  middleware,
+  provider::{profile::ProfileConfig, DirectoryFontProvider, FontDescriptor},
+  route, ServerState,
+};
+use serde_json::{json, Value};
+use std::{
+  collections::BTreeMap,
+  env, fs,
+  path::{Path, PathBuf},
+};
+
+mod common;
+
+/// Both listeners in one app, the font list is the same for each of them.
+macro_rules! app {
+  ($state:expr) => {
+    test::init_service(
+      App::new()
+        .app_data($state.clone())
+        .wrap(middleware::JsonErrors)
+        .configure(route::admin)
+        .configure(route::figma)
+        .default_service(web::to(route::notfound::handler)),
+    )
+    .await
+  };
+}
+
+/// A project directory pinning its own copy of DejaVuSans.
+fn project_dir(topic: &str) -> PathBuf {
+  let dir = env::temp_dir().join(format!("ffh-profile-{topic}-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  fs::copy(fonts_dir().join("DejaVuSans.ttf"), dir.join("DejaVuSans.ttf")).unwrap();
+  dir
+}
+
+/// `client` lists its own DejaVuSans and the other DejaVu fonts but the serif ones, `everything`
+/// lists every font.
+fn config(project: PathBuf) -> Config {
+  let client = ProfileConfig {
+    directories: vec![project],
+    allow: vec!["dejavu*".to_owned()],
+    deny: vec!["DejaVu Serif".to_owned()],
+  };
+  let profiles = BTreeMap::from([
+    ("client".to_owned(), client),
+    ("everything".to_owned(), ProfileConfig::default()),
+  ]);
+  Config { profiles, ..Default::default() }
+}
+
+fn state_with(config: &Config) -> web::Data<ServerState> {
+  let provider = Box::new(DirectoryFontProvider::new(fonts_dir()));
+  web::Data::new(ServerState::with_provider(provider, config).unwrap())
+}
+
+fn get(uri: &str) -> test::TestRequest {
+  test::TestRequest::get().uri(uri)
+}
+
+fn font_file_uri(path: &Path) -> String {
+  format!("/figma/font-file?freetype_minimum_api_version=0&file={}", path.to_string_lossy())
+}
+
+/// Paths and PostScript names of the font list, sorted.
+fn listed(body: &Value) -> Vec<(String, String)> {
+  let mut listed = vec![];
+  for (path, fonts) in body["fontFiles"].as_object().unwrap() {
+    for font in fonts.as_array().unwrap() {
+      listed.push((path.clone(), font["postscript"].as_str().unwrap().to_owned()));
+    }
+  }
+  listed.sort();
+  listed
+}
+
+#[actix_web::test]
+async fn patterns_match_postscript_and_family_names() {
+  let font = FontDescriptor {
+    postscript: "DejaVuSerif-Italic".to_owned(),
+    family: "DejaVu Serif".to_owned(),
+    ..Default::default()
+  };
+  let profile = |allow: &[&str], deny: &[&str]| ProfileConfig {
+    allow: allow.iter().map(|pattern| pattern.to_string()).collect(),
+    deny: deny.iter().map(|pattern| pattern.to_string()).collect(),
+    ..Default::default()
+  };
+
+  assert!(profile(&[], &[]).admits(&font));
+  for allow in ["dejavu*", "*italic", "DejaVu*Italic", "dejavu serif", "*"] {
+    assert!(profile(&[allow], &[]).admits(&font), "{allow}");
+  }
+  for allow in ["DejaVu", "Sans*", "*Serif-", "DejaVuSerif-Italic*x"] {
+    assert!(!profile(&[allow], &[]).admits(&font), "{allow}");
+  }
+  // deny wins over allow
+  assert!(!profile(&["*"], &["*Serif*"]).admits(&font));
+}
+
+#[actix_web::test]
+async fn profiles_are_switched_and_cleared() {
+  let project = project_dir("switch");
+  let state = state_with(&config(project.clone()));
+  let app = app!(state);
+
+  let body: Value = test::call_and_read_body_json(&app, get("/profile").to_request()).await;
+  let profiles = json!([{ "name": "client", "fonts": 1 }, { "name": "everything", "fonts": 3 }]);
+  assert_eq!(body, json!({ "active": null, "profiles": profiles }));
+
+  let req = test::TestRequest::put().uri("/profile/client").to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["active"], "client");
+  assert_eq!(state.database.active_profile().as_deref(), Some("client"));
+
+  let req = test::TestRequest::put().uri("/profile/missing").to_request();
+  let res = test::call_service(&app, req).await;
+  assert_eq!(res.status(), StatusCode::NOT_FOUND);
+  let body: Value = test::read_body_json(res).await;
+  assert_eq!(body["code"], "unknown_profile");
+  // the active profile is left as it was
+  assert_eq!(state.database.active_profile().as_deref(), Some("client"));
+
+  // what `ffh profile --clear` sends
+  let req = test::TestRequest::delete().uri("/profile").to_request();
+  let body: Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(body["active"], Value::Null);
+  assert_eq!(body["profiles"], profiles);
+  assert!(state.database.active_profile().is_none());
+
+  fs::remove_dir_all(project).unwrap();
+}
+
+#[actix_web::test]
+async fn fonts_are_served_out_of_the_active_profile() {
+  let project = project_dir("serve");
+  let mut config = config(project.clone());
+  config.profile = Some("client".to_owned());
+  let state = state_with(&config);
+  let app = app!(state);
+  let files_uri = "/figma/font-files?freetype_minimum_api_version=0&isolate=false";
+  let (pinned, installed) = (project.join("DejaVuSans.ttf"), fonts_dir().join("DejaVuSans.ttf"));
+
+  // the profile configured at startup, whose own copy wins over the installed one
+  let body: Value = test::call_and_read_body_json(&app, get(files_uri).to_request()).await;
+  let path = pinned.to_string_lossy().into_owned();
+  assert_eq!(listed(&body), [(path, "DejaVuSans".to_owned())]);
+
+  let res = test::call_service(&app, get(&font_file_uri(&pinned)).to_request()).await;
+  assert_eq!(res.status(), StatusCode::OK);
+  let res = test::call_service(&app, get(&font_file_uri(&installed)).to_request()).await;
+  assert_eq!(res.status(), StatusCode::NOT_FOUND);
+  let uri = "/figma/font-subset?postscript=LibertinusSerif-Regular&text=Figma";
+  let res = test::call_service(&app, get(uri).to_request()).await;
+  assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+  // every font outside of profiles once it is cleared
+  let req = test::TestRequest::delete().uri("/profile").to_request();
+  assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+  let body: Value = test::call_and_read_body_json(&app, get(files_uri).to_request()).await;
+  let listed = listed(&body);
+  assert_eq!(listed.len(), 3);
+  assert!(listed.iter().all(|(path, _)| PathBuf::from(path).starts_with(fonts_dir())));
+
+  let res = test::call_service(&app, get(&font_file_uri(&installed)).to_request()).await;
+  assert_eq!(res.status(), StatusCode::OK);
+  let res = test::call_service(&app, get(&font_file_uri(&pinned)).to_request()).await;
+  assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+  fs::remove_dir_all(project).unwrap();
+}
@@ -0,0 +1,132 @@
+//! Validates and re-serializes the fonts in `tests/fonts`, intact and corrupted.
+
+use common::font;
+use ffh::{
+  config::{Config, ConfigErr},
+  provider::sfnt::{checksum, read_u16, read_u32},
+  sanitize::{self, SanitizeErr},
+};
+
+mod common;
+
+/// Offset of the table record of `tag` in a single-face font.
+fn table_record(data: &[u8], tag: &[u8; 4]) -> usize {
+  let count = read_u16(data, 4).unwrap() as usize;
+  (0..count).map(|i| 12 + i * 16).find(|&record| &data[record..record + 4] == tag).unwrap()
+}
+
+/// Byte range of the table `tag` in a single-face font.
+fn table(data: &[u8], tag: &[u8; 4]) -> std::ops::Range<usize> {
+  let record = table_record(data, tag);
+  let start = read_u32(data, record + 8).unwrap() as usize;
+  start..start + read_u32(data, record + 12).unwrap() as usize
+}
+
+#[test]
+fn intact_fonts_pass_without_warnings() {
+  for name in ["DejaVuSans.ttf", "DejaVuSerif-Italic.ttf"] {
+    assert!(sanitize::check(&font(name)).unwrap().is_empty(), "{name}");
+  }
+}
+
+#[test]
+fn stale_checksums_are_only_warnings() {
+  let mut data = font("DejaVuSans.ttf");
+  let name = table(&data, b"name");
+  data[name.start + 10] ^= 0xFF;
+
+  let warnings = sanitize::check(&data).unwrap();
+  assert!(matches!(warnings[..], [SanitizeErr::Checksum(tag)] if tag.to_bytes() == *b"name"));
+}
+
+#[test]
+fn structural_errors_fail_the_check() {
+  let intact = font("DejaVuSans.ttf");
+
+  let mut data = intact.clone();
+  let record = table_record(&data, b"glyf");
+  data[record + 12..record + 16].copy_from_slice(&u32::MAX.to_be_bytes());
+  assert!(matches!(sanitize::check(&data), Err(SanitizeErr::OutOfBounds(_))));
+
+  let mut data = intact.clone();
+  let (cmap, name) = (table_record(&data, b"cmap"), table_record(&data, b"name"));
+  data.copy_within(cmap..cmap + 4, name);
+  assert!(matches!(sanitize::check(&data), Err(SanitizeErr::DuplicateTable(_))));
+
+  let mut data = intact.clone();
+  let (glyf, loca) = (table_record(&data, b"glyf"), table_record(&data, b"loca"));
+  data.copy_within(glyf + 8..glyf + 12, loca + 8);
+  assert!(matches!(sanitize::check(&data), Err(SanitizeErr::Overlap(..))));
+
+  let mut data = intact;
+  let head = table(&data, b"head");
+  data[head.start + 12..head.start + 16].fill(0);
+  assert!(matches!(sanitize::check(&data), Err(SanitizeErr::InvalidTable(..))));
+}
+
+#[test]
+fn clean_copies_have_fresh_checksums() {
+  let mut data = font("DejaVuSans.ttf");
+  let name = table(&data, b"name");
+  data[name.start + 10] ^= 0xFF;
+
+  let clean = sanitize::clean(&data).unwrap();
+  assert!(sanitize::check(&clean).unwrap().is_empty());
+  // the whole font sums up to the magic number once checkSumAdjustment is filled in
+  assert_eq!(checksum(&clean), 0xB1B0_AFBA);
+  assert_eq!(clean[table(&clean, b"name")], data[name]);
+}
+
+#[test]
+fn corrupted_fonts_are_not_cleaned() {
+  let mut data = font("DejaVuSans.ttf");
+  let record = table_record(&data, b"glyf");
+  data[record + 12..record + 16].copy_from_slice(&u32::MAX.to_be_bytes());
+  assert!(sanitize::clean(&data).is_err());
+}
+
+#[test]
+fn clean_collections_get_a_checksum_adjustment_per_face() {
+  // wrap the font into a collection of one face, moving every table by the collection header
+  let single = font("DejaVuSans.ttf");
+  let mut data = b"ttcf".to_vec();
+  data.extend(0x0001_0000u32.to_be_bytes());
+  data.extend(1u32.to_be_bytes());
+  data.extend(16u32.to_be_bytes());
+  data.extend(&single);
+  let count = read_u16(&single, 4).unwrap() as usize;
+  for i in 0..count {
+    let offset = 16 + 12 + i * 16 + 8;
+    let moved = read_u32(&data, offset).unwrap() + 16;
+    data[offset..offset + 4].copy_from_slice(&moved.to_be_bytes());
+  }
+  assert!(sanitize::check(&data).unwrap().is_empty());
+
+  let clean = sanitize::clean(&data).unwrap();
+  assert!(sanitize::check(&clean).unwrap().is_empty());
+
+  let directory = read_u32(&clean, 12).unwrap() as usize;
+  let count = read_u16(&clean, directory + 4).unwrap() as usize;
+  let mut sum = checksum(&clean[directory..directory + 12 + count * 16]);
+  let mut adjustment = None;
+  for record in (0..count).map(|i| directory + 12 + i * 16) {
+    let start = read_u32(&clean, record + 8).unwrap() as usize;
+    let length = read_u32(&clean, record + 12).unwrap() as usize;
+    sum = sum.wrapping_add(checksum(&clean[start..start + length]));
+    if &clean[record..record + 4] == b"head" {
+      adjustment = read_u32(&clean, start + 8);
+    }
+  }
+  // the face sums up to the magic number, adjustment included
+  assert_eq!(sum, 0xB1B0_AFBA);
+  assert_ne!(adjustment, Some(0));
+}
+
+#[test]
+fn clean_copies_require_the_sanitizer() {
+  let mut config = Config::default();
+  config.sanitize.clean_copy = true;
+  assert!(config.validate().is_ok());
+  config.sanitize.enabled = false;
+  assert!(matches!(config.validate(), Err(ConfigErr::Invalid(_))));
+}
@@ -0,0 +1,91 @@
+//! Subsets the fonts in `tests/fonts`, TrueType and CFF outlines alike.
+
+use common::font;
+use ffh::{provider::coverage::Coverage, subset};
+use std::{fs, path::PathBuf};
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+mod common;
+
+/// DejaVuSans with the double exclamation mark, a composite of two `!`.
+fn composite() -> Vec<u8> {
+  let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("subset");
+  fs::read(path.join("DejaVuSans-Composite.ttf")).unwrap()
+}
+
+struct Ignore;
+
+impl OutlineBuilder for Ignore {
+  fn move_to(&mut self, _: f32, _: f32) {}
+  fn line_to(&mut self, _: f32, _: f32) {}
+  fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {}
+  fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {}
+  fn close(&mut self) {}
+}
+
+fn has_outline(face: &Face<'_>, glyph: GlyphId) -> bool {
+  face.outline_glyph(glyph, &mut Ignore).is_some()
+}
+
+/// Glyph of `c` in the full font, the subset keeps glyph IDs.
+fn glyph(data: &[u8], c: char) -> GlyphId {
+  Face::parse(data, 0).unwrap().glyph_index(c).unwrap()
+}
+
+#[test]
+fn cff_fonts_keep_only_the_requested_outlines() {
+  let data = font("LibertinusSerif-Regular.otf");
+  let subset = subset::subset(&data, 0, &Coverage::parse("Fig", "").unwrap()).unwrap();
+  assert_eq!(subset.content_type, "font/otf");
+  assert!(subset.data.len() < data.len());
+
+  let face = Face::parse(&subset.data, 0).unwrap();
+  assert_eq!(face.glyph_index('F'), Some(glyph(&data, 'F')));
+  assert!(has_outline(&face, glyph(&data, 'F')));
+  assert!(face.glyph_index('z').is_none());
+  assert!(!has_outline(&face, glyph(&data, 'z')));
+  assert_eq!(face.number_of_glyphs(), Face::parse(&data, 0).unwrap().number_of_glyphs());
+}
+
+#[test]
+fn composite_glyphs_keep_their_components() {
+  let data = composite();
+  let subset = subset::subset(&data, 0, &Coverage::parse("\u{203C}", "").unwrap()).unwrap();
+  assert_eq!(subset.content_type, "font/ttf");
+
+  let face = Face::parse(&subset.data, 0).unwrap();
+  assert!(has_outline(&face, glyph(&data, '\u{203C}')));
+  // `!` is not mapped, but its outline is still there for the double exclamation mark
+  assert!(face.glyph_index('!').is_none());
+  assert!(has_outline(&face, glyph(&data, '!')));
+  assert!(!has_outline(&face, glyph(&data, '?')));
+}
+
+#[test]
+fn empty_text_keeps_only_the_notdef_glyph() {
+  for name in ["DejaVuSans.ttf", "LibertinusSerif-Regular.otf"] {
+    let data = font(name);
+    let subset = subset::subset(&data, 0, &Coverage::default()).unwrap();
+
+    let face = Face::parse(&subset.data, 0).unwrap();
+    assert!(face.glyph_index('A').is_none(), "{name}");
+    assert!(!has_outline(&face, glyph(&data, 'A')), "{name}");
+    assert!(has_outline(&face, GlyphId(0)), "{name}");
+  }
+}
+
+#[test]
+fn malformed_cff_operands_are_refused_without_panicking() {
+  let data = font("LibertinusSerif-Regular.otf");
+  let face = Face::parse(&data, 0).unwrap();
+  let cff = face.raw_face().table(ttf_parser::Tag::from_bytes(b"CFF ")).unwrap();
+  let start = cff.as_ptr() as usize - data.as_ptr() as usize;
+
+  // turn every byte of the header and the top DICT into the start of a negative operand
+  let characters = Coverage::parse("Fig", "").unwrap();
+  for offset in start..start + 256 {
+    let mut data = data.clone();
+    data[offset] = 254;
+    let _ = subset::subset(&data, 0, &characters);
+  }
+}
@@ -0,0 +1,123 @@
+//! Counts font requests, writes the counts to disk and reads them back, and orders them for
+//! `ffh stats`.
+
+use ffh::{
+  provider::{DirectoryFontProvider, FontDescriptor, FontProvider},
+  usage::{FontUsage, UsageConfig, UsageErr, UsageReport, UsageStats},
+};
+use std::{
+  env, fs,
+  path::{Path, PathBuf},
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+mod common;
+
+/// A statistics file of its own, tests running in parallel.
+fn usage_path() -> PathBuf {
+  static FILES: AtomicUsize = AtomicUsize::new(0);
+  let file = FILES.fetch_add(1, Ordering::Relaxed);
+  env::temp_dir().join(format!("ffh-usage-{}-{file}", std::process::id())).join("usage.json")
+}
+
+fn config(path: &Path) -> UsageConfig {
+  UsageConfig { path: Some(path.to_owned()), ..Default::default() }
+}
+
+fn usage(requests: u64, last_requested: u64) -> FontUsage {
+  FontUsage { family: "Family".to_owned(), requests, first_requested: 1, last_requested }
+}
+
+#[test]
+fn counts_are_written_and_continued() {
+  let path = usage_path();
+  let stats = UsageStats::new(&config(&path));
+  stats.record("DejaVuSans", "DejaVu Sans");
+  stats.record("DejaVuSans", "DejaVu Sans");
+  stats.record("LibertinusSerif-Regular", "Libertinus Serif");
+  stats.flush().unwrap();
+
+  let report = UsageReport::load(&path).unwrap();
+  let dejavu = &report.fonts["DejaVuSans"];
+  assert_eq!((&*dejavu.family, dejavu.requests), ("DejaVu Sans", 2));
+  assert!(dejavu.first_requested > 0 && dejavu.first_requested <= dejavu.last_requested);
+  assert_eq!(report.fonts["LibertinusSerif-Regular"].requests, 1);
+  // the partial file was renamed over the statistics
+  assert!(!path.with_extension("json.partial").exists());
+
+  // a restarted server counts on
+  let stats = UsageStats::new(&config(&path));
+  stats.record("DejaVuSans", "DejaVu Sans");
+  stats.flush().unwrap();
+  let report = UsageReport::load(&path).unwrap();
+  assert_eq!(report.fonts["DejaVuSans"].requests, 3);
+  assert_eq!(report.fonts["DejaVuSans"].first_requested, dejavu.first_requested);
+
+  fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn nothing_is_written_without_requests() {
+  let path = usage_path();
+  UsageStats::new(&config(&path)).flush().unwrap();
+  assert!(!path.exists());
+  assert!(UsageReport::load(&path).unwrap().fonts.is_empty());
+
+  let disabled = UsageConfig { enabled: false, ..config(&path) };
+  let stats = UsageStats::new(&disabled);
+  stats.record("DejaVuSans", "DejaVu Sans");
+  stats.flush().unwrap();
+  assert!(!path.exists());
+}
+
+#[test]
+fn invalid_statistics_are_started_over() {
+  let path = usage_path();
+  fs::create_dir_all(path.parent().unwrap()).unwrap();
+  fs::write(&path, "{\"fonts\": [").unwrap();
+  assert!(matches!(UsageReport::load(&path), Err(UsageErr::Parse(..))));
+
+  let stats = UsageStats::new(&config(&path));
+  stats.record("DejaVuSans", "DejaVu Sans");
+  stats.flush().unwrap();
+  let report = UsageReport::load(&path).unwrap();
+  assert_eq!(report.fonts.len(), 1);
+  assert_eq!(report.fonts["DejaVuSans"].requests, 1);
+
+  fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn most_requested_fonts_come_first() {
+  let report = UsageReport {
+    fonts: [
+      ("Once", usage(1, 50)),
+      ("Often", usage(9, 10)),
+      ("TwiceLately", usage(2, 40)),
+      ("TwiceB", usage(2, 20)),
+      ("TwiceA", usage(2, 20)),
+    ]
+    .into_iter()
+    .map(|(postscript, usage)| (postscript.to_owned(), usage))
+    .collect(),
+  };
+  let order: Vec<_> = report.by_use().into_iter().map(|(postscript, _)| &**postscript).collect();
+  // then the most recently requested, then by name
+  assert_eq!(order, ["Often", "TwiceLately", "TwiceA", "TwiceB", "Once"]);
+}
+
+#[test]
+fn never_requested_fonts_are_listed_once() {
+  let mut fonts = DirectoryFontProvider::new(common::fonts_dir()).get_all_fonts().unwrap().fonts;
+  // another copy of a font is not listed twice
+  let serif = fonts.iter().find(|font| font.postscript == "DejaVuSerif-Italic").unwrap();
+  let copy = FontDescriptor { path: PathBuf::from("/copy.ttf"), ..serif.clone() };
+  fonts.push(copy);
+
+  let report =
+    UsageReport { fonts: [("DejaVuSans".to_owned(), usage(1, 1))].into_iter().collect() };
+  assert_eq!(
+    report.unused(&fonts),
+    [("DejaVuSerif-Italic", "DejaVu Serif"), ("LibertinusSerif-Regular", "Libertinus Serif")]
+  );
+}